@@ -1,67 +1,795 @@
 pub mod apache {
-    use std::path::Path;
-    use std::process::exit;
+    use std::{env, fs};
+    use std::collections::HashMap;
+    use std::path::{Path, PathBuf};
 
     use regex::Regex;
 
-    use crate::domain::domain::VirtualHost;
-    use crate::ERROR_EXIT_CODE;
-    use crate::webserver::webserver::{get_vhost_config_file_list, get_virtual_hosts_from_file};
+    use crate::domain::domain::{ListenEndpoint, Provenance, Redirect, RedirectScope, TlsConfig, VirtualHost};
+    use crate::error::error::DiscoveryError;
+    use crate::source::source::{SourceSettings, WebServerSource};
+    use crate::webserver::webserver::{apply_tls_config, ConfigScanOptions, Directive, expand_include_pattern,
+                                      get_annotations, get_port, get_server_names, get_vhosts_from_dirs, has_wildcards,
+                                      get_vhost_redirect, get_vhosts_config_root, is_match_all_pattern, is_redirect_url,
+                                      ParseError, REDIRECT_CODES, resolve_certificate_files};
 
-    pub fn get_apache_vhosts(vhosts_path: &Path) -> Vec<VirtualHost> {
+    const VIRTUAL_HOST_SECTION: &str = "VirtualHost";
+    const SERVER_NAME_DIRECTIVE: &str = "ServerName";
+    const SERVER_ALIAS_DIRECTIVE: &str = "ServerAlias";
+    const REDIRECT_DIRECTIVE: &str = "Redirect";
+    const REDIRECT_PERMANENT_DIRECTIVE: &str = "RedirectPermanent";
+    const REDIRECT_TEMP_DIRECTIVE: &str = "RedirectTemp";
+    const REDIRECT_MATCH_DIRECTIVE: &str = "RedirectMatch";
+    const REWRITE_ENGINE_DIRECTIVE: &str = "RewriteEngine";
+    const REWRITE_COND_DIRECTIVE: &str = "RewriteCond";
+    const REWRITE_RULE_DIRECTIVE: &str = "RewriteRule";
+    const LOCATION_SECTION: &str = "Location";
+
+    /// `RewriteCond` test strings which don't restrict redirect of the whole virtual host,
+    /// e.g. `RewriteCond %{SERVER_NAME} =example.com` added by certbot.
+    const NON_RESTRICTIVE_REWRITE_CONDITIONS: [&str; 4] =
+        ["%{HTTPS}", "%{SERVER_NAME}", "%{HTTP_HOST}", "%{REQUEST_SCHEME}"];
+    const SERVER_ROOT_DIRECTIVE: &str = "ServerRoot";
+    const DEFINE_DIRECTIVE: &str = "Define";
+    const IF_DEFINE_SECTION: &str = "IfDefine";
+    const IF_MODULE_SECTION: &str = "IfModule";
+    const INCLUDE_DIRECTIVE: &str = "Include";
+    const INCLUDE_OPTIONAL_DIRECTIVE: &str = "IncludeOptional";
+    const SSL_ENGINE_DIRECTIVE: &str = "SSLEngine";
+    const SSL_CERTIFICATE_FILE_DIRECTIVE: &str = "SSLCertificateFile";
+
+    pub const APACHE_SOURCE_NAME: &str = "apache";
+
+    pub const APACHE_VHOSTS_PATH: &str = "/etc/apache2/sites-enabled";
+    pub const APACHE_CONFIG_PATH: &str = "/etc/apache2/apache2.conf";
+
+    /// Apache httpd source for discovery registry.
+    pub struct ApacheSource;
+
+    impl WebServerSource for ApacheSource {
+        fn name(&self) -> &'static str {
+            APACHE_SOURCE_NAME
+        }
+
+        fn default_vhosts_path(&self) -> &'static str {
+            APACHE_VHOSTS_PATH
+        }
+
+        fn default_config_path(&self) -> &'static str {
+            APACHE_CONFIG_PATH
+        }
+
+        fn vhosts_path_short_argument(&self) -> Option<&'static str> {
+            Some("a")
+        }
+
+        fn discover(&self, settings: &SourceSettings,
+                    errors: &mut Vec<DiscoveryError>) -> Result<Vec<VirtualHost>, DiscoveryError> {
+            match &settings.config_path {
+                Some(config_path) => get_apache_vhosts_from_main_config(config_path, settings.include_redirects,
+                                                                         settings.include_unnamed, errors),
+                None => Ok(get_apache_vhosts(&settings.vhosts_paths, &settings.scan_options,
+                                             settings.include_redirects, settings.include_unnamed, errors))
+            }
+        }
+    }
+
+    /// Returns virtual hosts from config files of directories.
+    /// Unreadable directories and problems with single files are added to `errors`.
+    pub fn get_apache_vhosts(vhosts_paths: &[PathBuf], scan_options: &ConfigScanOptions, include_redirects: bool,
+                             include_unnamed: bool, errors: &mut Vec<DiscoveryError>) -> Vec<VirtualHost> {
         debug!("get virtual hosts from apache configs");
-        debug!("configs path '{}'", vhosts_path.display());
 
+        get_vhosts_from_dirs(vhosts_paths, scan_options, errors, |vhost_file, errors| {
+            let mut vhosts = get_apache_vhosts_from_file(vhost_file, include_redirects, include_unnamed, errors)?;
+            resolve_certificate_files(&mut vhosts, &get_vhosts_config_root(vhosts_paths, vhost_file));
+            Ok(vhosts)
+        })
+    }
+
+    /// Discovers virtual hosts starting from main apache config (`httpd.conf`, `apache2.conf`)
+    /// and following `Include` and `IncludeOptional` directives. Problems with included files
+    /// are added to `errors`, unreadable main config is returned as error.
+    pub fn get_apache_vhosts_from_main_config(config_path: &Path, include_redirects: bool, include_unnamed: bool,
+                                              errors: &mut Vec<DiscoveryError>) -> Result<Vec<VirtualHost>, DiscoveryError> {
+        debug!("get virtual hosts from apache main config '{}'", config_path.display());
+
+        let directives = load_apache_config(config_path, errors)?;
+
+        let mut vhosts = get_apache_vhosts_from_directives(&directives, include_redirects, include_unnamed, errors);
+        resolve_certificate_files(&mut vhosts, &get_server_root(&directives, config_path));
+        vhosts.iter().for_each(|vhost| debug!("{}", vhost.to_string()));
+
+        Ok(vhosts)
+    }
+
+    pub fn get_apache_vhosts_from_file(vhost_file: &Path, include_redirects: bool, include_unnamed: bool,
+                                       errors: &mut Vec<DiscoveryError>) -> Result<Vec<VirtualHost>, DiscoveryError> {
+        info!("get virtual hosts from file '{}'", vhost_file.display());
+
+        let directives = read_apache_config(vhost_file)?;
+
+        Ok(get_apache_vhosts_from_directives(&directives, include_redirects, include_unnamed, errors))
+    }
+
+    /// Walks section tree and returns virtual hosts for every `<VirtualHost>` section.
+    /// `<IfModule>` sections are treated as enabled, negated `<IfModule !module>` ones are skipped.
+    /// `<IfDefine>` sections are evaluated while main config is loaded, here they are treated as enabled
+    /// because `Define` values are unknown for separate files.
+    pub fn get_apache_vhosts_from_directives(directives: &[Directive], include_redirects: bool, include_unnamed: bool,
+                                             errors: &mut Vec<DiscoveryError>) -> Vec<VirtualHost> {
         let mut vhosts: Vec<VirtualHost> = Vec::new();
 
-        if vhosts_path.is_dir() && vhosts_path.exists() {
-            match get_vhost_config_file_list(vhosts_path) {
-                Ok(vhost_files) => {
-                    for vhost_file in vhost_files {
-                        let vhost_file_path = vhost_file.as_path();
-
-                        debug!("analyze vhost file '{}'", vhost_file_path.display());
-
-                        let section_start_regex = get_apache_vhost_port_regex();
-                        let redirect_to_url_regex = get_apache_redirect_to_http_regex();
-                        let port_search_regex = get_apache_vhost_port_regex();
-                        let domain_search_regex = get_domain_search_regex_for_apache_vhost();
-
-                        if let Ok(apache_vhosts) = get_virtual_hosts_from_file(
-                            vhost_file_path,
-                            section_start_regex,
-                            redirect_to_url_regex,
-                            port_search_regex,
-                            domain_search_regex,
-                        ) {
-                            for apache_vhost in apache_vhosts {
-                                debug!("{}", apache_vhost.to_string());
-                                vhosts.push(apache_vhost);
-                            }
-
-                        } else { error!("unable to get virtual hosts from file") }
+        for directive in directives {
+            if directive.block.is_none() || !is_section_enabled(directive, None) {
+                continue;
+            }
+
+            if directive.name.eq_ignore_ascii_case(VIRTUAL_HOST_SECTION) {
+                if let Some(vhost) = get_vhost_from_section(directive, include_redirects, include_unnamed, errors) {
+                    vhosts.push(vhost);
+                }
+
+            } else {
+                vhosts.append(&mut get_apache_vhosts_from_directives(directive.children(), include_redirects,
+                                                                      include_unnamed, errors));
+            }
+        }
+
+        vhosts
+    }
+
+    fn get_vhost_from_section(section: &Directive, include_redirects: bool, include_unnamed: bool,
+                              errors: &mut Vec<DiscoveryError>) -> Option<VirtualHost> {
+        trace!("virtual host section at {}:{}", section.file.display(), section.line);
+
+        let redirect = get_vhost_redirect(get_redirects(section, RedirectScope::VirtualHost));
+
+        if redirect.is_some() && !include_redirects {
+            return None
+        }
+
+        let mut listens: Vec<ListenEndpoint> = section.args.iter()
+            .filter_map(|address| get_listen_endpoint(address, section, errors)).collect();
+
+        if listens.is_empty() {
+            return None
+        }
+
+        let tls = get_tls_config(section);
+        apply_tls_config(&mut listens, &tls);
+
+        let server_name = section.find_all_ignore_case(SERVER_NAME_DIRECTIVE).into_iter().last();
+
+        if server_name.is_none() && !include_unnamed {
+            return None
+        }
+
+        let alias_values = section.find_all_ignore_case(SERVER_ALIAS_DIRECTIVE).into_iter()
+                                  .flat_map(|server_alias| server_alias.args.iter());
+
+        let raw_server_names: Vec<String> = server_name.iter().flat_map(|server_name| server_name.args.iter())
+            .chain(alias_values).cloned().collect();
+
+        let server_name_values: Vec<String> = raw_server_names.iter()
+            .map(|value| get_host_from_server_name(value)).collect();
+
+        // aliases without `ServerName` don't name virtual host
+        let mut names = match server_name {
+            Some(_) => get_server_names(server_name_values),
+            None => Vec::new()
+        };
+
+        if names.is_empty() && !include_unnamed {
+            if listens.iter().any(|listen| listen.default_server) {
+                debug!("catch-all _default_ virtual host skipped at {}:{}", section.file.display(), section.line);
+            }
+            return None
+        }
+
+        // unnamed virtual hosts are kept with empty domain for config checks
+        let domain = if names.is_empty() { String::new() } else { names.remove(0) };
+
+        debug!("domain found {}", domain);
+
+        if !names.is_empty() {
+            debug!("aliases found {}", names.join(" "));
+        }
+
+        let provenance = Provenance {
+            server: APACHE_SOURCE_NAME.to_string(),
+            file: section.file.to_path_buf(),
+            line: section.line,
+            listen: section.args.clone(),
+            server_names: raw_server_names
+        };
+
+        let annotations = get_annotations(&section.file, section.line);
+
+        Some(VirtualHost {
+            domain, aliases: names, listens, tls, wildcard: None, redirect, provenance: Some(provenance), annotations
+        })
+    }
+
+    fn get_tls_config(section: &Directive) -> TlsConfig {
+        let engine = section.find_all_ignore_case(SSL_ENGINE_DIRECTIVE).into_iter().last()
+            .and_then(|ssl_engine| ssl_engine.args.first())
+            .map(|value| value.eq_ignore_ascii_case("on"));
+
+        let certificate_files = section.find_all_ignore_case(SSL_CERTIFICATE_FILE_DIRECTIVE).into_iter()
+            .filter_map(|certificate| certificate.args.first().cloned()).collect();
+
+        TlsConfig { engine, certificate_files }
+    }
+
+    /// `ServerName` accepts `[scheme://]domain-name[:port]`.
+    fn get_host_from_server_name(value: &str) -> String {
+        let without_scheme = match value.find("://") {
+            Some(index) => &value[index + 3..],
+            None => value
+        };
+
+        match without_scheme.rsplit_once(':') {
+            Some((host, port)) if port.chars().all(|ch| ch.is_ascii_digit()) => host.to_string(),
+            _ => without_scheme.to_string()
+        }
+    }
+
+    /// Parses virtual host address: `*:80`, `192.168.1.1:8080`, `[::]:443`, `_default_:443`.
+    fn get_listen_endpoint(address: &str, section: &Directive,
+                           errors: &mut Vec<DiscoveryError>) -> Option<ListenEndpoint> {
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && !host.ends_with(':') => (host, port),
+            _ => {
+                debug!("unsupported virtual host address '{}'", address);
+                return None
+            }
+        };
+
+        let port = match get_port(port) {
+            Some(port) => port,
+            None => {
+                errors.push(DiscoveryError::InvalidPort {
+                    file: section.file.to_path_buf(), line: section.line, value: port.to_string()
+                });
+                return None
+            }
+        };
+
+        let mut endpoint = ListenEndpoint::new(port);
+
+        match host.strip_prefix('[').and_then(|value| value.strip_suffix(']')) {
+            Some(ipv6_address) => {
+                endpoint.address = Some(ipv6_address.to_string());
+                endpoint.ipv6 = true;
+            }
+            None => {
+                if host != "*" && host != "_default_" {
+                    endpoint.address = Some(host.to_string());
+                }
+                endpoint.default_server = host == "_default_";
+            }
+        }
+
+        debug!("listen found {}", endpoint);
+
+        Some(endpoint)
+    }
+
+    /// Returns redirects of section. Redirects of `/` path, match-all patterns and `<Location "/">`
+    /// apply to the whole virtual host, other ones apply to part of requests.
+    fn get_redirects(section: &Directive, scope: RedirectScope) -> Vec<Redirect> {
+        let mut redirects: Vec<Redirect> = Vec::new();
+
+        let rewrite_enabled = section.find_all_ignore_case(REWRITE_ENGINE_DIRECTIVE).into_iter().last()
+            .and_then(|rewrite_engine| rewrite_engine.args.first())
+            .map(|value| value.eq_ignore_ascii_case("on")) == Some(true);
+
+        let mut rewrite_conditions: Vec<&Directive> = Vec::new();
+
+        for directive in section.children() {
+            if directive.block.is_some() {
+                let root_location = directive.name.eq_ignore_ascii_case(LOCATION_SECTION) &&
+                                    directive.args == ["/"];
+
+                let nested_scope = if scope == RedirectScope::VirtualHost && root_location {
+                    RedirectScope::VirtualHost
+                } else {
+                    RedirectScope::Location(format!("<{} {}>", directive.name, directive.args.join(" ")))
+                };
+
+                redirects.append(&mut get_redirects(directive, nested_scope));
+
+            } else if directive.name.eq_ignore_ascii_case(REWRITE_COND_DIRECTIVE) {
+                rewrite_conditions.push(directive);
+
+            } else if directive.name.eq_ignore_ascii_case(REWRITE_RULE_DIRECTIVE) {
+                if rewrite_enabled {
+                    redirects.extend(get_rewrite_redirect(directive, &rewrite_conditions, &scope));
+                }
+                rewrite_conditions.clear();
+
+            } else {
+                redirects.extend(get_redirect(directive, &scope));
+            }
+        }
+
+        redirects
+    }
+
+    /// Recognizes `Redirect [status] URL-path URL`, `RedirectPermanent`, `RedirectTemp`
+    /// and `RedirectMatch [status] regex URL`. URL-path may be omitted inside `<Location>`.
+    fn get_redirect(directive: &Directive, scope: &RedirectScope) -> Option<Redirect> {
+        let name = directive.name.as_str();
+
+        let (code, args) = if name.eq_ignore_ascii_case(REDIRECT_PERMANENT_DIRECTIVE) {
+            (301, &directive.args[..])
+
+        } else if name.eq_ignore_ascii_case(REDIRECT_TEMP_DIRECTIVE) {
+            (302, &directive.args[..])
+
+        } else if name.eq_ignore_ascii_case(REDIRECT_DIRECTIVE) || name.eq_ignore_ascii_case(REDIRECT_MATCH_DIRECTIVE) {
+            match directive.args.first().and_then(|value| get_redirect_code(value)) {
+                Some(code) => (code, &directive.args[1..]),
+                None => (302, &directive.args[..])
+            }
+
+        } else {
+            return None
+        };
+
+        let (path, target) = match args {
+            [path, target] => (Some(path), target),
+            [target] => (None, target),
+            _ => return None
+        };
+
+        if !is_redirect_url(target) {
+            return None
+        }
+
+        let whole_scope = match path {
+            Some(path) if name.eq_ignore_ascii_case(REDIRECT_MATCH_DIRECTIVE) => is_match_all_pattern(path),
+            Some(path) => path == "/",
+            None => true
+        };
+
+        let scope = match path {
+            Some(path) if !whole_scope => RedirectScope::Location(format!("{} {}", directive.name, path)),
+            _ => scope.clone()
+        };
+
+        Some(Redirect { code, target: target.to_string(), scope })
+    }
+
+    /// Recognizes `RewriteRule pattern substitution [R=301]`, absolute URL substitution is
+    /// an external redirect even without `R` flag. Conditions except ones for scheme or host
+    /// make redirect partial.
+    fn get_rewrite_redirect(rule: &Directive, conditions: &Vec<&Directive>, scope: &RedirectScope) -> Option<Redirect> {
+        let pattern = rule.args.first()?;
+        let substitution = rule.args.get(1)?;
+
+        let flags: Vec<&str> = rule.args.get(2)
+            .map(|value| value.trim_start_matches('[').trim_end_matches(']').split(',').collect())
+            .unwrap_or_default();
+
+        let redirect_flag = flags.iter().find_map(|flag| {
+            let (flag_name, flag_value) = match flag.split_once('=') {
+                Some((flag_name, flag_value)) => (flag_name, Some(flag_value)),
+                None => (*flag, None)
+            };
+
+            if flag_name.eq_ignore_ascii_case("R") || flag_name.eq_ignore_ascii_case("redirect") {
+                Some(flag_value)
+            } else {
+                None
+            }
+        });
+
+        let code = match redirect_flag {
+            Some(Some(value)) => get_redirect_code(value)?,
+            Some(None) => 302,
+            None if is_redirect_url(substitution) && !flags.iter().any(|flag| flag.eq_ignore_ascii_case("P")) => 302,
+            None => return None
+        };
+
+        let restricted = conditions.iter().any(|condition| match condition.args.first() {
+            Some(test_string) => !NON_RESTRICTIVE_REWRITE_CONDITIONS.iter()
+                .any(|variable| test_string.eq_ignore_ascii_case(variable)),
+            None => false
+        });
+
+        let scope = if is_match_all_pattern(pattern) && !restricted {
+            scope.clone()
+        } else {
+            RedirectScope::Location(format!("{} {}", rule.name, pattern))
+        };
+
+        Some(Redirect { code, target: substitution.to_string(), scope })
+    }
+
+    /// Status of `Redirect` directives or `R` flag: `permanent`, `temp`, `seeother` or 3xx code.
+    fn get_redirect_code(value: &str) -> Option<i32> {
+        match value.to_lowercase().as_str() {
+            "permanent" => Some(301),
+            "temp" => Some(302),
+            "seeother" => Some(303),
+            code => code.parse().ok().filter(|code| REDIRECT_CODES.contains(code))
+        }
+    }
+
+    /// Reads apache config file and replaces `Include` and `IncludeOptional` directives
+    /// with directives from included files. Relative include paths are resolved against `ServerRoot`,
+    /// main config directory is used until `ServerRoot` is defined.
+    /// Included files which can't be read or parsed are added to `errors` and skipped.
+    pub fn load_apache_config(config_path: &Path,
+                              errors: &mut Vec<DiscoveryError>) -> Result<Vec<Directive>, DiscoveryError> {
+        let mut context = IncludeContext {
+            server_root: config_path.parent().unwrap_or(Path::new("")).to_path_buf(),
+            defines: HashMap::new(),
+            include_stack: Vec::new(),
+            errors: Vec::new()
+        };
+
+        let directives = load_apache_config_file(config_path, &mut context);
+        errors.append(&mut context.errors);
+
+        directives
+    }
+
+    /// The last `ServerRoot` of loaded config, main config directory if it isn't defined.
+    fn get_server_root(directives: &[Directive], config_path: &Path) -> PathBuf {
+        directives.iter().rev()
+            .filter(|directive| directive.block.is_none() && directive.name.eq_ignore_ascii_case(SERVER_ROOT_DIRECTIVE))
+            .find_map(|directive| directive.args.first())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| config_path.parent().unwrap_or(Path::new("")).to_path_buf())
+    }
+
+    struct IncludeContext {
+        server_root: PathBuf,
+        defines: HashMap<String, String>,
+        include_stack: Vec<PathBuf>,
+        errors: Vec<DiscoveryError>
+    }
+
+    fn load_apache_config_file(config_path: &Path, context: &mut IncludeContext) -> Result<Vec<Directive>, DiscoveryError> {
+        info!("read apache config '{}'", config_path.display());
+
+        let directives = read_apache_config(config_path)?;
+
+        let canonical_path = fs::canonicalize(config_path)
+            .map_err(|e| DiscoveryError::from_io_error(config_path, &e))?;
+
+        context.include_stack.push(canonical_path);
+        let resolved_directives = resolve_includes(directives, context);
+        context.include_stack.pop();
+
+        Ok(resolved_directives)
+    }
+
+    fn read_apache_config(config_path: &Path) -> Result<Vec<Directive>, DiscoveryError> {
+        let content = fs::read_to_string(config_path)
+            .map_err(|e| DiscoveryError::from_io_error(config_path, &e))?;
+
+        Ok(parse_apache_config(&content, config_path)?)
+    }
+
+    fn resolve_includes(directives: Vec<Directive>, context: &mut IncludeContext) -> Vec<Directive> {
+        let mut results: Vec<Directive> = Vec::new();
+
+        for mut directive in directives {
+            if directive.block.is_none() {
+                if is_include_directive(&directive) {
+                    results.append(&mut get_included_directives(&directive, context));
+                    continue;
+                }
+
+                if directive.name.eq_ignore_ascii_case(SERVER_ROOT_DIRECTIVE) {
+                    if let Some(server_root) = directive.args.first() {
+                        debug!("server root '{}'", server_root);
+                        context.server_root = PathBuf::from(server_root);
+                    }
+                }
+
+                if directive.name.eq_ignore_ascii_case(DEFINE_DIRECTIVE) {
+                    if let Some(name) = directive.args.first() {
+                        let value = directive.args.get(1).cloned().unwrap_or_default();
+                        context.defines.insert(name.to_string(), value);
+                    }
+                }
+            }
+
+            if !is_section_enabled(&directive, Some(&context.defines)) {
+                debug!("skip disabled section <{} {}> at {}:{}", directive.name, directive.args.join(" "),
+                       directive.file.display(), directive.line);
+                continue;
+            }
+
+            if let Some(block) = directive.block.take() {
+                directive.block = Some(resolve_includes(block, context));
+            }
+
+            results.push(directive);
+        }
+
+        results
+    }
+
+    /// Evaluates conditional sections. `<IfDefine [!]NAME>` is checked against `defines` if they are known,
+    /// parameters from httpd command line (`-D NAME`) aren't available, so they are treated as undefined.
+    /// Loaded modules are unknown as well: `<IfModule module>` is enabled, `<IfModule !module>` isn't.
+    fn is_section_enabled(section: &Directive, defines: Option<&HashMap<String, String>>) -> bool {
+        let argument = section.args.first().map(|argument| argument.as_str()).unwrap_or_default();
+
+        let (negated, name) = match argument.strip_prefix('!') {
+            Some(name) => (true, name.trim()),
+            None => (false, argument)
+        };
+
+        if section.name.eq_ignore_ascii_case(IF_DEFINE_SECTION) {
+            match defines {
+                Some(defines) => defines.contains_key(name) != negated,
+                None => true
+            }
+
+        } else if section.name.eq_ignore_ascii_case(IF_MODULE_SECTION) {
+            !negated
+
+        } else {
+            true
+        }
+    }
+
+    fn is_include_directive(directive: &Directive) -> bool {
+        directive.name.eq_ignore_ascii_case(INCLUDE_DIRECTIVE) ||
+        directive.name.eq_ignore_ascii_case(INCLUDE_OPTIONAL_DIRECTIVE)
+    }
+
+    fn get_included_directives(include: &Directive, context: &mut IncludeContext) -> Vec<Directive> {
+        let mut directives: Vec<Directive> = Vec::new();
+
+        let optional = include.name.eq_ignore_ascii_case(INCLUDE_OPTIONAL_DIRECTIVE);
+
+        let pattern = match include.args.first() {
+            Some(pattern) => substitute_variables(pattern, &context.defines),
+            None => {
+                context.errors.push(DiscoveryError::ParseError {
+                    file: include.file.to_path_buf(), line: include.line, message: "include without file".to_string()
+                });
+                return directives
+            }
+        };
+
+        let files = get_include_files(&context.server_root.join(&pattern));
+
+        if files.is_empty() && has_wildcards(&pattern) {
+            if optional {
+                debug!("no files match include '{}' at {}:{}", pattern, include.file.display(), include.line);
+            } else {
+                context.errors.push(DiscoveryError::ParseError {
+                    file: include.file.to_path_buf(), line: include.line,
+                    message: format!("no files match include '{}'", pattern)
+                });
+            }
+        }
+
+        for file in files {
+            if let Ok(canonical_path) = fs::canonicalize(&file) {
+                if context.include_stack.contains(&canonical_path) {
+                    error!("include cycle detected, file '{}' is already included, skip include at {}:{}",
+                           file.display(), include.file.display(), include.line);
+                    continue;
+                }
+            }
+
+            match load_apache_config_file(&file, context) {
+                Ok(mut included_directives) => directives.append(&mut included_directives),
+                Err(e) => {
+                    if optional && !file.exists() {
+                        debug!("optional include '{}' not found at {}:{}",
+                               file.display(), include.file.display(), include.line);
+                    } else {
+                        debug!("unable to include '{}' at {}:{}: {}",
+                               file.display(), include.file.display(), include.line, e);
+                        context.errors.push(e);
+                    }
+                }
+            }
+        }
+
+        directives
+    }
+
+    /// Include pointing to directory means all files from directory and its subdirectories.
+    fn get_include_files(pattern: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = Vec::new();
+
+        for path in expand_include_pattern(pattern) {
+            if path.is_dir() {
+                files.append(&mut get_files_from_dir(&path));
+            } else {
+                files.push(path);
+            }
+        }
+
+        files
+    }
+
+    fn get_files_from_dir(dir: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = Vec::new();
+
+        if let Ok(entries) = fs::read_dir(dir) {
+            let mut paths: Vec<PathBuf> = entries.filter_map(|entry| entry.ok())
+                                                 .map(|entry| entry.path()).collect();
+            paths.sort();
+
+            for path in paths {
+                if path.is_dir() {
+                    files.append(&mut get_files_from_dir(&path));
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+
+        files
+    }
+
+    /// Replaces `${NAME}` with value from `Define` directives or environment variables.
+    fn substitute_variables(value: &str, defines: &HashMap<String, String>) -> String {
+        let variable_regex = Regex::new("\\$\\{([A-Za-z0-9_]+)\\}").unwrap();
+
+        variable_regex.replace_all(value, |captures: &regex::Captures| {
+            let name = &captures[1];
+
+            match defines.get(name) {
+                Some(defined_value) => defined_value.to_string(),
+                None => env::var(name).unwrap_or_else(|_| captures[0].to_string())
+            }
+        }).to_string()
+    }
+
+    /// Parses apache configuration into directive tree, sections (`<VirtualHost>`, `<IfModule>`, etc.)
+    /// become block directives.
+    pub fn parse_apache_config(content: &str, file: &Path) -> Result<Vec<Directive>, ParseError> {
+        let mut stack: Vec<(Directive, Vec<Directive>)> = Vec::new();
+        let mut current: Vec<Directive> = Vec::new();
+
+        for (line, row) in get_logical_lines(content) {
+            let row = row.trim();
+
+            if row.is_empty() || row.starts_with('#') {
+                continue;
+            }
+
+            if let Some(section_end) = row.strip_prefix("</") {
+                let name = section_end.trim_end_matches('>').trim();
+
+                match stack.pop() {
+                    Some((mut section, parent)) if section.name.eq_ignore_ascii_case(name) => {
+                        section.block = Some(current);
+                        current = parent;
+                        current.push(section);
                     }
+                    Some((section, _)) => {
+                        return Err(get_parse_error(file, line,
+                                                   &format!("expected </{}> but saw </{}>", section.name, name)))
+                    }
+                    None => return Err(get_parse_error(file, line, &format!("unexpected </{}>", name)))
+                }
+
+            } else if let Some(section_start) = row.strip_prefix('<') {
+                let section_value = match section_start.strip_suffix('>') {
+                    Some(value) => value,
+                    None => return Err(get_parse_error(file, line, "section directive is missing closing '>'"))
+                };
+
+                let directive = get_directive(section_value, file, line)?;
+                stack.push((directive, current));
+                current = Vec::new();
+
+            } else {
+                current.push(get_directive(row, file, line)?);
+            }
+        }
+
+        if let Some((section, _)) = stack.last() {
+            return Err(get_parse_error(file, section.line,
+                                       &format!("unexpected end of file, section <{}> is not closed", section.name)))
+        }
+
+        Ok(current)
+    }
+
+    /// Joins lines ending with backslash, returns line number of the first physical line.
+    fn get_logical_lines(content: &str) -> Vec<(usize, String)> {
+        let mut lines: Vec<(usize, String)> = Vec::new();
+        let mut buffer: Option<(usize, String)> = None;
+
+        for (index, row) in content.lines().enumerate() {
+            let (start_line, mut value) = buffer.take().unwrap_or((index + 1, String::new()));
+
+            match row.strip_suffix('\\') {
+                Some(continued_row) => {
+                    value.push_str(continued_row);
+                    buffer = Some((start_line, value));
                 }
-                Err(_) => {
-                    error!("unable to get vhost file list from '{}', \
-                        possible reason: lack of permissions", vhosts_path.display());
-                    exit(ERROR_EXIT_CODE)
+                None => {
+                    value.push_str(row);
+                    lines.push((start_line, value));
                 }
             }
         }
 
-        return vhosts;
+        if let Some(last_line) = buffer {
+            lines.push(last_line);
+        }
+
+        lines
     }
 
-    fn get_domain_search_regex_for_apache_vhost() -> Regex {
-        return Regex::new("(?:^|^[^#]+)ServerName[\\s\t]+([a-zA-Z0-9.-]+)$").unwrap();
+    fn get_directive(row: &str, file: &Path, line: usize) -> Result<Directive, ParseError> {
+        let mut words = split_words(row, file, line)?;
+
+        if words.is_empty() {
+            return Err(get_parse_error(file, line, "empty directive"))
+        }
+
+        let name = words.remove(0);
+
+        Ok(Directive { name, args: words, file: file.to_path_buf(), line, block: None })
     }
 
-    fn get_apache_redirect_to_http_regex() -> Regex {
-        return Regex::new("(?:^|^[^#]+)Redirect[\\s\t]+/[\\s\t]+http").unwrap();
+    fn split_words(row: &str, file: &Path, line: usize) -> Result<Vec<String>, ParseError> {
+        let mut words: Vec<String> = Vec::new();
+        let mut chars = row.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            if ch.is_whitespace() {
+                continue;
+            }
+
+            let mut word = String::new();
+
+            if ch == '"' || ch == '\'' {
+                let mut closed = false;
+
+                while let Some(next) = chars.next() {
+                    if next == ch {
+                        closed = true;
+                        break
+                    }
+
+                    if next == '\\' && chars.peek() == Some(&ch) {
+                        word.push(ch);
+                        chars.next();
+                    } else {
+                        word.push(next);
+                    }
+                }
+
+                if !closed {
+                    return Err(get_parse_error(file, line, "quote is not closed"))
+                }
+
+            } else {
+                word.push(ch);
+
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() { break }
+                    word.push(next);
+                    chars.next();
+                }
+            }
+
+            words.push(word);
+        }
+
+        Ok(words)
     }
 
-    fn get_apache_vhost_port_regex() -> Regex {
-        return Regex::new("(?:^|^[^#]+)<VirtualHost[\\s\t]+.*:(\\d+)>").unwrap();
+    fn get_parse_error(file: &Path, line: usize, message: &str) -> ParseError {
+        ParseError { file: file.to_path_buf(), line, message: message.to_string() }
     }
 }
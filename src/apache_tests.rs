@@ -2,14 +2,21 @@
 pub mod apache_tests {
     use std::path::Path;
 
-    use crate::apache::apache::get_apache_vhosts;
+    use crate::apache::apache::{get_apache_vhosts, get_apache_vhosts_from_directives,
+                                get_apache_vhosts_from_main_config, load_apache_config, parse_apache_config};
+    use crate::domain::domain::VirtualHost;
     use crate::test_utils::test_utils::assert_vhost_in_vec;
+    use crate::webserver::webserver::ConfigScanOptions;
+
+    fn get_vhosts_from_dir(path: &Path, include_redirects: bool) -> Vec<VirtualHost> {
+        get_apache_vhosts(&[path.to_path_buf()], &ConfigScanOptions::default(), include_redirects, false, &mut Vec::new())
+    }
 
     #[test]
     fn get_virtual_hosts_from_apache_file() {
         let vhosts_path = Path::new("tests/apache-vhosts");
 
-        let vhosts = get_apache_vhosts(vhosts_path);
+        let vhosts = get_vhosts_from_dir(vhosts_path, false);
 
         for vhost in &vhosts {
             println!("{}", vhost.to_string());
@@ -23,4 +30,171 @@ pub mod apache_tests {
         assert_vhost_in_vec(&vhosts, "whatever.ru", 5380);
         assert_vhost_in_vec(&vhosts, "demo.company.ru", 1480);
     }
+
+    #[test]
+    fn apache_vhosts_should_keep_position_of_virtual_host_section() {
+        let vhosts = get_vhosts_from_dir(Path::new("tests/apache-vhosts"), false);
+
+        let vhost = vhosts.iter().find(|vhost| vhost.domain == "demo.company.ru").unwrap();
+        let provenance = vhost.provenance.as_ref().unwrap();
+
+        assert_eq!(provenance.server, "apache");
+        assert_eq!(provenance.file, Path::new("tests/apache-vhosts/vhost2.conf"));
+        assert_eq!(provenance.line, 14);
+        assert_eq!(provenance.listen, vec!["111.22.33.44:1480"]);
+        assert_eq!(provenance.server_names, vec!["demo.company.ru"]);
+    }
+
+    #[test]
+    fn get_apache_vhosts_from_debian_main_config_should_follow_includes() {
+        let vhosts = get_apache_vhosts_from_main_config(Path::new("tests/apache-main/apache2.conf"), false, false, &mut Vec::new()).unwrap();
+
+        vhosts.iter().for_each(|vhost| println!("{}", vhost.to_string()));
+
+        assert_eq!(vhosts.len(), 3);
+
+        assert_vhost_in_vec(&vhosts, "secure.whatever.ru", 443);
+        assert_vhost_in_vec(&vhosts, "one.whatever.ru", 80);
+        assert_vhost_in_vec(&vhosts, "two.whatever.ru", 8080);
+    }
+
+    #[test]
+    fn get_apache_vhosts_from_main_config_should_resolve_includes_from_server_root() {
+        let vhosts = get_apache_vhosts_from_main_config(Path::new("tests/apache-rhel/conf/httpd.conf"), false, false, &mut Vec::new()).unwrap();
+
+        assert_eq!(vhosts.len(), 1);
+
+        assert_vhost_in_vec(&vhosts, "rhel.whatever.ru", 80);
+    }
+
+    #[test]
+    fn conditional_sections_should_be_evaluated_against_defines() {
+        let vhosts = get_apache_vhosts_from_main_config(Path::new("tests/apache-conditional/apache2.conf"), false, false, &mut Vec::new()).unwrap();
+
+        let domains: Vec<&str> = vhosts.iter().map(|vhost| vhost.domain.as_str()).collect();
+
+        assert_eq!(domains, vec!["one.whatever.ru", "not-two.whatever.ru", "secure.whatever.ru"]);
+    }
+
+    #[test]
+    fn negated_if_module_sections_should_be_skipped_in_separate_files() {
+        let config = "<IfModule !mod_ssl.c>\n    <VirtualHost *:80>\n        ServerName no-ssl.whatever.ru\n    </VirtualHost>\n</IfModule>\n\
+                      <IfDefine SSL>\n    <VirtualHost *:443>\n        ServerName whatever.ru\n    </VirtualHost>\n</IfDefine>\n";
+        let directives = parse_apache_config(config, Path::new("vhost.conf")).unwrap();
+
+        let vhosts = get_apache_vhosts_from_directives(&directives, false, false, &mut Vec::new());
+
+        assert_eq!(vhosts.len(), 1);
+        assert_vhost_in_vec(&vhosts, "whatever.ru", 443);
+    }
+
+    #[test]
+    fn load_apache_config_should_keep_section_positions_from_included_files() {
+        let directives = load_apache_config(Path::new("tests/apache-main/apache2.conf"), &mut Vec::new()).unwrap();
+
+        let if_module = directives.iter().find(|directive| directive.name == "IfModule").unwrap();
+        let nested_if_module = if_module.find_first("IfModule").unwrap();
+        let virtual_host = nested_if_module.find_first("VirtualHost").unwrap();
+
+        assert_eq!(virtual_host.args, vec!["_default_:443"]);
+        assert_eq!(virtual_host.file, Path::new("tests/apache-main/ssl-sites/secure.conf"));
+        assert_eq!(virtual_host.line, 2);
+
+        let listen = directives.iter().find(|directive| directive.name == "Listen").unwrap();
+        assert_eq!(listen.file, Path::new("tests/apache-main/ports.conf"));
+    }
+
+    #[test]
+    fn parse_apache_config_should_join_continued_lines_and_unquote_values() {
+        let config = "<Directory \"/var/www/my site\">\n    Options Indexes \\\n        FollowSymLinks\n</Directory>\n";
+
+        let directives = parse_apache_config(config, Path::new("apache2.conf")).unwrap();
+
+        assert_eq!(directives.len(), 1);
+
+        let section = &directives[0];
+        assert_eq!(section.args, vec!["/var/www/my site"]);
+
+        let options = section.find_first("Options").unwrap();
+        assert_eq!(options.args, vec!["Indexes", "FollowSymLinks"]);
+        assert_eq!(options.line, 2);
+    }
+
+    #[test]
+    fn parse_apache_config_should_return_error_for_mismatched_section() {
+        let config = "<VirtualHost *:80>\n    ServerName whatever.ru\n</Directory>\n";
+
+        let error = parse_apache_config(config, Path::new("broken.conf")).unwrap_err();
+
+        assert_eq!(error.to_string(), "broken.conf:3: expected </VirtualHost> but saw </Directory>");
+    }
+
+    #[test]
+    fn parse_apache_config_should_return_error_for_unclosed_section() {
+        let config = "<VirtualHost *:80>\n    ServerName whatever.ru\n";
+
+        let error = parse_apache_config(config, Path::new("broken.conf")).unwrap_err();
+
+        assert_eq!(error.line, 1);
+    }
+
+    #[test]
+    fn apache_vhost_should_contain_server_aliases() {
+        let vhosts = get_apache_vhosts_from_main_config(Path::new("tests/apache-main/apache2.conf"), false, false, &mut Vec::new()).unwrap();
+
+        let vhost = vhosts.iter().find(|vhost| vhost.domain == "two.whatever.ru").unwrap();
+
+        assert_eq!(vhost.aliases, vec!["www.two.whatever.ru", "two.whatever.org"]);
+    }
+
+    #[test]
+    fn apache_vhost_with_several_server_name_values_should_use_first_as_primary() {
+        let config = "<VirtualHost *:443>\n    ServerName www.whatever.ru whatever.ru\n</VirtualHost>\n";
+        let directives = parse_apache_config(config, Path::new("vhost.conf")).unwrap();
+
+        let vhosts = get_apache_vhosts_from_directives(&directives, false, false, &mut Vec::new());
+
+        assert_eq!(vhosts.len(), 1);
+        assert_eq!(vhosts[0].domain, "www.whatever.ru");
+        assert_eq!(vhosts[0].aliases, vec!["whatever.ru"]);
+    }
+
+    #[test]
+    fn apache_vhost_should_contain_all_addresses() {
+        let config = "<VirtualHost 10.0.0.1:80 [::]:80 _default_:8080>\n    ServerName whatever.ru\n</VirtualHost>\n";
+        let directives = parse_apache_config(config, Path::new("vhost.conf")).unwrap();
+
+        let vhosts = get_apache_vhosts_from_directives(&directives, false, false, &mut Vec::new());
+
+        assert_eq!(vhosts.len(), 1);
+
+        let listens: Vec<String> = vhosts[0].listens.iter().map(|listen| listen.to_string()).collect();
+        assert_eq!(listens, vec!["10.0.0.1:80", "[::]:80", "8080 default_server"]);
+        assert_eq!(vhosts[0].get_ports(), vec![80, 8080]);
+    }
+
+    #[test]
+    fn apache_listen_endpoints_should_be_marked_as_tls_from_ssl_engine() {
+        let vhosts = get_vhosts_from_dir(Path::new("tests/apache-tls"), false);
+
+        let panel_vhost = vhosts.iter().find(|vhost| vhost.domain == "panel.whatever.ru").unwrap();
+        assert!(panel_vhost.is_tls_enabled());
+        assert_eq!(panel_vhost.tls.engine, Some(true));
+        assert_eq!(panel_vhost.tls.certificate_files, vec!["/etc/pki/tls/certs/panel.whatever.ru.crt"]);
+
+        let plain_vhost = vhosts.iter().find(|vhost| vhost.domain == "plain.whatever.ru").unwrap();
+        assert!(!plain_vhost.is_tls_enabled());
+        assert_eq!(plain_vhost.tls.engine, Some(false));
+    }
+
+    #[test]
+    fn apache_vhosts_redirecting_all_requests_should_be_skipped() {
+        let vhosts = get_vhosts_from_dir(Path::new("tests/apache-redirect"), false);
+
+        vhosts.iter().for_each(|vhost| println!("{}", vhost.to_string()));
+
+        let domains: Vec<&str> = vhosts.iter().map(|vhost| vhost.domain.as_str()).collect();
+
+        assert_eq!(domains, vec!["partial.whatever.ru", "conditional.whatever.ru", "disabled.whatever.ru"]);
+    }
 }
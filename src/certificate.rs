@@ -0,0 +1,277 @@
+pub mod certificate {
+    use std::collections::{BTreeMap, HashMap};
+    use std::convert::TryFrom;
+    use std::fs;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::path::Path;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use serde::Serialize;
+    use x509_parser::prelude::{FromDer, GeneralName, X509Certificate};
+
+    use crate::domain::domain::{Scheme, VirtualHost};
+    use crate::error::error::CertificateError;
+    use crate::output::output::serialize_flag;
+    use crate::site::site::{get_scheme, get_url};
+
+    const PEM_CERTIFICATE_TAG: &str = "CERTIFICATE";
+
+    const WILDCARD_PREFIX: &str = "*.";
+
+    const SECONDS_PER_DAY: i64 = 86400;
+
+    /// Values of X.509 certificate used for monitoring, signatures aren't verified.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Certificate {
+        /// Distinguished name in certificate order, i.e. `C=RU, O=Company, CN=app.company.ru`
+        pub subject: String,
+        pub issuer: String,
+        /// DNS names and IP addresses of Subject Alternative Name extension
+        pub sans: Vec<String>,
+        /// Unix timestamps
+        pub not_before: i64,
+        pub not_after: i64
+    }
+
+    impl Certificate {
+        /// Whole days left, negative for expired certificate.
+        pub fn get_days_to_expiry(&self, now: i64) -> i64 {
+            (self.not_after - now).div_euclid(SECONDS_PER_DAY)
+        }
+
+        /// Name is covered by SAN value or `*.` wildcard of one left-most label. Common name is ignored
+        /// like browsers do.
+        pub fn is_name_covered(&self, name: &str) -> bool {
+            let name = name.trim_end_matches('.').to_lowercase();
+
+            self.sans.iter().any(|san| {
+                let san = san.trim_end_matches('.').to_lowercase();
+
+                match san.strip_prefix(WILDCARD_PREFIX) {
+                    Some(parent) => name.split_once('.')
+                        .map(|(label, rest)| !label.is_empty() && rest == parent).unwrap_or(false),
+                    None => san == name
+                }
+            })
+        }
+    }
+
+    /// Certificate after the first one in file, i.e. intermediate certificate of `fullchain.pem`.
+    #[derive(Clone, Debug, PartialEq, Serialize)]
+    pub struct ChainCertificate {
+        pub subject: String,
+        pub issuer: String,
+        pub not_after: String,
+        pub days_to_expiry: i64
+    }
+
+    /// Leaf certificate of configured file with its chain. Values are empty and error is set
+    /// if file can't be read.
+    #[derive(Clone, Debug, PartialEq, Serialize)]
+    pub struct CertificateFile {
+        pub file: String,
+        pub subject: String,
+        pub issuer: String,
+        pub sans: Vec<String>,
+        pub not_after: String,
+        pub days_to_expiry: i64,
+        /// `1` if domain is covered by SANs of certificate
+        #[serde(serialize_with = "serialize_flag")]
+        pub covered: bool,
+        pub chain: Vec<ChainCertificate>,
+        pub error: String
+    }
+
+    /// Certificates of https virtual host. Values are never null, so they can be taken with JSONPath
+    /// by dependent item.
+    #[derive(Clone, Debug, PartialEq, Serialize)]
+    pub struct CertificateReport {
+        pub domain: String,
+        #[serde(skip)]
+        pub url: String,
+        pub port: i32,
+        /// The least days to expiry of all certificates including chains, `0` if any file has error
+        pub days_to_expiry: i64,
+        /// `1` if domain is covered by every certificate
+        #[serde(serialize_with = "serialize_flag")]
+        pub covered: bool,
+        /// First file error, empty if all files are read
+        pub error: String,
+        pub files: Vec<CertificateFile>
+    }
+
+    /// Reads certificate files of https virtual hosts. Relative paths are resolved by sources
+    /// against web server config root.
+    pub fn get_certificate_reports(vhosts: &[VirtualHost], port_schemes: &HashMap<i32, Scheme>,
+                                   now: i64) -> Vec<CertificateReport> {
+        vhosts.iter()
+            .filter(|vhost| !vhost.tls.certificate_files.is_empty())
+            .filter(|vhost| get_scheme(vhost, port_schemes) == Scheme::Https)
+            .map(|vhost| get_certificate_report(vhost, now))
+            .collect()
+    }
+
+    pub fn get_certificate_report(vhost: &VirtualHost, now: i64) -> CertificateReport {
+        let port = vhost.get_port();
+
+        let files: Vec<CertificateFile> = vhost.tls.certificate_files.iter()
+            .map(|file| get_certificate_file(file, &vhost.domain, now)).collect();
+
+        let error = files.iter().map(|file| file.error.to_string())
+            .find(|error| !error.is_empty()).unwrap_or_default();
+
+        let days_to_expiry = if error.is_empty() {
+            files.iter()
+                .flat_map(|file| file.chain.iter().map(|certificate| certificate.days_to_expiry)
+                    .chain(std::iter::once(file.days_to_expiry)))
+                .min().unwrap_or_default()
+        } else { 0 };
+
+        CertificateReport {
+            domain: vhost.domain.to_string(),
+            url: get_url(&vhost.domain, port, Scheme::Https),
+            port,
+            days_to_expiry,
+            covered: files.iter().all(|file| file.covered),
+            error,
+            files
+        }
+    }
+
+    fn get_certificate_file(file: &str, domain: &str, now: i64) -> CertificateFile {
+        let mut certificate_file = CertificateFile {
+            file: file.to_string(), subject: String::new(), issuer: String::new(), sans: Vec::new(),
+            not_after: String::new(), days_to_expiry: 0, covered: false, chain: Vec::new(), error: String::new()
+        };
+
+        match load_certificate_chain(file) {
+            Ok(certificates) => {
+                let leaf = &certificates[0];
+
+                certificate_file.subject = leaf.subject.to_string();
+                certificate_file.issuer = leaf.issuer.to_string();
+                certificate_file.sans = leaf.sans.clone();
+                certificate_file.not_after = get_date_time(leaf.not_after);
+                certificate_file.days_to_expiry = leaf.get_days_to_expiry(now);
+                certificate_file.covered = leaf.is_name_covered(domain);
+
+                certificate_file.chain = certificates.iter().skip(1).map(|certificate| ChainCertificate {
+                    subject: certificate.subject.to_string(),
+                    issuer: certificate.issuer.to_string(),
+                    not_after: get_date_time(certificate.not_after),
+                    days_to_expiry: certificate.get_days_to_expiry(now)
+                }).collect();
+
+                if !certificate_file.covered {
+                    debug!("domain '{}' isn't covered by certificate '{}' SANs {:?}", domain, file, leaf.sans);
+                }
+            }
+            Err(e) => {
+                warn!("{}", e);
+                certificate_file.error = e.to_string();
+            }
+        }
+
+        certificate_file
+    }
+
+    /// nginx evaluates variables and `data:` values per request, such paths can't be read.
+    pub fn is_supported_certificate_path(file: &str) -> bool {
+        !file.contains('$') && !file.starts_with("data:")
+    }
+
+    /// Certificates of PEM file in file order, other PEM blocks such as private keys are skipped.
+    pub fn load_certificate_chain(file: &str) -> Result<Vec<Certificate>, CertificateError> {
+        if !is_supported_certificate_path(file) {
+            return Err(CertificateError::UnsupportedPath { path: file.to_string() })
+        }
+
+        let path = Path::new(file);
+        debug!("read certificate file '{}'", path.display());
+
+        let content = fs::read_to_string(path).map_err(|e| CertificateError::UnreadableFile {
+            path: path.to_path_buf(), message: e.to_string()
+        })?;
+
+        let certificates = parse_pem_certificates(&content).map_err(|message| CertificateError::InvalidCertificate {
+            path: path.to_path_buf(), message
+        })?;
+
+        if certificates.is_empty() {
+            return Err(CertificateError::MissingCertificate { path: path.to_path_buf() })
+        }
+
+        Ok(certificates)
+    }
+
+    /// Certificates of PEM blocks in content order, blocks with other tags are skipped.
+    pub fn parse_pem_certificates(content: &str) -> Result<Vec<Certificate>, String> {
+        let blocks = pem::parse_many(content).map_err(|e| e.to_string())?;
+
+        blocks.iter()
+            .filter(|block| block.tag() == PEM_CERTIFICATE_TAG)
+            .map(|block| parse_der_certificate(block.contents()))
+            .collect()
+    }
+
+    pub fn parse_der_certificate(der: &[u8]) -> Result<Certificate, String> {
+        let (_, certificate) = X509Certificate::from_der(der).map_err(|e| e.to_string())?;
+
+        let sans = match certificate.subject_alternative_name().map_err(|e| e.to_string())? {
+            Some(extension) => extension.value.general_names.iter()
+                .filter_map(|name| match name {
+                    GeneralName::DNSName(value) => Some(value.to_string()),
+                    GeneralName::IPAddress(value) => get_ip_address(value),
+                    _ => None
+                }).collect(),
+            None => Vec::new()
+        };
+
+        Ok(Certificate {
+            subject: certificate.subject().to_string(),
+            issuer: certificate.issuer().to_string(),
+            sans,
+            not_before: certificate.validity().not_before.timestamp(),
+            not_after: certificate.validity().not_after.timestamp()
+        })
+    }
+
+    fn get_ip_address(octets: &[u8]) -> Option<String> {
+        match octets.len() {
+            4 => <[u8; 4]>::try_from(octets).ok().map(|octets| Ipv4Addr::from(octets).to_string()),
+            16 => <[u8; 16]>::try_from(octets).ok().map(|octets| Ipv6Addr::from(octets).to_string()),
+            _ => None
+        }
+    }
+
+    /// Unix timestamp as `2026-12-31T23:59:59Z`.
+    pub fn get_date_time(timestamp: i64) -> String {
+        let days = timestamp.div_euclid(SECONDS_PER_DAY);
+        let seconds = timestamp.rem_euclid(SECONDS_PER_DAY);
+
+        let days = days + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days - era * 146097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day,
+                seconds / 3600, seconds % 3600 / 60, seconds % 60)
+    }
+
+    pub fn get_current_timestamp() -> i64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs() as i64).unwrap_or_default()
+    }
+
+    /// Object with reports by https url, i.e. JSONPath `$['{#URL}'].days_to_expiry` for item prototype.
+    pub fn get_certificates_json(reports: &[CertificateReport]) -> String {
+        let reports: BTreeMap<&str, &CertificateReport> = reports.iter()
+            .map(|report| (report.url.as_str(), report)).collect();
+
+        serde_json::to_string(&reports).unwrap_or_else(|_| "{}".to_string())
+    }
+}
@@ -0,0 +1,285 @@
+pub mod check {
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::path::PathBuf;
+
+    use crate::DEFAULT_HTTP_PORT;
+    use crate::domain::domain::{get_name_kind, ListenEndpoint, NameKind, Provenance, Scheme, VirtualHost};
+    use crate::error::error::DiscoveryError;
+    use crate::filter::filter::get_vhosts_per_port;
+    use crate::site::site::{get_redirect_target, get_scheme, get_url};
+
+    const MAX_HOSTNAME_LENGTH: usize = 253;
+    const MAX_HOSTNAME_LABEL_LENGTH: usize = 63;
+
+    /// Listen addresses which mean all addresses.
+    const WILDCARD_ADDRESSES: [&str; 4] = ["", "*", "0.0.0.0", "::"];
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum IssueKind {
+        /// Config file can't be read or parsed
+        ConfigError,
+        /// The same server name and port on the same address in several virtual hosts
+        DuplicateName,
+        /// Virtual host without server name which isn't default one
+        MissingName,
+        /// TLS directives contradict each other
+        TlsConflict,
+        /// Virtual host redirects to its own url
+        RedirectLoop,
+        /// Server name isn't valid host name
+        InvalidName
+    }
+
+    impl IssueKind {
+        pub fn as_str(&self) -> &str {
+            match self {
+                IssueKind::ConfigError => "config-error",
+                IssueKind::DuplicateName => "duplicate-name",
+                IssueKind::MissingName => "missing-name",
+                IssueKind::TlsConflict => "tls-conflict",
+                IssueKind::RedirectLoop => "redirect-loop",
+                IssueKind::InvalidName => "invalid-name"
+            }
+        }
+    }
+
+    /// Problem found in web server configs. File and line are set if known.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Issue {
+        pub kind: IssueKind,
+        pub file: Option<PathBuf>,
+        pub line: Option<usize>,
+        pub message: String
+    }
+
+    /// `file:line: kind: message`, compatible with compiler style output parsers.
+    impl fmt::Display for Issue {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let location = match (&self.file, self.line) {
+                (Some(file), Some(line)) => format!("{}:{}", file.display(), line),
+                (Some(file), None) => file.display().to_string(),
+                (None, _) => "-".to_string()
+            };
+
+            write!(f, "{}: {}: {}", location, self.kind.as_str(), self.message)
+        }
+    }
+
+    /// Returns issue for config which can't be read or parsed.
+    pub fn get_error_issue(error: &DiscoveryError) -> Issue {
+        let (file, line, message) = match error {
+            DiscoveryError::PermissionDenied { path } =>
+                (Some(path.to_path_buf()), None, "permission denied".to_string()),
+            DiscoveryError::UnreadableFile { path, message } =>
+                (Some(path.to_path_buf()), None, format!("unable to read: {}", message)),
+            DiscoveryError::ParseError { file, line, message } =>
+                (Some(file.to_path_buf()), Some(*line), message.to_string()),
+            DiscoveryError::InvalidPort { file, line, value } =>
+                (Some(file.to_path_buf()), Some(*line), format!("invalid port '{}'", value)),
+            DiscoveryError::InvalidConfig { path, message } =>
+                (Some(path.to_path_buf()), None, message.to_string()),
+            DiscoveryError::UnknownSource { .. } => (None, None, error.to_string())
+        };
+
+        Issue { kind: IssueKind::ConfigError, file, line, message }
+    }
+
+    /// Returns issues of virtual hosts of one source. Unnamed virtual hosts (empty domain)
+    /// and redirects are expected to be included.
+    pub fn check_vhosts(vhosts: &Vec<VirtualHost>, port_schemes: &HashMap<i32, Scheme>) -> Vec<Issue> {
+        let mut issues: Vec<Issue> = Vec::new();
+
+        for vhost in vhosts {
+            issues.append(&mut get_name_issues(vhost));
+            issues.append(&mut get_tls_issues(vhost));
+        }
+
+        let vhosts_per_port = get_vhosts_per_port(vhosts);
+
+        issues.append(&mut get_duplicate_name_issues(&vhosts_per_port));
+
+        for vhost in &vhosts_per_port {
+            issues.append(&mut get_redirect_loop_issues(vhost, port_schemes));
+        }
+
+        issues
+    }
+
+    /// Checks host name syntax: labels of 1-63 chars without leading or trailing hyphen.
+    pub fn is_valid_hostname(name: &str) -> bool {
+        let name = name.strip_suffix('.').unwrap_or(name);
+
+        if name.is_empty() || name.len() > MAX_HOSTNAME_LENGTH {
+            return false
+        }
+
+        name.split('.').all(|label| {
+            !label.is_empty() && label.len() <= MAX_HOSTNAME_LABEL_LENGTH &&
+            !label.starts_with('-') && !label.ends_with('-') &&
+            label.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+        })
+    }
+
+    fn get_name_issues(vhost: &VirtualHost) -> Vec<Issue> {
+        let mut issues: Vec<Issue> = Vec::new();
+
+        let raw_names: Vec<String> = match &vhost.provenance {
+            Some(provenance) => provenance.server_names.clone(),
+            None => vhost.get_names()
+        };
+
+        let mut has_invalid_names = false;
+
+        for raw_name in &raw_names {
+            let name = get_host_name(raw_name);
+
+            let valid = match get_name_kind(&name) {
+                NameKind::Invalid => false,
+                NameKind::Exact => is_valid_hostname(&name),
+                _ => true
+            };
+
+            if !valid {
+                has_invalid_names = true;
+                issues.push(get_issue(IssueKind::InvalidName, vhost,
+                                      format!("server name '{}' isn't valid host name", raw_name)));
+            }
+        }
+
+        let default_server = vhost.listens.iter().any(|listen| listen.default_server);
+
+        if vhost.domain.is_empty() && !default_server && !has_invalid_names {
+            issues.push(get_issue(IssueKind::MissingName, vhost,
+                                  "virtual host without server name, it isn't default one".to_string()));
+        }
+
+        issues
+    }
+
+    /// Host part of name as written in config: without apache scheme and port, without nginx leading dot.
+    fn get_host_name(raw_name: &str) -> String {
+        if get_name_kind(raw_name) == NameKind::Regex {
+            return raw_name.to_string()
+        }
+
+        let name = raw_name.split_once("://").map(|(_, name)| name).unwrap_or(raw_name);
+
+        let name = match name.rsplit_once(':') {
+            Some((host, port)) if !port.is_empty() && port.chars().all(|ch| ch.is_ascii_digit()) => host,
+            _ => name
+        };
+
+        name.strip_prefix('.').filter(|name| !name.is_empty()).unwrap_or(name).to_string()
+    }
+
+    fn get_tls_issues(vhost: &VirtualHost) -> Vec<Issue> {
+        let mut issues: Vec<Issue> = Vec::new();
+
+        if vhost.tls.engine == Some(false) {
+            if vhost.listens.iter().any(|listen| listen.ssl) {
+                issues.push(get_issue(IssueKind::TlsConflict, vhost,
+                                      "listen has ssl flag, but TLS is disabled".to_string()));
+            }
+
+            if !vhost.tls.certificate_files.is_empty() {
+                issues.push(get_issue(IssueKind::TlsConflict, vhost,
+                                      "certificate is configured, but TLS is disabled".to_string()));
+            }
+        }
+
+        if vhost.listens.iter().any(|listen| listen.ssl && listen.port == DEFAULT_HTTP_PORT) {
+            issues.push(get_issue(IssueKind::TlsConflict, vhost,
+                                  format!("TLS is enabled on http port {}", DEFAULT_HTTP_PORT)));
+        }
+
+        issues
+    }
+
+    /// Reports every virtual host which repeats name and port of virtual host defined before
+    /// on the same listen address. Wildcard address overlaps every address.
+    fn get_duplicate_name_issues(vhosts: &Vec<VirtualHost>) -> Vec<Issue> {
+        let mut issues: Vec<Issue> = Vec::new();
+
+        let mut first_vhosts: Vec<(String, &VirtualHost)> = Vec::new();
+
+        for vhost in vhosts {
+            for name in vhost.get_names().iter().filter(|name| !name.is_empty()) {
+                let key = name.to_lowercase();
+
+                let first_vhost = first_vhosts.iter()
+                    .find(|(first_name, first_vhost)| {
+                        *first_name == key && first_vhost.get_port() == vhost.get_port() &&
+                        has_common_address(first_vhost, vhost)
+                    })
+                    .map(|(_, first_vhost)| *first_vhost);
+
+                match first_vhost {
+                    Some(first_vhost) if first_vhost.provenance != vhost.provenance => {
+                        let first_position = first_vhost.provenance.as_ref()
+                            .map(get_position).unwrap_or_default();
+
+                        issues.push(get_issue(IssueKind::DuplicateName, vhost,
+                                              format!("duplicate server name '{}' on port {}, first defined at {}",
+                                                      name, vhost.get_port(), first_position)));
+                    }
+                    Some(_) => {}
+                    None => first_vhosts.push((key, vhost))
+                }
+            }
+        }
+
+        issues
+    }
+
+    fn has_common_address(first_vhost: &VirtualHost, vhost: &VirtualHost) -> bool {
+        first_vhost.listens.iter().any(|first_listen| vhost.listens.iter().any(|listen| {
+            match (get_specific_address(first_listen), get_specific_address(listen)) {
+                (Some(first_address), Some(address)) => first_address.eq_ignore_ascii_case(address),
+                _ => true
+            }
+        }))
+    }
+
+    /// Listen address, `None` for all addresses.
+    fn get_specific_address(listen: &ListenEndpoint) -> Option<&str> {
+        listen.address.as_deref().filter(|address| !WILDCARD_ADDRESSES.contains(address))
+    }
+
+    /// Redirect of virtual host per port which target is url of the same virtual host.
+    fn get_redirect_loop_issues(vhost: &VirtualHost, port_schemes: &HashMap<i32, Scheme>) -> Vec<Issue> {
+        let redirect = match &vhost.redirect {
+            Some(redirect) => redirect,
+            None => return Vec::new()
+        };
+
+        let scheme = get_scheme(vhost, port_schemes);
+
+        vhost.get_names().iter()
+            .filter(|name| get_name_kind(name) == NameKind::Exact)
+            .filter_map(|name| {
+                let url = get_url(name, vhost.get_port(), scheme);
+                let target = get_redirect_target(redirect, name, scheme);
+
+                if target.trim_end_matches('/').eq_ignore_ascii_case(&url) {
+                    Some(get_issue(IssueKind::RedirectLoop, vhost,
+                                   format!("{} redirects to itself ({})", url, redirect)))
+                } else {
+                    None
+                }
+            }).collect()
+    }
+
+    fn get_issue(kind: IssueKind, vhost: &VirtualHost, message: String) -> Issue {
+        Issue {
+            kind,
+            file: vhost.provenance.as_ref().map(|provenance| provenance.file.to_path_buf()),
+            line: vhost.provenance.as_ref().map(|provenance| provenance.line),
+            message
+        }
+    }
+
+    fn get_position(provenance: &Provenance) -> String {
+        format!("{}:{}", provenance.file.display(), provenance.line)
+    }
+}
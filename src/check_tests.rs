@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod check_tests {
+    use std::path::PathBuf;
+
+    use crate::check::check::{get_error_issue, is_valid_hostname, Issue, IssueKind};
+    use crate::discovery::discovery::{check_configs, DiscoveryOptions};
+    use crate::error::error::DiscoveryError;
+
+    fn get_issues(vhosts_path: &str) -> Vec<Issue> {
+        let options = DiscoveryOptions::new()
+            .sources(&["nginx"])
+            .vhosts_path("nginx", vhosts_path);
+
+        check_configs(&options).unwrap()
+    }
+
+    #[test]
+    fn check_should_report_issues_with_file_and_line() {
+        let issues: Vec<String> = get_issues("tests/nginx-check").iter().map(|issue| issue.to_string()).collect();
+
+        assert_eq!(issues, vec![
+            // the same name on different addresses isn't duplicate, wildcard address overlaps them
+            "tests/nginx-check/addresses.conf:11: duplicate-name: duplicate server name 'api.company.ru' on port 80, \
+             first defined at tests/nginx-check/addresses.conf:1",
+            "tests/nginx-check/shop.conf:1: invalid-name: server name 'bad_name.company.ru' isn't valid host name",
+            "tests/nginx-check/shop.conf:1: invalid-name: server name '-dash.company.ru' isn't valid host name",
+            "tests/nginx-check/site.conf:1: duplicate-name: duplicate server name 'shop.company.ru' on port 80, \
+             first defined at tests/nginx-check/shop.conf:1",
+            "tests/nginx-check/site.conf:6: tls-conflict: listen has ssl flag, but TLS is disabled",
+            "tests/nginx-check/site.conf:6: tls-conflict: certificate is configured, but TLS is disabled",
+            "tests/nginx-check/site.conf:13: missing-name: virtual host without server name, it isn't default one",
+            "tests/nginx-check/site.conf:23: redirect-loop: https://loop.company.ru redirects to itself \
+             (301 https://$host$request_uri)"
+        ]);
+    }
+
+    #[test]
+    fn check_should_report_unreadable_configs() {
+        let issues = get_issues("tests/nginx-broken");
+
+        assert!(issues.iter().all(|issue| issue.kind == IssueKind::ConfigError));
+        assert!(issues.contains(&Issue {
+            kind: IssueKind::ConfigError, file: Some(PathBuf::from("tests/nginx-broken/site.conf")), line: Some(7),
+            message: "invalid port '99999'".to_string()
+        }));
+    }
+
+    #[test]
+    fn check_should_not_report_valid_configs() {
+        assert!(get_issues("tests/nginx-listen").is_empty());
+    }
+
+    #[test]
+    fn error_issue_without_line_should_contain_only_file() {
+        let error = DiscoveryError::PermissionDenied { path: PathBuf::from("/etc/nginx/conf.d/app.conf") };
+
+        assert_eq!(get_error_issue(&error).to_string(), "/etc/nginx/conf.d/app.conf: config-error: permission denied");
+    }
+
+    #[test]
+    fn hostname_labels_should_be_validated() {
+        assert!(is_valid_hostname("app-1.company.ru"));
+        assert!(is_valid_hostname("company.ru."));
+        assert!(!is_valid_hostname("app..company.ru"));
+        assert!(!is_valid_hostname("app-.company.ru"));
+        assert!(!is_valid_hostname(&format!("{}.ru", "a".repeat(64))));
+    }
+}
@@ -0,0 +1,286 @@
+pub mod config {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use serde::Deserialize;
+
+    use crate::discovery::discovery::DiscoveryOptions;
+    use crate::domain::domain::Scheme;
+    use crate::error::error::DiscoveryError;
+    use crate::filter::filter::ServerNamesPolicy;
+    use crate::output::output::OutputFormat;
+    use crate::probe::probe::get_probe_timeout;
+    use crate::site::site::{SiteMacro, validate_name_template};
+    use crate::source::source::get_web_server_sources;
+
+    pub const DEFAULT_CONFIG_PATH: &str = "/etc/site-discovery-flea.toml";
+
+    /// Discovery settings file, TOML or YAML. Every value is optional,
+    /// command line options override values from file.
+    #[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct Config {
+        /// Web server sources by name, all registered sources are enabled if none is given
+        pub sources: HashMap<String, SourceConfig>,
+        pub scan: ScanConfig,
+        pub sites: SitesConfig,
+        pub redirects: RedirectsConfig,
+        pub log: LogConfig,
+        pub output: OutputConfig,
+        pub zabbix: ZabbixConfig,
+        pub probe: ProbeConfig,
+        /// Fail on first unreadable or broken config instead of skipping it
+        pub strict: bool
+    }
+
+    #[derive(Clone, Debug, Deserialize, PartialEq)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct SourceConfig {
+        pub enabled: bool,
+        /// Virtual host configs directories, default source directory is used if empty
+        pub vhosts_paths: Vec<PathBuf>,
+        /// Main config, `vhosts_paths` are ignored if set
+        pub config: Option<PathBuf>
+    }
+
+    impl Default for SourceConfig {
+        fn default() -> Self {
+            SourceConfig { enabled: true, vhosts_paths: Vec::new(), config: None }
+        }
+    }
+
+    /// Virtual host configs directories scanning, see `ConfigScanOptions`.
+    #[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct ScanConfig {
+        pub max_depth: Option<usize>,
+        pub include: Option<Vec<String>>,
+        pub exclude: Vec<String>
+    }
+
+    #[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct SitesConfig {
+        pub include_www: bool,
+        pub include_custom_ports: bool,
+        pub exclude_http: bool,
+        /// `primary` or `all`
+        pub server_names: Option<String>,
+        pub ignore_list: Vec<String>,
+        pub allow_list: Vec<String>,
+        pub known_hostnames: Vec<String>,
+        /// Add `{#SERVER}`, `{#CONFIG_FILE}` and `{#CONFIG_LINE}` macros
+        pub include_provenance: bool,
+        /// Add `{#TAG_SERVER}`, `{#TAG_SOURCE_DIR}`, `{#TAG_TLS}`, `{#TAG_ENV}`, `{#TAG_OWNER}` and other annotation macros
+        pub include_tags: bool,
+        /// `{#NAME}` template, i.e. `{domain}{port_suffix}`
+        pub name_template: Option<String>,
+        /// Additional macros: `domain`, `port`, `scheme`, `server`, `aliases`
+        pub macros: Vec<String>,
+        /// Port to scheme, i.e. `8443 = "https"`
+        pub port_schemes: HashMap<String, String>
+    }
+
+    #[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct RedirectsConfig {
+        pub detect_302: bool,
+        pub include: bool
+    }
+
+    #[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct LogConfig {
+        pub level: Option<String>,
+        pub file: Option<PathBuf>
+    }
+
+    #[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct OutputConfig {
+        /// `lld`, `lld-data`, `prometheus`, `csv`, `yaml` or `urls`
+        pub format: Option<String>
+    }
+
+    /// Zabbix server or proxy for `send` command, see `SenderSettings`, and frontend API for `zabbix-sync` command.
+    #[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct ZabbixConfig {
+        pub server: Option<String>,
+        pub port: Option<u16>,
+        /// Host name as configured in Zabbix frontend
+        pub host: Option<String>,
+        /// Trapper discovery rule key, `site.discovery` by default
+        pub key: Option<String>,
+        /// Frontend API url for `zabbix-sync` command, i.e. `https://zabbix.company.ru/api_jsonrpc.php`
+        pub api_url: Option<String>,
+        pub api_token: Option<String>
+    }
+
+    /// HTTP probe for `probe` command, see `ProbeSettings`.
+    #[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+    #[serde(default, deny_unknown_fields)]
+    pub struct ProbeConfig {
+        /// Request timeout in seconds, fractional values are allowed
+        pub timeout: Option<f64>,
+        pub method: Option<String>,
+        pub expected_status: Vec<u16>,
+        pub follow_redirects: Option<bool>
+    }
+
+    impl Config {
+        /// Returns discovery options with values from config file, defaults are used for missing values.
+        pub fn get_discovery_options(&self) -> DiscoveryOptions {
+            let mut options = DiscoveryOptions::new()
+                .include_www(self.sites.include_www)
+                .include_custom_ports(self.sites.include_custom_ports)
+                .exclude_http(self.sites.exclude_http)
+                .detect_302_redirects(self.redirects.detect_302)
+                .include_redirects(self.redirects.include)
+                .ignore_list(self.sites.ignore_list.clone())
+                .allow_list(self.sites.allow_list.clone())
+                .known_hostnames(self.sites.known_hostnames.clone())
+                .include_provenance(self.sites.include_provenance)
+                .include_tags(self.sites.include_tags)
+                .port_schemes(self.get_port_schemes().unwrap_or_default())
+                .exclude_files(self.scan.exclude.clone())
+                .strict(self.strict);
+
+            if let Some(max_depth) = self.scan.max_depth {
+                options = options.max_depth(max_depth);
+            }
+
+            if let Some(patterns) = &self.scan.include {
+                options = options.include_files(patterns.clone());
+            }
+
+            if let Some(policy) = &self.sites.server_names {
+                options = options.server_names_policy(policy.parse().unwrap_or(ServerNamesPolicy::Primary));
+            }
+
+            if let Some(template) = &self.sites.name_template {
+                options = options.name_template(template);
+            }
+
+            if !self.sites.macros.is_empty() {
+                options = options.macros(self.get_macros().unwrap_or_default());
+            }
+
+            if !self.sources.is_empty() {
+                let enabled_sources: Vec<&str> = get_web_server_sources().iter()
+                    .map(|source| source.name())
+                    .filter(|name| self.sources.get(*name).map(|source| source.enabled).unwrap_or(true))
+                    .collect();
+
+                options = options.sources(&enabled_sources);
+            }
+
+            for (name, source) in &self.sources {
+                if !source.vhosts_paths.is_empty() {
+                    options = options.vhosts_paths(name, source.vhosts_paths.clone());
+                }
+
+                if let Some(config_path) = &source.config {
+                    options = options.config_path(name, config_path);
+                }
+            }
+
+            options
+        }
+
+        fn get_port_schemes(&self) -> Result<HashMap<i32, Scheme>, String> {
+            let mut port_schemes: HashMap<i32, Scheme> = HashMap::new();
+
+            for (port, scheme) in &self.sites.port_schemes {
+                let port = port.trim().parse::<i32>()
+                    .map_err(|_| format!("invalid port '{}' in port schemes", port))?;
+                let scheme = scheme.parse::<Scheme>()
+                    .map_err(|_| format!("unsupported scheme '{}' for port {}", scheme, port))?;
+
+                port_schemes.insert(port, scheme);
+            }
+
+            Ok(port_schemes)
+        }
+
+        fn get_macros(&self) -> Result<Vec<SiteMacro>, String> {
+            self.sites.macros.iter().map(|value| value.parse::<SiteMacro>()).collect()
+        }
+
+        fn validate(&self) -> Result<(), String> {
+            let sources = get_web_server_sources();
+
+            for name in self.sources.keys() {
+                if sources.iter().all(|source| source.name() != name) {
+                    return Err(format!("unknown web server source '{}'", name))
+                }
+            }
+
+            if let Some(policy) = &self.sites.server_names {
+                policy.parse::<ServerNamesPolicy>()?;
+            }
+
+            self.get_port_schemes()?;
+            self.get_macros()?;
+
+            if let Some(template) = &self.sites.name_template {
+                validate_name_template(template)?;
+            }
+
+            if let Some(format) = &self.output.format {
+                format.parse::<OutputFormat>()?;
+            }
+
+            if self.zabbix.port == Some(0) {
+                return Err("zabbix port must be 1 or greater".to_string())
+            }
+
+            if let Some(timeout) = self.probe.timeout {
+                if get_probe_timeout(timeout).is_none() {
+                    return Err(format!("probe timeout must be greater than 0 seconds, got {}", timeout))
+                }
+            }
+
+            if self.scan.max_depth == Some(0) {
+                return Err("scan max_depth must be 1 or greater".to_string())
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Loads config file, YAML is expected for `.yaml` and `.yml` files, TOML otherwise.
+    pub fn load_config(config_path: &Path) -> Result<Config, DiscoveryError> {
+        info!("read discovery config '{}'", config_path.display());
+
+        let content = fs::read_to_string(config_path)
+            .map_err(|e| DiscoveryError::from_io_error(config_path, &e))?;
+
+        let config = parse_config(&content, is_yaml_config(config_path))
+            .map_err(|message| DiscoveryError::InvalidConfig { path: config_path.to_path_buf(), message })?;
+
+        debug!("discovery config: {:?}", config);
+
+        Ok(config)
+    }
+
+    pub fn parse_config(content: &str, yaml: bool) -> Result<Config, String> {
+        let config: Config = if yaml {
+            serde_yaml::from_str(content).map_err(|e| e.to_string())?
+        } else {
+            toml::from_str(content).map_err(|e| e.to_string())?
+        };
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    fn is_yaml_config(config_path: &Path) -> bool {
+        match config_path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) => extension == "yaml" || extension == "yml",
+            None => false
+        }
+    }
+}
@@ -0,0 +1,105 @@
+#[cfg(test)]
+mod config_tests {
+    use std::path::{Path, PathBuf};
+
+    use crate::config::config::{load_config, parse_config};
+    use crate::discovery::discovery::discover_vhosts;
+    use crate::error::error::DiscoveryError;
+
+    #[test]
+    fn toml_and_yaml_configs_should_have_same_values() {
+        let toml_config = load_config(Path::new("tests/config/site-discovery-flea.toml")).unwrap();
+        let yaml_config = load_config(Path::new("tests/config/site-discovery-flea.yaml")).unwrap();
+
+        assert_eq!(toml_config, yaml_config);
+
+        let nginx = toml_config.sources.get("nginx").unwrap();
+        assert!(nginx.enabled);
+        assert_eq!(nginx.vhosts_paths, vec![PathBuf::from("tests/nginx-vhosts"), PathBuf::from("tests/nginx-wildcard")]);
+
+        assert!(!toml_config.sources.get("apache").unwrap().enabled);
+        assert_eq!(toml_config.log.level, Some("debug".to_string()));
+        assert_eq!(toml_config.output.format, Some("lld-data".to_string()));
+    }
+
+    #[test]
+    fn discovery_options_should_use_config_values() {
+        let config = load_config(Path::new("tests/config/site-discovery-flea.toml")).unwrap();
+
+        let vhosts = discover_vhosts(&config.get_discovery_options()).unwrap();
+
+        let domains: Vec<&str> = vhosts.iter().map(|vhost| vhost.domain.as_str()).collect();
+
+        assert!(domains.contains(&"gallery.whatever.ru"));
+        assert!(domains.contains(&"regex.whatever.ru"));
+        assert!(domains.iter().all(|domain| domain.ends_with(".whatever.ru")));
+    }
+
+    #[test]
+    fn empty_config_should_be_valid() {
+        let config = parse_config("", false).unwrap();
+
+        assert!(config.sources.is_empty());
+        assert!(!config.strict);
+    }
+
+    #[test]
+    fn config_with_unknown_source_should_be_invalid() {
+        assert!(parse_config("[sources.caddy]\nenabled = true", false).is_err());
+    }
+
+    #[test]
+    fn config_with_unknown_field_should_be_invalid() {
+        assert!(parse_config("[sites]\ninclude_wwww = true", false).is_err());
+    }
+
+    #[test]
+    fn config_with_invalid_port_scheme_should_be_invalid() {
+        assert!(parse_config("[sites]\nport_schemes = { 8443 = \"ftp\" }", false).is_err());
+    }
+
+    #[test]
+    fn config_with_unsupported_output_format_should_be_invalid() {
+        assert!(parse_config("[output]\nformat = \"xml\"", false).is_err());
+        assert!(parse_config("[output]\nformat = \"prometheus\"", false).is_ok());
+    }
+
+    #[test]
+    fn config_with_unknown_macro_or_name_placeholder_should_be_invalid() {
+        assert!(parse_config("[sites]\nmacros = [\"domain\", \"host\"]", false).is_err());
+        assert!(parse_config("[sites]\nname_template = \"{host}\"", false).is_err());
+        assert!(parse_config("[sites]\nmacros = [\"port\"]\nname_template = \"{domain}_{port}\"", false).is_ok());
+    }
+
+    #[test]
+    fn config_should_contain_zabbix_server() {
+        let config = parse_config("[zabbix]\nserver = \"zabbix.local\"\nhost = \"web-1\"", false).unwrap();
+
+        assert_eq!(config.zabbix.server, Some("zabbix.local".to_string()));
+        assert_eq!(config.zabbix.host, Some("web-1".to_string()));
+        assert_eq!(config.zabbix.port, None);
+
+        assert!(parse_config("[zabbix]\nport = 0", false).is_err());
+    }
+
+    #[test]
+    fn config_should_contain_probe_settings() {
+        let config = parse_config("[probe]\ntimeout = 5\nmethod = \"HEAD\"\nexpected_status = [200, 301]", false).unwrap();
+
+        assert_eq!(config.probe.timeout, Some(5.0));
+        assert_eq!(config.probe.method, Some("HEAD".to_string()));
+        assert_eq!(config.probe.expected_status, vec![200, 301]);
+        assert_eq!(config.probe.follow_redirects, None);
+
+        assert!(parse_config("[probe]\ntimeout = 0", false).is_err());
+        assert_eq!(parse_config("[probe]\ntimeout = 0.5", false).unwrap().probe.timeout, Some(0.5));
+    }
+
+    #[test]
+    fn missing_config_should_be_returned_as_error() {
+        match load_config(Path::new("tests/config/missing.toml")) {
+            Err(DiscoveryError::UnreadableFile { path, .. }) => assert_eq!(path, PathBuf::from("tests/config/missing.toml")),
+            _ => panic!("unreadable file error expected")
+        }
+    }
+}
@@ -0,0 +1,402 @@
+pub mod discovery {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use crate::certificate::certificate::{CertificateReport, get_certificate_reports};
+    use crate::check::check::{check_vhosts, get_error_issue, Issue};
+    use crate::domain::domain::{Scheme, Site, VirtualHost};
+    use crate::error::error::DiscoveryError;
+    use crate::explain::explain::{Explanation, Verdict};
+    use crate::filter::filter::{filter_vhosts, get_allowed_vhosts, get_vhost_verdicts, is_allowed_domain,
+                                ServerNamesPolicy};
+    use crate::site::site::{get_lld_macros, get_site_verdict, get_sites_from_vhosts, SiteFormat, SiteMacro};
+    use crate::source::source::{get_web_server_sources, SourceSettings, WebServerSource};
+    use crate::webserver::webserver::ConfigScanOptions;
+
+    /// Discovery options, defaults match command line defaults.
+    #[derive(Clone, Debug)]
+    pub struct DiscoveryOptions {
+        sources: Option<Vec<String>>,
+        vhosts_paths: HashMap<String, Vec<PathBuf>>,
+        config_paths: HashMap<String, PathBuf>,
+        scan_options: ConfigScanOptions,
+        include_www: bool,
+        include_custom_ports: bool,
+        exclude_http: bool,
+        detect_302_redirects: bool,
+        include_redirects: bool,
+        ignore_list: Vec<String>,
+        allow_list: Vec<String>,
+        server_names_policy: ServerNamesPolicy,
+        port_schemes: HashMap<i32, Scheme>,
+        known_hostnames: Vec<String>,
+        site_format: SiteFormat,
+        strict: bool
+    }
+
+    impl Default for DiscoveryOptions {
+        fn default() -> Self {
+            DiscoveryOptions {
+                sources: None,
+                vhosts_paths: HashMap::new(),
+                config_paths: HashMap::new(),
+                scan_options: ConfigScanOptions::default(),
+                include_www: false,
+                include_custom_ports: false,
+                exclude_http: false,
+                detect_302_redirects: false,
+                include_redirects: false,
+                ignore_list: Vec::new(),
+                allow_list: Vec::new(),
+                server_names_policy: ServerNamesPolicy::Primary,
+                port_schemes: HashMap::new(),
+                known_hostnames: Vec::new(),
+                site_format: SiteFormat::default(),
+                strict: false
+            }
+        }
+    }
+
+    impl DiscoveryOptions {
+        pub fn new() -> DiscoveryOptions {
+            DiscoveryOptions::default()
+        }
+
+        /// Enabled sources by name (`nginx`, `apache`), all registered sources are enabled by default.
+        pub fn sources(mut self, names: &[&str]) -> Self {
+            self.sources = Some(names.iter().map(|name| name.to_string()).collect());
+            self
+        }
+
+        /// Virtual host configs directory of source, default one is used otherwise.
+        pub fn vhosts_path(mut self, source: &str, path: impl Into<PathBuf>) -> Self {
+            self.vhosts_paths.insert(source.to_string(), vec![path.into()]);
+            self
+        }
+
+        /// Several virtual host configs directories of source.
+        pub fn vhosts_paths(mut self, source: &str, paths: Vec<PathBuf>) -> Self {
+            self.vhosts_paths.insert(source.to_string(), paths);
+            self
+        }
+
+        /// Main config of source, virtual hosts are discovered by following includes from it.
+        pub fn config_path(mut self, source: &str, path: impl Into<PathBuf>) -> Self {
+            self.config_paths.insert(source.to_string(), path.into());
+            self
+        }
+
+        /// Directory levels to walk in virtual host configs directories, `1` means no recursion.
+        pub fn max_depth(mut self, value: usize) -> Self {
+            self.scan_options.max_depth = value;
+            self
+        }
+
+        /// Config file name globs, `*.conf` by default.
+        pub fn include_files(mut self, patterns: Vec<String>) -> Self {
+            self.scan_options.include_patterns = patterns;
+            self
+        }
+
+        /// Config file name globs to skip, i.e. `*.bak`.
+        pub fn exclude_files(mut self, patterns: Vec<String>) -> Self {
+            self.scan_options.exclude_patterns = patterns;
+            self
+        }
+
+        pub fn include_www(mut self, value: bool) -> Self {
+            self.include_www = value;
+            self
+        }
+
+        pub fn include_custom_ports(mut self, value: bool) -> Self {
+            self.include_custom_ports = value;
+            self
+        }
+
+        pub fn exclude_http(mut self, value: bool) -> Self {
+            self.exclude_http = value;
+            self
+        }
+
+        pub fn detect_302_redirects(mut self, value: bool) -> Self {
+            self.detect_302_redirects = value;
+            self
+        }
+
+        pub fn include_redirects(mut self, value: bool) -> Self {
+            self.include_redirects = value;
+            self
+        }
+
+        /// Domain masks to skip, e.g. `*.local`.
+        pub fn ignore_list(mut self, masks: Vec<String>) -> Self {
+            self.ignore_list = masks;
+            self
+        }
+
+        /// Domain masks to keep, other domains are skipped. Empty list keeps all domains.
+        pub fn allow_list(mut self, masks: Vec<String>) -> Self {
+            self.allow_list = masks;
+            self
+        }
+
+        pub fn server_names_policy(mut self, policy: ServerNamesPolicy) -> Self {
+            self.server_names_policy = policy;
+            self
+        }
+
+        pub fn port_schemes(mut self, port_schemes: HashMap<i32, Scheme>) -> Self {
+            self.port_schemes = port_schemes;
+            self
+        }
+
+        pub fn known_hostnames(mut self, hostnames: Vec<String>) -> Self {
+            self.known_hostnames = hostnames;
+            self
+        }
+
+        /// Add `{#SERVER}`, `{#CONFIG_FILE}` and `{#CONFIG_LINE}` macros to sites.
+        pub fn include_provenance(mut self, value: bool) -> Self {
+            self.site_format.include_provenance = value;
+            self
+        }
+
+        pub fn include_tags(mut self, value: bool) -> Self {
+            self.site_format.include_tags = value;
+            self
+        }
+
+        /// Additional macros of sites, i.e. `{#DOMAIN}` and `{#PORT}`.
+        pub fn macros(mut self, macros: Vec<SiteMacro>) -> Self {
+            self.site_format.macros = macros;
+            self
+        }
+
+        /// `{#NAME}` template, i.e. `{domain}{port_suffix}`. See `site::DEFAULT_NAME_TEMPLATE`.
+        pub fn name_template(mut self, template: &str) -> Self {
+            self.site_format.name_template = template.to_string();
+            self
+        }
+
+        /// Fail discovery on first config which can't be read or parsed. Otherwise problems are logged
+        /// and virtual hosts from other configs are returned.
+        pub fn strict(mut self, value: bool) -> Self {
+            self.strict = value;
+            self
+        }
+
+        /// LLD macros of discovered sites for these options, see `get_lld_macros`.
+        pub fn get_lld_macros(&self) -> Vec<&'static str> {
+            get_lld_macros(&self.site_format, self.include_redirects, !self.known_hostnames.is_empty())
+        }
+
+        pub fn is_source_enabled(&self, name: &str) -> bool {
+            match &self.sources {
+                Some(sources) => sources.iter().any(|source| source == name),
+                None => true
+            }
+        }
+
+        pub fn get_source_settings(&self, source: &dyn WebServerSource) -> SourceSettings {
+            let vhosts_paths: Vec<PathBuf> = match self.vhosts_paths.get(source.name()) {
+                Some(paths) if !paths.is_empty() => paths.clone(),
+                _ => vec![PathBuf::from(source.default_vhosts_path())]
+            };
+
+            SourceSettings {
+                vhosts_paths,
+                scan_options: self.scan_options.clone(),
+                config_path: self.config_paths.get(source.name()).cloned(),
+                detect_302_redirects: self.detect_302_redirects,
+                include_redirects: self.include_redirects,
+                include_unnamed: false
+            }
+        }
+    }
+
+    /// Discovers and filters virtual hosts of enabled sources. Filtering is done per source,
+    /// so nginx and apache virtual hosts with the same name are both kept.
+    ///
+    /// Problems with single configs are logged and skipped, first one is returned as error in strict mode.
+    pub fn discover_vhosts(options: &DiscoveryOptions) -> Result<Vec<VirtualHost>, DiscoveryError> {
+        let sources = get_enabled_sources(options)?;
+
+        let ignore_list: Vec<&str> = options.ignore_list.iter().map(|mask| mask.as_str()).collect();
+
+        debug!("ignore list '{:?}'", &ignore_list);
+
+        let mut vhosts: Vec<VirtualHost> = Vec::new();
+
+        for source in &sources {
+            let settings = options.get_source_settings(source.as_ref());
+
+            let source_vhosts = discover_source_vhosts(options, source.as_ref(), &settings)?;
+
+            let filtered_vhosts = filter_vhosts(&source_vhosts, options.include_custom_ports, &ignore_list,
+                                                options.server_names_policy, &options.known_hostnames);
+            vhosts.append(&mut get_allowed_vhosts(filtered_vhosts, &options.allow_list));
+        }
+
+        Ok(vhosts)
+    }
+
+    /// Discovers virtual hosts and returns sites for them.
+    pub fn discover_sites(options: &DiscoveryOptions) -> Result<Vec<Site>, DiscoveryError> {
+        let vhosts = discover_vhosts(options)?;
+        Ok(get_sites_from_vhosts(vhosts, options.include_www, options.exclude_http, &options.port_schemes,
+                                 &options.site_format))
+    }
+
+    /// Reads certificate files of discovered https virtual hosts, certificate expiry is calculated for `now`
+    /// unix timestamp. Virtual hosts are the same as for `discover_sites`.
+    pub fn discover_certificates(options: &DiscoveryOptions, now: i64) -> Result<Vec<CertificateReport>, DiscoveryError> {
+        let vhosts: Vec<VirtualHost> = discover_vhosts(options)?.into_iter()
+            .filter(|vhost| get_site_verdict(vhost, options.include_www, options.exclude_http,
+                                             &options.port_schemes).is_kept())
+            .collect();
+
+        Ok(get_certificate_reports(&vhosts, &options.port_schemes, now))
+    }
+
+    /// Returns filtering decision for every candidate virtual host per name and port of enabled sources,
+    /// the same rules as `discover_sites` are applied.
+    pub fn explain_discovery(options: &DiscoveryOptions) -> Result<Vec<Explanation>, DiscoveryError> {
+        let sources = get_enabled_sources(options)?;
+
+        let ignore_list: Vec<&str> = options.ignore_list.iter().map(|mask| mask.as_str()).collect();
+
+        let mut explanations: Vec<Explanation> = Vec::new();
+
+        for source in &sources {
+            // parsers skip redirects unless included, they are requested to be shown as dropped
+            let mut settings = options.get_source_settings(source.as_ref());
+            settings.include_redirects = true;
+
+            let source_vhosts = discover_source_vhosts(options, source.as_ref(), &settings)?;
+
+            let (redirect_vhosts, source_vhosts): (Vec<VirtualHost>, Vec<VirtualHost>) = source_vhosts.into_iter()
+                .partition(|vhost| vhost.redirect.is_some() && !options.include_redirects);
+
+            let redirect_verdicts = get_vhost_verdicts(&redirect_vhosts, true, &Vec::new(),
+                                                       options.server_names_policy, &options.known_hostnames)
+                .into_iter().map(|(vhost, _)| (vhost, Verdict::Redirect));
+
+            let verdicts = get_vhost_verdicts(&source_vhosts, options.include_custom_ports, &ignore_list,
+                                              options.server_names_policy, &options.known_hostnames)
+                .into_iter().map(|(vhost, verdict)| {
+                    let verdict = if !verdict.is_kept() {
+                        verdict
+                    } else if !is_allowed_domain(&vhost.domain, &options.allow_list) {
+                        Verdict::NotAllowed
+                    } else {
+                        get_site_verdict(&vhost, options.include_www, options.exclude_http, &options.port_schemes)
+                    };
+
+                    (vhost, verdict)
+                });
+
+            for (vhost, verdict) in redirect_verdicts.chain(verdicts) {
+                explanations.push(Explanation {
+                    source: source.name().to_string(),
+                    port: vhost.get_port(),
+                    domain: vhost.domain,
+                    verdict,
+                    provenance: vhost.provenance
+                });
+            }
+        }
+
+        Ok(explanations)
+    }
+
+    /// Checks configs of enabled sources. Files which can't be read or parsed are returned as issues,
+    /// error means nothing can be checked, i.e. unknown source is enabled.
+    pub fn check_configs(options: &DiscoveryOptions) -> Result<Vec<Issue>, DiscoveryError> {
+        let sources = get_enabled_sources(options)?;
+
+        let mut issues: Vec<Issue> = Vec::new();
+
+        for source in &sources {
+            let mut settings = options.get_source_settings(source.as_ref());
+            settings.include_redirects = true;
+            settings.include_unnamed = true;
+
+            let mut errors: Vec<DiscoveryError> = Vec::new();
+
+            let vhosts = match source.discover(&settings, &mut errors) {
+                Ok(vhosts) => vhosts,
+                Err(e) => {
+                    errors.push(e);
+                    Vec::new()
+                }
+            };
+
+            let mut source_issues: Vec<Issue> = errors.iter().map(get_error_issue).collect();
+            source_issues.append(&mut check_vhosts(&vhosts, &options.port_schemes));
+            source_issues.sort_by(|issue, other| (&issue.file, issue.line).cmp(&(&other.file, other.line)));
+
+            debug!("{} config issues found for {} source", source_issues.len(), source.name());
+
+            issues.append(&mut source_issues);
+        }
+
+        Ok(issues)
+    }
+
+    fn get_enabled_sources(options: &DiscoveryOptions) -> Result<Vec<Box<dyn WebServerSource>>, DiscoveryError> {
+        let sources = get_web_server_sources();
+
+        if let Some(names) = &options.sources {
+            let source_names: Vec<&str> = sources.iter().map(|source| source.name()).collect();
+
+            for name in names {
+                if !source_names.contains(&name.as_str()) {
+                    error!("available web server sources: {}", source_names.join(","));
+                    return Err(DiscoveryError::UnknownSource { name: name.to_string() })
+                }
+            }
+        }
+
+        Ok(sources.into_iter().filter(|source| {
+            let enabled = options.is_source_enabled(source.name());
+
+            if !enabled {
+                debug!("- {} source is disabled", source.name());
+            }
+
+            enabled
+        }).collect())
+    }
+
+    /// Returns virtual hosts of source. Problems are logged and skipped,
+    /// first one is returned as error in strict mode.
+    fn discover_source_vhosts(options: &DiscoveryOptions, source: &dyn WebServerSource,
+                              settings: &SourceSettings) -> Result<Vec<VirtualHost>, DiscoveryError> {
+        match &settings.config_path {
+            Some(config_path) => debug!("- {} main config: '{}'", source.name(), config_path.display()),
+            None => debug!("- {} vhosts roots: {:?}", source.name(), settings.vhosts_paths)
+        }
+
+        let mut errors: Vec<DiscoveryError> = Vec::new();
+
+        let source_vhosts = match source.discover(settings, &mut errors) {
+            Ok(source_vhosts) => source_vhosts,
+            Err(e) => {
+                errors.push(e);
+                Vec::new()
+            }
+        };
+
+        if !errors.is_empty() {
+            if options.strict {
+                errors.iter().skip(1).for_each(|e| error!("unable to discover {} virtual hosts: {}", source.name(), e));
+                return Err(errors.remove(0))
+            }
+
+            errors.iter().for_each(|e| warn!("unable to discover {} virtual hosts: {}", source.name(), e));
+        }
+
+        Ok(source_vhosts)
+    }
+}
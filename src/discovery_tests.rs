@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod discovery_tests {
+    use crate::discovery::discovery::{discover_sites, discover_vhosts, DiscoveryOptions, explain_discovery};
+    use crate::error::error::DiscoveryError;
+    use crate::explain::explain::Verdict;
+
+    #[test]
+    fn discover_sites_should_use_source_paths_from_options() {
+        let options = DiscoveryOptions::new()
+            .vhosts_path("nginx", "tests/nginx-vhosts")
+            .vhosts_path("apache", "tests/apache-vhosts")
+            .include_custom_ports(true);
+
+        let sites = discover_sites(&options).unwrap();
+
+        let urls: Vec<&str> = sites.iter().map(|site| site.url.as_str()).collect();
+
+        assert!(urls.contains(&"http://gallery.whatever.ru:23512"));
+        assert!(urls.contains(&"http://demo.company.ru:1480"));
+    }
+
+    #[test]
+    fn discover_vhosts_should_skip_disabled_sources() {
+        let options = DiscoveryOptions::new()
+            .sources(&["apache"])
+            .vhosts_path("nginx", "tests/nginx-vhosts")
+            .config_path("apache", "tests/apache-main/apache2.conf");
+
+        let vhosts = discover_vhosts(&options).unwrap();
+
+        assert!(!vhosts.is_empty());
+        assert!(vhosts.iter().all(|vhost| vhost.domain != "gallery.whatever.ru"));
+    }
+
+    #[test]
+    fn discover_vhosts_should_return_error_for_unknown_source() {
+        let options = DiscoveryOptions::new().sources(&["caddy"]);
+
+        match discover_vhosts(&options) {
+            Err(e) => assert_eq!(e, DiscoveryError::UnknownSource { name: "caddy".to_string() }),
+            Ok(_) => panic!("unknown source error expected")
+        }
+    }
+
+    #[test]
+    fn discover_vhosts_should_fail_on_broken_config_in_strict_mode() {
+        let options = DiscoveryOptions::new()
+            .sources(&["nginx"])
+            .vhosts_path("nginx", "tests/nginx-broken")
+            .strict(true);
+
+        assert!(discover_vhosts(&options).is_err());
+    }
+
+    #[test]
+    fn discover_vhosts_should_skip_broken_config_by_default() {
+        let options = DiscoveryOptions::new()
+            .vhosts_path("nginx", "tests/nginx-broken")
+            .config_path("apache", "tests/missing.conf");
+
+        let vhosts = discover_vhosts(&options).unwrap();
+
+        let domains: Vec<&str> = vhosts.iter().map(|vhost| vhost.domain.as_str()).collect();
+        assert_eq!(domains, vec!["site.company.ru"]);
+    }
+
+    #[test]
+    fn explain_discovery_should_return_verdict_for_every_candidate_vhost() {
+        let options = DiscoveryOptions::new()
+            .sources(&["nginx"])
+            .vhosts_path("nginx", "tests/nginx-vhosts");
+
+        let explanations = explain_discovery(&options).unwrap();
+
+        let verdicts: Vec<(String, i32, Verdict)> = explanations.iter()
+            .map(|explanation| (explanation.domain.to_string(), explanation.port, explanation.verdict.clone()))
+            .collect();
+
+        assert!(verdicts.contains(&("whatever.ru".to_string(), 80, Verdict::Redirect)));
+        assert!(verdicts.contains(&("www.whatever.ru".to_string(), 443, Verdict::Redirect)));
+        assert!(verdicts.contains(&("whatever.ru".to_string(), 443, Verdict::Kept)));
+        assert!(verdicts.contains(&("gallery.whatever.ru".to_string(), 23512, Verdict::CustomPort)));
+
+        assert!(explanations.iter().all(|explanation| explanation.source == "nginx"));
+    }
+
+    #[test]
+    fn explain_discovery_should_keep_the_same_vhosts_as_discovery() {
+        let options = DiscoveryOptions::new()
+            .vhosts_path("nginx", "tests/nginx-vhosts")
+            .vhosts_path("apache", "tests/apache-vhosts")
+            .include_redirects(true)
+            .ignore_list(vec!["demo.*".to_string()]);
+
+        let sites = discover_sites(&options).unwrap();
+
+        let kept_count = explain_discovery(&options).unwrap().iter()
+            .filter(|explanation| explanation.verdict.is_kept()).count();
+
+        assert_eq!(kept_count, sites.len());
+    }
+}
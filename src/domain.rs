@@ -1,15 +1,286 @@
 pub mod domain {
+    use std::collections::BTreeMap;
+    use std::fmt;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+
+    use regex::Regex;
     use serde::Serialize;
+    use wildmatch::WildMatch;
+
+    use crate::{DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT};
+
+    const REGEX_NAME_PREFIX: &str = "~";
+
+    /// Kind of virtual host name.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum NameKind {
+        /// `example.com`
+        Exact,
+        /// `*.example.com`, `www.example.*`
+        Wildcard,
+        /// `~^(?<sub>.+)\.example\.com$`
+        Regex,
+        /// `_` or empty name, matches any host
+        CatchAll,
+        Invalid
+    }
+
+    pub fn get_name_kind(name: &str) -> NameKind {
+        if name.is_empty() || name == "_" {
+            return NameKind::CatchAll
+        }
+
+        if name.starts_with(REGEX_NAME_PREFIX) {
+            return NameKind::Regex
+        }
+
+        let valid_chars = name.chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '.' || ch == '-' || ch == '*' || ch == '?');
+
+        if !valid_chars {
+            NameKind::Invalid
+        } else if name.contains(&['*', '?'][..]) {
+            NameKind::Wildcard
+        } else {
+            NameKind::Exact
+        }
+    }
+
+    /// Checks host name against wildcard or regex (`~` prefixed) name.
+    pub fn name_pattern_matches(pattern: &str, host: &str) -> bool {
+        match pattern.strip_prefix(REGEX_NAME_PREFIX) {
+            Some(regex_value) => {
+                // PCRE named groups `(?<name>)` aren't supported by regex crate
+                let regex_value = regex_value.replace("(?<", "(?P<");
+
+                match Regex::new(&format!("(?i){}", regex_value)) {
+                    Ok(regex) => regex.is_match(host),
+                    Err(e) => {
+                        error!("unable to parse server name regex '{}': {}", pattern, e);
+                        false
+                    }
+                }
+            }
+            None => WildMatch::new(&pattern.to_lowercase()).is_match(&host.to_lowercase())
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Scheme {
+        Http,
+        Https
+    }
+
+    impl Scheme {
+        pub fn as_str(&self) -> &str {
+            match self {
+                Scheme::Http => "http",
+                Scheme::Https => "https"
+            }
+        }
+
+        pub fn get_default_port(&self) -> i32 {
+            match self {
+                Scheme::Http => DEFAULT_HTTP_PORT,
+                Scheme::Https => DEFAULT_HTTPS_PORT
+            }
+        }
+    }
+
+    impl FromStr for Scheme {
+        type Err = String;
+
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            match value.to_lowercase().as_str() {
+                "http" => Ok(Scheme::Http),
+                "https" => Ok(Scheme::Https),
+                _ => Err(format!("unsupported scheme '{}'", value))
+            }
+        }
+    }
+
+    /// Part of virtual host affected by redirect.
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum RedirectScope {
+        /// All requests are redirected
+        VirtualHost,
+        /// Only requests matched by location, condition or pattern are redirected
+        Location(String)
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Redirect {
+        pub code: i32,
+        pub target: String,
+        pub scope: RedirectScope
+    }
+
+    impl Redirect {
+        pub fn is_permanent(&self) -> bool {
+            self.code == 301 || self.code == 308
+        }
+    }
+
+    impl fmt::Display for Redirect {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match &self.scope {
+                RedirectScope::VirtualHost => write!(f, "{} {}", self.code, self.target),
+                RedirectScope::Location(location) => write!(f, "{} {} ({})", self.code, self.target, location)
+            }
+        }
+    }
+
+    /// TLS directives of virtual host.
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct TlsConfig {
+        /// `ssl on|off` for nginx, `SSLEngine on|off` for apache
+        pub engine: Option<bool>,
+        /// `ssl_certificate` for nginx, `SSLCertificateFile` for apache
+        pub certificate_files: Vec<String>
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct ListenEndpoint {
+        /// Listen address, `None` means all addresses
+        pub address: Option<String>,
+        pub port: i32,
+        pub ipv6: bool,
+        pub default_server: bool,
+        pub ssl: bool,
+        pub http2: bool,
+        pub quic: bool
+    }
+
+    impl ListenEndpoint {
+        pub fn new(port: i32) -> ListenEndpoint {
+            ListenEndpoint {
+                address: None, port, ipv6: false, default_server: false, ssl: false, http2: false, quic: false
+            }
+        }
+    }
+
+    impl fmt::Display for ListenEndpoint {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let mut value = match &self.address {
+                Some(address) if self.ipv6 => format!("[{}]:{}", address, self.port),
+                Some(address) => format!("{}:{}", address, self.port),
+                None => self.port.to_string()
+            };
+
+            let flags = [(self.default_server, "default_server"), (self.ssl, "ssl"),
+                         (self.http2, "http2"), (self.quic, "quic")];
+
+            for (enabled, flag) in flags.iter() {
+                if *enabled {
+                    value.push(' ');
+                    value.push_str(flag);
+                }
+            }
+
+            write!(f, "{}", value)
+        }
+    }
+
+    /// Where virtual host is defined. Listen and name values are kept as written in config.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Provenance {
+        /// Source name, i.e. `nginx`
+        pub server: String,
+        pub file: PathBuf,
+        /// Line of `server` block or `<VirtualHost>` section
+        pub line: usize,
+        /// `listen` directive values or `<VirtualHost>` addresses
+        pub listen: Vec<String>,
+        /// `server_name` values or `ServerName` and `ServerAlias` values
+        pub server_names: Vec<String>
+    }
+
+    impl fmt::Display for Provenance {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{} {}:{}", self.server, self.file.display(), self.line)
+        }
+    }
 
     #[derive(Clone)]
     pub struct VirtualHost {
+        /// Primary name, may be wildcard or regex name until names are expanded
         pub domain: String,
-        pub port: i32
+        pub aliases: Vec<String>,
+        pub listens: Vec<ListenEndpoint>,
+        pub tls: TlsConfig,
+        /// Wildcard or regex name the domain was expanded from
+        pub wildcard: Option<String>,
+        /// Redirect of all requests, such virtual hosts are kept only if redirects are included
+        pub redirect: Option<Redirect>,
+        pub provenance: Option<Provenance>,
+        /// Values of `# site-discovery-flea: key=value` config comments, i.e. `env` and `owner`
+        pub annotations: BTreeMap<String, String>
     }
 
     impl VirtualHost {
+        pub fn new(domain: &str, port: i32) -> VirtualHost {
+            VirtualHost {
+                domain: domain.to_string(), aliases: Vec::new(),
+                listens: vec![ListenEndpoint::new(port)], tls: TlsConfig::default(), wildcard: None,
+                redirect: None, provenance: None, annotations: BTreeMap::new()
+            }
+        }
+
+        pub fn is_tls_enabled(&self) -> bool {
+            self.listens.iter().any(|listen| listen.ssl)
+        }
+
+        /// Primary name followed by aliases.
+        pub fn get_names(&self) -> Vec<String> {
+            let mut names = vec![self.domain.to_string()];
+            names.extend(self.aliases.iter().cloned());
+            names
+        }
+
+        /// Unique ports in order of listen directives.
+        pub fn get_ports(&self) -> Vec<i32> {
+            let mut ports: Vec<i32> = Vec::new();
+
+            for listen in &self.listens {
+                if !ports.contains(&listen.port) {
+                    ports.push(listen.port);
+                }
+            }
+
+            ports
+        }
+
+        /// Port of the first listen endpoint. Virtual hosts are split per port before filtering,
+        /// so after that all endpoints have the same port.
+        pub fn get_port(&self) -> i32 {
+            self.listens.first().map(|listen| listen.port).unwrap_or_default()
+        }
+
         pub fn to_string(&self) -> String {
-            return String::from(format!("domain: {}, port: {}", self.domain, self.port));
+            let listens: Vec<String> = self.listens.iter().map(|listen| listen.to_string()).collect();
+
+            let mut value = String::from(format!("domain: {}", self.domain));
+
+            if !self.aliases.is_empty() {
+                value.push_str(&format!(", aliases: {}", self.aliases.join(" ")));
+            }
+
+            value.push_str(&format!(", listen: {}", listens.join(", ")));
+
+            if !self.tls.certificate_files.is_empty() {
+                value.push_str(&format!(", certificate: {}", self.tls.certificate_files.join(" ")));
+            }
+
+            if let Some(redirect) = &self.redirect {
+                value.push_str(&format!(", redirect: {}", redirect));
+            }
+
+            if let Some(provenance) = &self.provenance {
+                value.push_str(&format!(", source: {}", provenance));
+            }
+
+            return value;
         }
     }
 
@@ -19,5 +290,39 @@ pub mod domain {
         pub name: String,
         #[serde(rename(serialize = "{#URL}"))]
         pub url: String,
+        #[serde(rename(serialize = "{#WILDCARD}"), skip_serializing_if = "Option::is_none")]
+        pub wildcard: Option<String>,
+        /// `redirect` for virtual hosts which redirect all requests
+        #[serde(rename(serialize = "{#TYPE}"), skip_serializing_if = "Option::is_none")]
+        pub site_type: Option<String>,
+        #[serde(rename(serialize = "{#REDIRECT_TARGET}"), skip_serializing_if = "Option::is_none")]
+        pub redirect_target: Option<String>,
+        #[serde(rename(serialize = "{#REDIRECT_CODE}"), skip_serializing_if = "Option::is_none")]
+        pub redirect_code: Option<String>,
+        /// Source name, set if provenance macros are included
+        #[serde(rename(serialize = "{#SERVER}"), skip_serializing_if = "Option::is_none")]
+        pub server: Option<String>,
+        #[serde(rename(serialize = "{#CONFIG_FILE}"), skip_serializing_if = "Option::is_none")]
+        pub config_file: Option<String>,
+        #[serde(rename(serialize = "{#CONFIG_LINE}"), skip_serializing_if = "Option::is_none")]
+        pub config_line: Option<String>,
+        #[serde(rename(serialize = "{#DOMAIN}"), skip_serializing_if = "Option::is_none")]
+        pub domain_macro: Option<String>,
+        #[serde(rename(serialize = "{#PORT}"), skip_serializing_if = "Option::is_none")]
+        pub port_macro: Option<String>,
+        #[serde(rename(serialize = "{#SCHEME}"), skip_serializing_if = "Option::is_none")]
+        pub scheme_macro: Option<String>,
+        #[serde(rename(serialize = "{#ALIASES}"), skip_serializing_if = "Option::is_none")]
+        pub aliases_macro: Option<String>,
+        /// `{#TAG_*}` macros by macro name, set if tags are included
+        #[serde(flatten)]
+        pub tags: BTreeMap<String, String>,
+        /// Values below aren't macros, they are used by other output formats
+        #[serde(skip)]
+        pub domain: String,
+        #[serde(skip)]
+        pub scheme: Scheme,
+        #[serde(skip)]
+        pub port: i32
     }
 }
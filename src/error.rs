@@ -0,0 +1,168 @@
+pub mod error {
+    use std::{error, fmt, io};
+    use std::path::{Path, PathBuf};
+
+    use crate::webserver::webserver::ParseError;
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum DiscoveryError {
+        /// Config file or directory can't be read because of permissions
+        PermissionDenied { path: PathBuf },
+        /// Config file or directory doesn't exist or can't be read for other reason
+        UnreadableFile { path: PathBuf, message: String },
+        ParseError { file: PathBuf, line: usize, message: String },
+        InvalidPort { file: PathBuf, line: usize, value: String },
+        UnknownSource { name: String },
+        /// Discovery config file has invalid syntax or values
+        InvalidConfig { path: PathBuf, message: String }
+    }
+
+    impl DiscoveryError {
+        pub fn from_io_error(path: &Path, error: &io::Error) -> DiscoveryError {
+            match error.kind() {
+                io::ErrorKind::PermissionDenied => DiscoveryError::PermissionDenied { path: path.to_path_buf() },
+                _ => DiscoveryError::UnreadableFile { path: path.to_path_buf(), message: error.to_string() }
+            }
+        }
+    }
+
+    impl fmt::Display for DiscoveryError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                DiscoveryError::PermissionDenied { path } =>
+                    write!(f, "permission denied for '{}'", path.display()),
+                DiscoveryError::UnreadableFile { path, message } =>
+                    write!(f, "unable to read '{}': {}", path.display(), message),
+                DiscoveryError::ParseError { file, line, message } =>
+                    write!(f, "{}:{}: {}", file.display(), line, message),
+                DiscoveryError::InvalidPort { file, line, value } =>
+                    write!(f, "{}:{}: invalid port '{}'", file.display(), line, value),
+                DiscoveryError::UnknownSource { name } =>
+                    write!(f, "unknown web server source '{}'", name),
+                DiscoveryError::InvalidConfig { path, message } =>
+                    write!(f, "invalid config '{}': {}", path.display(), message)
+            }
+        }
+    }
+
+    impl error::Error for DiscoveryError {}
+
+    impl From<ParseError> for DiscoveryError {
+        fn from(error: ParseError) -> Self {
+            DiscoveryError::ParseError { file: error.file, line: error.line, message: error.message }
+        }
+    }
+
+    /// Problems of sending data to Zabbix server or proxy trapper.
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum SenderError {
+        /// Server can't be reached or connection is broken
+        Connection { address: String, message: String },
+        /// Server response isn't Zabbix protocol packet with JSON
+        InvalidResponse { message: String },
+        /// Server didn't accept request, i.e. host or item doesn't exist
+        Rejected { info: String }
+    }
+
+    impl fmt::Display for SenderError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                SenderError::Connection { address, message } =>
+                    write!(f, "unable to send data to '{}': {}", address, message),
+                SenderError::InvalidResponse { message } =>
+                    write!(f, "invalid zabbix server response: {}", message),
+                SenderError::Rejected { info } =>
+                    write!(f, "data rejected by zabbix server: {}", info)
+            }
+        }
+    }
+
+    impl error::Error for SenderError {}
+
+    /// Problems of Zabbix JSON-RPC API calls.
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum ZabbixApiError {
+        /// API url isn't supported, i.e. plain http to remote frontend
+        InvalidUrl { url: String, message: String },
+        /// Frontend can't be reached or connection is broken
+        Connection { url: String, message: String },
+        /// Frontend answered with non 200 status
+        HttpStatus { url: String, status: u16 },
+        /// Response isn't HTTP or JSON-RPC response
+        InvalidResponse { message: String },
+        /// JSON-RPC error, i.e. invalid token or missing permissions
+        Api { method: String, code: i64, message: String, data: String },
+        HostNotFound { host: String }
+    }
+
+    impl fmt::Display for ZabbixApiError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ZabbixApiError::InvalidUrl { url, message } =>
+                    write!(f, "invalid zabbix api url '{}': {}", url, message),
+                ZabbixApiError::Connection { url, message } =>
+                    write!(f, "unable to call zabbix api '{}': {}", url, message),
+                ZabbixApiError::HttpStatus { url, status } =>
+                    write!(f, "zabbix api '{}' returned http status {}", url, status),
+                ZabbixApiError::InvalidResponse { message } =>
+                    write!(f, "invalid zabbix api response: {}", message),
+                ZabbixApiError::Api { method, code, message, data } =>
+                    write!(f, "zabbix api method '{}' failed ({}): {} {}", method, code, message, data),
+                ZabbixApiError::HostNotFound { host } =>
+                    write!(f, "host '{}' isn't found in zabbix", host)
+            }
+        }
+    }
+
+    impl error::Error for ZabbixApiError {}
+
+    /// Problems of running HTTP probes, failed requests are probe results.
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum ProbeError {
+        /// Address to connect to instead of site domain can't be resolved
+        InvalidAddress { address: String, message: String },
+        /// Certificate verification can't be set up
+        Tls { message: String }
+    }
+
+    impl fmt::Display for ProbeError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ProbeError::InvalidAddress { address, message } =>
+                    write!(f, "unable to resolve address '{}': {}", address, message),
+                ProbeError::Tls { message } =>
+                    write!(f, "unable to set up TLS: {}", message)
+            }
+        }
+    }
+
+    impl error::Error for ProbeError {}
+
+    /// Problems of certificate file, they are reported per virtual host.
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum CertificateError {
+        UnreadableFile { path: PathBuf, message: String },
+        /// File has no PEM certificates
+        MissingCertificate { path: PathBuf },
+        InvalidCertificate { path: PathBuf, message: String },
+        /// Path contains nginx variables or inline `data:` value
+        UnsupportedPath { path: String }
+    }
+
+    impl fmt::Display for CertificateError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                CertificateError::UnreadableFile { path, message } =>
+                    write!(f, "unable to read '{}': {}", path.display(), message),
+                CertificateError::MissingCertificate { path } =>
+                    write!(f, "no PEM certificates in '{}'", path.display()),
+                CertificateError::InvalidCertificate { path, message } =>
+                    write!(f, "invalid certificate in '{}': {}", path.display(), message),
+                CertificateError::UnsupportedPath { path } =>
+                    write!(f, "unsupported certificate path '{}'", path)
+            }
+        }
+    }
+
+    impl error::Error for CertificateError {}
+}
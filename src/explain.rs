@@ -0,0 +1,97 @@
+pub mod explain {
+    use std::fmt;
+
+    use crate::domain::domain::Provenance;
+
+    /// Filtering decision for candidate virtual host, the first rule which dropped it.
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Verdict {
+        Kept,
+        /// Domain matches ignore list mask
+        Ignored(String),
+        /// Domain doesn't match any allow list mask
+        NotAllowed,
+        /// Port isn't 80 or 443 and custom ports aren't included
+        CustomPort,
+        /// Same name and port was added before
+        Duplicate,
+        /// Http virtual host of name with https virtual host
+        SupersededByHttps,
+        /// Whole virtual host is redirect and redirects aren't included
+        Redirect,
+        /// Name starts with `www.` and such names aren't included
+        WwwPolicy,
+        /// Http site and http sites are excluded
+        HttpExcluded,
+        /// Wildcard or regex name doesn't match any known host name
+        NoKnownHostnames
+    }
+
+    impl Verdict {
+        pub fn is_kept(&self) -> bool {
+            *self == Verdict::Kept
+        }
+    }
+
+    impl fmt::Display for Verdict {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Verdict::Kept => write!(f, "kept"),
+                Verdict::Ignored(mask) => write!(f, "dropped: ignored by pattern '{}'", mask),
+                Verdict::NotAllowed => write!(f, "dropped: not in allow list"),
+                Verdict::CustomPort => write!(f, "dropped: custom port"),
+                Verdict::Duplicate => write!(f, "dropped: duplicate name and port"),
+                Verdict::SupersededByHttps => write!(f, "dropped: superseded by https"),
+                Verdict::Redirect => write!(f, "dropped: redirect"),
+                Verdict::WwwPolicy => write!(f, "dropped: www name"),
+                Verdict::HttpExcluded => write!(f, "dropped: http excluded"),
+                Verdict::NoKnownHostnames => write!(f, "dropped: no known host names match")
+            }
+        }
+    }
+
+    /// Candidate virtual host per name and port with filtering decision.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Explanation {
+        pub source: String,
+        pub domain: String,
+        pub port: i32,
+        pub verdict: Verdict,
+        pub provenance: Option<Provenance>
+    }
+
+    const TABLE_HEADERS: [&str; 5] = ["SOURCE", "DOMAIN", "PORT", "CONFIG", "VERDICT"];
+
+    /// Returns explanations as table with row per candidate virtual host, columns are aligned with spaces.
+    pub fn get_explanation_table(explanations: &[Explanation]) -> String {
+        let mut rows: Vec<Vec<String>> = vec![TABLE_HEADERS.iter().map(|header| header.to_string()).collect()];
+
+        for explanation in explanations {
+            let config = match &explanation.provenance {
+                Some(provenance) => format!("{}:{}", provenance.file.display(), provenance.line),
+                None => "-".to_string()
+            };
+
+            rows.push(vec![
+                explanation.source.to_string(), explanation.domain.to_string(), explanation.port.to_string(),
+                config, explanation.verdict.to_string()
+            ]);
+        }
+
+        let mut widths: Vec<usize> = vec![0; TABLE_HEADERS.len()];
+
+        for row in &rows {
+            for (index, value) in row.iter().enumerate() {
+                widths[index] = widths[index].max(value.chars().count());
+            }
+        }
+
+        let lines: Vec<String> = rows.iter().map(|row| {
+            let line: Vec<String> = row.iter().enumerate()
+                .map(|(index, value)| format!("{:width$}", value, width = widths[index])).collect();
+            line.join("  ").trim_end().to_string()
+        }).collect();
+
+        lines.join("\n")
+    }
+}
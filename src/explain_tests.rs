@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod explain_tests {
+    use std::path::PathBuf;
+
+    use crate::domain::domain::Provenance;
+    use crate::explain::explain::{Explanation, get_explanation_table, Verdict};
+
+    #[test]
+    fn explanation_table_should_have_aligned_columns() {
+        let provenance = Provenance {
+            server: "nginx".to_string(), file: PathBuf::from("/etc/nginx/conf.d/app.conf"), line: 12,
+            listen: vec!["443 ssl".to_string()], server_names: vec!["app.company.ru".to_string()]
+        };
+
+        let explanations = vec![
+            Explanation {
+                source: "nginx".to_string(), domain: "app.company.ru".to_string(), port: 443,
+                verdict: Verdict::Kept, provenance: Some(provenance)
+            },
+            Explanation {
+                source: "apache".to_string(), domain: "test.local".to_string(), port: 8080,
+                verdict: Verdict::Ignored("*.local".to_string()), provenance: None
+            }
+        ];
+
+        let expected_table = "\
+SOURCE  DOMAIN          PORT  CONFIG                         VERDICT
+nginx   app.company.ru  443   /etc/nginx/conf.d/app.conf:12  kept
+apache  test.local      8080  -                              dropped: ignored by pattern '*.local'";
+
+        assert_eq!(get_explanation_table(&explanations), expected_table);
+    }
+}
@@ -1,55 +1,190 @@
 pub mod filter {
+    use std::str::FromStr;
+
     use crate::{DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT};
-    use crate::domain::domain::VirtualHost;
+    use crate::domain::domain::{get_name_kind, name_pattern_matches, NameKind, VirtualHost};
+    use crate::explain::explain::Verdict;
     use wildmatch::WildMatch;
 
-    pub fn filter_vhosts(vhosts: &Vec<VirtualHost>, include_custom_domains: bool, ignore_list: &Vec<&str>) -> Vec<VirtualHost> {
+    /// Which names of virtual host become sites.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum ServerNamesPolicy {
+        /// First `server_name` or `ServerName` value only
+        Primary,
+        /// Primary name and all aliases
+        All
+    }
+
+    impl FromStr for ServerNamesPolicy {
+        type Err = String;
+
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            match value.to_lowercase().as_str() {
+                "primary" => Ok(ServerNamesPolicy::Primary),
+                "all" => Ok(ServerNamesPolicy::All),
+                _ => Err(format!("unsupported server names policy '{}'", value))
+            }
+        }
+    }
+
+    pub fn filter_vhosts(vhosts: &Vec<VirtualHost>, include_custom_domains: bool, ignore_list: &Vec<&str>,
+                         server_names_policy: ServerNamesPolicy, known_hostnames: &Vec<String>) -> Vec<VirtualHost> {
+        get_vhost_verdicts(vhosts, include_custom_domains, ignore_list, server_names_policy, known_hostnames)
+            .into_iter().filter(|(_, verdict)| verdict.is_kept()).map(|(vhost, _)| vhost).collect()
+    }
+
+    /// Returns every candidate virtual host per name and port with filtering decision,
+    /// kept ones are in `filter_vhosts` order.
+    pub fn get_vhost_verdicts(vhosts: &Vec<VirtualHost>, include_custom_domains: bool, ignore_list: &Vec<&str>,
+                              server_names_policy: ServerNamesPolicy,
+                              known_hostnames: &Vec<String>) -> Vec<(VirtualHost, Verdict)> {
+        let mut verdicts: Vec<(VirtualHost, Verdict)> = Vec::new();
         let mut results: Vec<VirtualHost> = Vec::new();
-        let mut results_dedup: Vec<VirtualHost> = Vec::new();
 
-        for vhost in vhosts {
-            if vhost_add_permitted(vhost, &results, include_custom_domains, &ignore_list) {
+        let (vhosts_per_name, unmatched_vhosts) = get_vhosts_for_names_with_unmatched(
+            vhosts, server_names_policy, known_hostnames);
+
+        for vhost in get_vhosts_per_port(&vhosts_per_name) {
+            let verdict = get_vhost_add_verdict(&vhost, &results, include_custom_domains, ignore_list);
+
+            if verdict.is_kept() {
                 debug!("+ add vhost '{}'", vhost.to_string());
                 results.push(vhost.to_owned());
+            } else {
+                debug!("- skip vhost '{}' - {}", vhost.to_string(), verdict);
             }
+
+            verdicts.push((vhost, verdict));
         }
 
-        for result in &results {
-            if result.port == DEFAULT_HTTP_PORT && vec_contains_same_domain_with_https_port(&results, &result.domain) {
-                debug!("- remove vhost '{}' - has both 80 and 443", result.to_string());
-                continue;
+        for (vhost, verdict) in verdicts.iter_mut() {
+            // http redirect to https is kept, it's what redirect checks are for
+            if verdict.is_kept() && vhost.get_port() == DEFAULT_HTTP_PORT && vhost.redirect.is_none() &&
+               vec_contains_same_domain_with_https_port(&results, &vhost.domain) {
+                debug!("- remove vhost '{}' - has both 80 and 443", vhost.to_string());
+                *verdict = Verdict::SupersededByHttps;
             }
-            results_dedup.push(result.to_owned());
         }
 
-        return results_dedup
+        for vhost in get_vhosts_per_port(&unmatched_vhosts) {
+            verdicts.push((vhost, Verdict::NoKnownHostnames));
+        }
+
+        verdicts
     }
 
-    fn vhost_add_permitted(vhost: &VirtualHost, buffer: &Vec<VirtualHost>,
-                           include_custom_ports: bool, ignore_list: &Vec<&str>) -> bool {
-        let mut permitted = false;
+    /// Keeps virtual hosts which domains match any of allow list masks. Empty list keeps all.
+    pub fn get_allowed_vhosts(vhosts: Vec<VirtualHost>, allow_list: &[String]) -> Vec<VirtualHost> {
+        if allow_list.is_empty() {
+            return vhosts
+        }
 
-        if vhost_not_in_ignore_list(&vhost.domain, &ignore_list) {
-            if include_custom_ports {
-                if !vec_contains_same_domain_with_port(buffer, &vhost.domain, vhost.port) {
-                    permitted = true;
-                }
+        vhosts.into_iter().filter(|vhost| {
+            let allowed = is_allowed_domain(&vhost.domain, allow_list);
 
-            } else {
-                if vhost_has_standard_port(vhost.port) {
-                    if !vec_contains_same_domain_with_port(buffer, &vhost.domain, vhost.port) {
-                        permitted = true;
+            if !allowed {
+                debug!("- skip vhost '{}', not in allow list", vhost.domain);
+            }
+
+            allowed
+        }).collect()
+    }
+
+    /// Empty allow list allows any domain.
+    pub fn is_allowed_domain(domain: &str, allow_list: &[String]) -> bool {
+        allow_list.is_empty() || allow_list.iter().any(|mask| WildMatch::new(mask).is_match(domain))
+    }
+
+    /// Returns virtual host per name. Each result keeps other names of the same block as aliases.
+    /// Wildcard and regex names are replaced with matched known host names or skipped.
+    pub fn get_vhosts_for_names(vhosts: &Vec<VirtualHost>, server_names_policy: ServerNamesPolicy,
+                                known_hostnames: &Vec<String>) -> Vec<VirtualHost> {
+        get_vhosts_for_names_with_unmatched(vhosts, server_names_policy, known_hostnames).0
+    }
+
+    /// Same as `get_vhosts_for_names`, also returns virtual hosts for wildcard and regex names
+    /// without matched known host names.
+    fn get_vhosts_for_names_with_unmatched(vhosts: &Vec<VirtualHost>, server_names_policy: ServerNamesPolicy,
+                                           known_hostnames: &Vec<String>) -> (Vec<VirtualHost>, Vec<VirtualHost>) {
+        let mut results: Vec<VirtualHost> = Vec::new();
+        let mut unmatched: Vec<VirtualHost> = Vec::new();
+
+        for vhost in vhosts {
+            let names = vhost.get_names();
+
+            let selected_names: Vec<&String> = match server_names_policy {
+                ServerNamesPolicy::Primary => names.iter().take(1).collect(),
+                ServerNamesPolicy::All => names.iter().collect()
+            };
+
+            for name in selected_names {
+                match get_name_kind(name) {
+                    NameKind::Wildcard | NameKind::Regex => {
+                        let hostnames: Vec<&String> = known_hostnames.iter()
+                            .filter(|hostname| name_pattern_matches(name, hostname)).collect();
+
+                        if hostnames.is_empty() {
+                            debug!("- skip wildcard name '{}', no known host names match", name);
+                            unmatched.push(get_vhost_for_name(vhost, name, &names, Some(name)));
+                        }
+
+                        for hostname in hostnames {
+                            debug!("wildcard name '{}' expanded to '{}'", name, hostname);
+                            results.push(get_vhost_for_name(vhost, hostname, &names, Some(name)));
+                        }
                     }
+                    _ => results.push(get_vhost_for_name(vhost, name, &names, None))
                 }
             }
         }
 
-        permitted
+        (results, unmatched)
+    }
+
+    fn get_vhost_for_name(vhost: &VirtualHost, name: &str, names: &Vec<String>,
+                          wildcard: Option<&String>) -> VirtualHost {
+        let mut name_vhost = vhost.to_owned();
+        name_vhost.domain = name.to_string();
+        name_vhost.aliases = names.iter().filter(|other_name| *other_name != name).cloned().collect();
+        name_vhost.wildcard = wildcard.cloned();
+        name_vhost
+    }
+
+    /// Splits virtual host with several listen directives into virtual host per port.
+    /// Endpoints with the same port (i.e. IPv4 and IPv6 addresses) are kept together.
+    pub fn get_vhosts_per_port(vhosts: &Vec<VirtualHost>) -> Vec<VirtualHost> {
+        let mut results: Vec<VirtualHost> = Vec::new();
+
+        for vhost in vhosts {
+            for port in vhost.get_ports() {
+                let mut port_vhost = vhost.to_owned();
+                port_vhost.listens.retain(|listen| listen.port == port);
+                results.push(port_vhost);
+            }
+        }
+
+        results
+    }
+
+    fn get_vhost_add_verdict(vhost: &VirtualHost, buffer: &Vec<VirtualHost>,
+                             include_custom_ports: bool, ignore_list: &Vec<&str>) -> Verdict {
+        if let Some(mask) = get_ignore_mask(&vhost.domain, ignore_list) {
+            return Verdict::Ignored(mask.to_string())
+        }
+
+        if !include_custom_ports && !vhost_has_standard_port(vhost.get_port()) {
+            return Verdict::CustomPort
+        }
+
+        if vec_contains_same_domain_with_port(buffer, &vhost.domain, vhost.get_port()) {
+            return Verdict::Duplicate
+        }
+
+        Verdict::Kept
     }
 
-    fn vhost_not_in_ignore_list(domain: &String, ignore_list: &Vec<&str>) -> bool {
-        ignore_list.iter()
-            .find(|ignore| WildMatch::new(ignore).is_match(domain)).is_none()
+    fn get_ignore_mask<'a>(domain: &String, ignore_list: &Vec<&'a str>) -> Option<&'a str> {
+        ignore_list.iter().find(|ignore| WildMatch::new(ignore).is_match(domain)).copied()
     }
 
     fn vhost_has_standard_port(port: i32) -> bool {
@@ -59,12 +194,12 @@ pub mod filter {
     fn vec_contains_same_domain_with_port(vhosts: &Vec<VirtualHost>,
                                               domain: &String, port: i32) -> bool {
         vhosts.iter()
-              .find(|vhost| &vhost.domain == domain && vhost.port == port).is_some()
+              .find(|vhost| &vhost.domain == domain && vhost.get_port() == port).is_some()
     }
 
     fn vec_contains_same_domain_with_https_port(vhosts: &Vec<VirtualHost>,
                                                 domain: &String) -> bool {
         vhosts.iter()
-            .find(|vhost| &vhost.domain == domain && vhost.port == DEFAULT_HTTPS_PORT).is_some()
+            .find(|vhost| &vhost.domain == domain && vhost.get_port() == DEFAULT_HTTPS_PORT).is_some()
     }
 }
@@ -1,32 +1,34 @@
 #[cfg(test)]
 mod filter_tests {
     use crate::{DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT};
-    use crate::domain::domain::VirtualHost;
-    use crate::filter::filter::filter_vhosts;
+    use crate::domain::domain::{ListenEndpoint, Redirect, RedirectScope, VirtualHost};
+    use crate::explain::explain::Verdict;
+    use crate::filter::filter::{filter_vhosts, get_allowed_vhosts, get_vhost_verdicts, get_vhosts_per_port,
+                                ServerNamesPolicy};
 
     const DOMAIN: &str = "cronbox.ru";
     const DOMAIN2: &str = "dfov.ru";
 
     #[test]
     fn result_without_custom_ports_should_contain_only_http_or_https_ports() {
-        let vhost1 = VirtualHost { domain: DOMAIN.to_string(), port: 7435 };
-        let vhost2 = VirtualHost { domain: DOMAIN2.to_string(), port: DEFAULT_HTTP_PORT };
-        let vhost3 = VirtualHost { domain: DOMAIN.to_string(), port: DEFAULT_HTTPS_PORT };
+        let vhost1 = VirtualHost::new(DOMAIN, 7435);
+        let vhost2 = VirtualHost::new(DOMAIN2, DEFAULT_HTTP_PORT);
+        let vhost3 = VirtualHost::new(DOMAIN, DEFAULT_HTTPS_PORT);
 
         let vhosts: Vec<VirtualHost> = vec![vhost1.clone(), vhost2.clone(), vhost3.clone()];
 
-        let results = filter_vhosts(&vhosts, false);
+        let results = filter_vhosts(&vhosts, false, &Vec::new(), ServerNamesPolicy::Primary, &Vec::new());
 
         assert_eq!(results.len(), 2);
 
         let vhost2_found = results.iter().find(
-            |vhost| vhost.domain == vhost2.domain && vhost.port == vhost2.port
+            |vhost| vhost.domain == vhost2.domain && vhost.get_port() == vhost2.get_port()
         );
 
         assert!(vhost2_found.is_some());
 
         let vhost3_found = results.iter().find(
-            |vhost| vhost.domain == vhost3.domain && vhost.port == vhost3.port
+            |vhost| vhost.domain == vhost3.domain && vhost.get_port() == vhost3.get_port()
         );
 
         assert!(vhost3_found.is_some());
@@ -34,24 +36,24 @@ mod filter_tests {
 
     #[test]
     fn result_should_not_contain_duplicates_without_custom_ports() {
-        let vhost1 = VirtualHost { domain: DOMAIN.to_string(), port: DEFAULT_HTTPS_PORT };
-        let vhost2 = VirtualHost { domain: DOMAIN2.to_string(), port: DEFAULT_HTTP_PORT };
-        let vhost3 = VirtualHost { domain: DOMAIN.to_string(), port: DEFAULT_HTTPS_PORT };
+        let vhost1 = VirtualHost::new(DOMAIN, DEFAULT_HTTPS_PORT);
+        let vhost2 = VirtualHost::new(DOMAIN2, DEFAULT_HTTP_PORT);
+        let vhost3 = VirtualHost::new(DOMAIN, DEFAULT_HTTPS_PORT);
 
         let vhosts: Vec<VirtualHost> = vec![vhost1.clone(), vhost2.clone(), vhost3.clone()];
 
-        let results = filter_vhosts(&vhosts, false);
+        let results = filter_vhosts(&vhosts, false, &Vec::new(), ServerNamesPolicy::Primary, &Vec::new());
 
         assert_eq!(results.len(), 2);
 
         let vhost1_found = results.iter().find(
-            |vhost| vhost.domain == vhost1.domain && vhost.port == vhost1.port
+            |vhost| vhost.domain == vhost1.domain && vhost.get_port() == vhost1.get_port()
         );
 
         assert!(vhost1_found.is_some());
 
         let vhost2_found = results.iter().find(
-            |vhost| vhost.domain == vhost2.domain && vhost.port == vhost2.port
+            |vhost| vhost.domain == vhost2.domain && vhost.get_port() == vhost2.get_port()
         );
 
         assert!(vhost2_found.is_some());
@@ -61,26 +63,175 @@ mod filter_tests {
     fn result_should_not_contain_duplicates_with_custom_ports() {
         let custom_port = 4113;
 
-        let vhost1 = VirtualHost { domain: DOMAIN.to_string(), port: custom_port };
-        let vhost2 = VirtualHost { domain: DOMAIN2.to_string(), port: DEFAULT_HTTPS_PORT };
-        let vhost3 = VirtualHost { domain: DOMAIN.to_string(), port: custom_port };
+        let vhost1 = VirtualHost::new(DOMAIN, custom_port);
+        let vhost2 = VirtualHost::new(DOMAIN2, DEFAULT_HTTPS_PORT);
+        let vhost3 = VirtualHost::new(DOMAIN, custom_port);
 
         let vhosts: Vec<VirtualHost> = vec![vhost1.clone(), vhost2.clone(), vhost3.clone()];
 
-        let results = filter_vhosts(&vhosts, true);
+        let results = filter_vhosts(&vhosts, true, &Vec::new(), ServerNamesPolicy::Primary, &Vec::new());
 
         assert_eq!(results.len(), 2);
 
         let vhost1_found = results.iter().find(
-            |vhost| vhost.domain == vhost1.domain && vhost.port == vhost1.port
+            |vhost| vhost.domain == vhost1.domain && vhost.get_port() == vhost1.get_port()
         );
 
         assert!(vhost1_found.is_some());
 
         let vhost2_found = results.iter().find(
-            |vhost| vhost.domain == vhost2.domain && vhost.port == vhost2.port
+            |vhost| vhost.domain == vhost2.domain && vhost.get_port() == vhost2.get_port()
         );
 
         assert!(vhost2_found.is_some());
     }
+
+    #[test]
+    fn result_should_contain_aliases_for_all_server_names_policy() {
+        let mut vhost = VirtualHost::new(DOMAIN, DEFAULT_HTTPS_PORT);
+        vhost.aliases = vec![DOMAIN2.to_string()];
+
+        let results = filter_vhosts(&vec![vhost], false, &Vec::new(), ServerNamesPolicy::All, &Vec::new());
+
+        assert_eq!(results.len(), 2);
+
+        let alias_vhost = results.iter().find(|vhost| vhost.domain == DOMAIN2).unwrap();
+        assert_eq!(alias_vhost.aliases, vec![DOMAIN]);
+    }
+
+    #[test]
+    fn result_should_contain_only_primary_names_for_primary_server_names_policy() {
+        let mut vhost = VirtualHost::new(DOMAIN, DEFAULT_HTTPS_PORT);
+        vhost.aliases = vec![DOMAIN2.to_string()];
+
+        let results = filter_vhosts(&vec![vhost], false, &Vec::new(), ServerNamesPolicy::Primary, &Vec::new());
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].domain, DOMAIN);
+    }
+
+    #[test]
+    fn allow_list_should_keep_only_matched_domains() {
+        let vhosts = vec![VirtualHost::new(DOMAIN, DEFAULT_HTTPS_PORT), VirtualHost::new(DOMAIN2, DEFAULT_HTTPS_PORT)];
+
+        assert_eq!(get_allowed_vhosts(vhosts.clone(), &Vec::new()).len(), 2);
+
+        let results = get_allowed_vhosts(vhosts, &["cronbox.*".to_string()]);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].domain, DOMAIN);
+    }
+
+    #[test]
+    fn ignore_list_should_be_applied_to_aliases() {
+        let mut vhost = VirtualHost::new(DOMAIN, DEFAULT_HTTPS_PORT);
+        vhost.aliases = vec![DOMAIN2.to_string()];
+
+        let results = filter_vhosts(&vec![vhost], false, &vec!["dfov.*"], ServerNamesPolicy::All, &Vec::new());
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].domain, DOMAIN);
+    }
+
+    #[test]
+    fn vhost_with_several_ports_should_be_split_per_port() {
+        let mut vhost = VirtualHost::new(DOMAIN, DEFAULT_HTTP_PORT);
+
+        let mut ipv6_endpoint = ListenEndpoint::new(DEFAULT_HTTPS_PORT);
+        ipv6_endpoint.address = Some("::".to_string());
+        ipv6_endpoint.ipv6 = true;
+
+        vhost.listens.push(ListenEndpoint::new(DEFAULT_HTTPS_PORT));
+        vhost.listens.push(ipv6_endpoint);
+
+        let results = get_vhosts_per_port(&vec![vhost]);
+
+        assert_eq!(results.len(), 2);
+
+        assert_eq!(results[0].get_port(), DEFAULT_HTTP_PORT);
+        assert_eq!(results[0].listens.len(), 1);
+
+        assert_eq!(results[1].get_port(), DEFAULT_HTTPS_PORT);
+        assert_eq!(results[1].listens.len(), 2);
+    }
+
+    #[test]
+    fn vhost_with_http_and_https_ports_should_be_superseded_by_https() {
+        let mut vhost = VirtualHost::new(DOMAIN, DEFAULT_HTTP_PORT);
+        vhost.listens.push(ListenEndpoint::new(DEFAULT_HTTPS_PORT));
+
+        let results = filter_vhosts(&vec![vhost], false, &Vec::new(), ServerNamesPolicy::Primary, &Vec::new());
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].get_port(), DEFAULT_HTTPS_PORT);
+    }
+
+    #[test]
+    fn wildcard_names_should_be_expanded_to_known_hostnames() {
+        let wildcard_vhost = VirtualHost::new("*.apps.whatever.ru", DEFAULT_HTTPS_PORT);
+        let regex_vhost = VirtualHost::new("~^(?<app>.+)\\.regex\\.whatever\\.ru$", DEFAULT_HTTPS_PORT);
+
+        let known_hostnames = vec![
+            "app1.apps.whatever.ru".to_string(), "api.regex.whatever.ru".to_string(), DOMAIN.to_string()
+        ];
+
+        let results = filter_vhosts(&vec![wildcard_vhost, regex_vhost], false, &Vec::new(),
+                                    ServerNamesPolicy::Primary, &known_hostnames);
+
+        assert_eq!(results.len(), 2);
+
+        let app_vhost = results.iter().find(|vhost| vhost.domain == "app1.apps.whatever.ru").unwrap();
+        assert_eq!(app_vhost.wildcard, Some("*.apps.whatever.ru".to_string()));
+
+        assert!(results.iter().any(|vhost| vhost.domain == "api.regex.whatever.ru"));
+    }
+
+    #[test]
+    fn wildcard_names_should_be_skipped_without_known_hostnames() {
+        let mut vhost = VirtualHost::new(DOMAIN, DEFAULT_HTTPS_PORT);
+        vhost.aliases = vec!["*.whatever.ru".to_string()];
+
+        let results = filter_vhosts(&vec![vhost], false, &Vec::new(), ServerNamesPolicy::All, &Vec::new());
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].domain, DOMAIN);
+        assert_eq!(results[0].wildcard, None);
+    }
+
+    #[test]
+    fn http_redirect_vhost_should_be_kept_with_https_vhost() {
+        let mut redirect_vhost = VirtualHost::new(DOMAIN, DEFAULT_HTTP_PORT);
+        redirect_vhost.redirect = Some(Redirect {
+            code: 301, target: "https://$host$request_uri".to_string(), scope: RedirectScope::VirtualHost
+        });
+
+        let vhost = VirtualHost::new(DOMAIN, DEFAULT_HTTPS_PORT);
+
+        let results = filter_vhosts(&vec![redirect_vhost, vhost], false, &Vec::new(),
+                                    ServerNamesPolicy::Primary, &Vec::new());
+
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn vhost_verdicts_should_contain_rule_for_dropped_vhosts() {
+        let vhosts: Vec<VirtualHost> = vec![
+            VirtualHost::new(DOMAIN, DEFAULT_HTTPS_PORT), VirtualHost::new(DOMAIN, DEFAULT_HTTP_PORT),
+            VirtualHost::new(DOMAIN, DEFAULT_HTTPS_PORT), VirtualHost::new(DOMAIN2, 7435),
+            VirtualHost::new("test.local", DEFAULT_HTTPS_PORT), VirtualHost::new("*.cronbox.ru", DEFAULT_HTTPS_PORT)
+        ];
+
+        let verdicts: Vec<(String, i32, Verdict)> = get_vhost_verdicts(
+            &vhosts, false, &vec!["*.local"], ServerNamesPolicy::Primary, &Vec::new()).iter()
+            .map(|(vhost, verdict)| (vhost.domain.to_string(), vhost.get_port(), verdict.clone())).collect();
+
+        assert_eq!(verdicts, vec![
+            (DOMAIN.to_string(), DEFAULT_HTTPS_PORT, Verdict::Kept),
+            (DOMAIN.to_string(), DEFAULT_HTTP_PORT, Verdict::SupersededByHttps),
+            (DOMAIN.to_string(), DEFAULT_HTTPS_PORT, Verdict::Duplicate),
+            (DOMAIN2.to_string(), 7435, Verdict::CustomPort),
+            ("test.local".to_string(), DEFAULT_HTTPS_PORT, Verdict::Ignored("*.local".to_string())),
+            ("*.cronbox.ru".to_string(), DEFAULT_HTTPS_PORT, Verdict::NoKnownHostnames)
+        ]);
+    }
 }
@@ -0,0 +1,78 @@
+//! Discovery of sites from nginx and apache configuration files.
+//!
+//! ```no_run
+//! use site_discovery_flea::discovery::discovery::{discover_sites, DiscoveryOptions};
+//!
+//! let options = DiscoveryOptions::new()
+//!     .config_path("nginx", "/etc/nginx/nginx.conf")
+//!     .include_custom_ports(true);
+//!
+//! for site in discover_sites(&options).unwrap() {
+//!     println!("{} {}", site.name, site.url);
+//! }
+//! ```
+
+#[macro_use]
+extern crate log;
+extern crate serde_yaml;
+extern crate toml;
+extern crate wildmatch;
+
+pub mod webserver;
+mod webserver_tests;
+pub mod nginx;
+
+pub mod domain;
+
+pub mod error;
+
+pub mod apache;
+
+pub mod site;
+
+pub mod source;
+mod source_tests;
+
+pub mod filter;
+mod filter_tests;
+
+pub mod discovery;
+mod discovery_tests;
+
+pub mod explain;
+mod explain_tests;
+
+pub mod check;
+mod check_tests;
+
+pub mod output;
+mod output_tests;
+
+pub mod sender;
+mod sender_tests;
+
+pub mod zabbix;
+mod zabbix_tests;
+
+pub mod probe;
+mod probe_tests;
+
+pub mod certificate;
+mod certificate_tests;
+
+pub mod template;
+mod template_tests;
+
+pub mod config;
+mod config_tests;
+
+mod site_tests;
+mod nginx_tests;
+mod apache_tests;
+mod test_utils;
+mod test_samples;
+
+pub const DEFAULT_HTTP_PORT: i32 = 80;
+pub const DEFAULT_HTTPS_PORT: i32 = 443;
+
+pub const WWW_SEARCH_PATTERN: &str = "www.";
@@ -3,10 +3,11 @@ pub mod logging {
     use log4rs::config::{Appender, Config, Logger, Root};
     use log4rs::encode::pattern::PatternEncoder;
     use log::LevelFilter;
+    use std::path::Path;
 
     const FILE_APPENDER_NAME: &str = "file";
 
-    const LOG_FILE_PATH: &str = "/var/log/zabbix/site-discovery-flea.log";
+    pub const LOG_FILE_PATH: &str = "/var/log/zabbix/site-discovery-flea.log";
 
     fn get_logging_level_from_string(level: &str) -> LevelFilter {
         return match level {
@@ -19,29 +20,29 @@ pub mod logging {
         };
     }
 
-    pub fn get_logging_config(logging_level: &str) -> Config {
+    pub fn get_logging_config(logging_level: &str, log_file: &Path) -> Config {
         let level = get_logging_level_from_string(logging_level);
 
         Config::builder()
-            .appender(get_file_appender_definition())
+            .appender(get_file_appender_definition(log_file))
             .logger(get_default_logger())
             .build(
             Root::builder()
                 .appender(FILE_APPENDER_NAME)
                 .build(level)
-            ).expect(&format!("unable to create log file '{}'", LOG_FILE_PATH))
+            ).expect(&format!("unable to create log file '{}'", log_file.display()))
     }
 
-    fn get_file_appender_definition() -> Appender {
+    fn get_file_appender_definition(log_file: &Path) -> Appender {
         Appender::builder()
-            .build(FILE_APPENDER_NAME, Box::new(get_file_appender())
+            .build(FILE_APPENDER_NAME, Box::new(get_file_appender(log_file))
         )
     }
 
-    fn get_file_appender() -> FileAppender {
+    fn get_file_appender(log_file: &Path) -> FileAppender {
         FileAppender::builder()
             .encoder(get_encoder())
-            .build(LOG_FILE_PATH)
+            .build(log_file)
             .unwrap()
     }
 
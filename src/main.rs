@@ -1,81 +1,179 @@
 #[macro_use]
 extern crate log;
 extern crate log4rs;
-extern crate serde_json;
-extern crate wildmatch;
 
+use std::collections::HashMap;
 use std::env;
-use std::path::Path;
-
-use clap::{App, Arg, ArgMatches};
-use serde_json::json;
-
-use crate::apache::apache::get_apache_vhosts;
-use crate::domain::domain::{Site, VirtualHost};
-use crate::filter::filter::filter_vhosts;
-use crate::logging::logging::get_logging_config;
-use crate::nginx::nginx::get_nginx_vhosts;
-use crate::site::site::get_sites_from_vhosts;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use clap::{App, Arg, ArgMatches, SubCommand};
+
+use site_discovery_flea::certificate::certificate::{get_certificates_json, get_current_timestamp};
+use site_discovery_flea::config::config::{Config, DEFAULT_CONFIG_PATH, load_config};
+use site_discovery_flea::discovery::discovery::{check_configs, discover_certificates, discover_sites,
+                                                explain_discovery};
+use site_discovery_flea::domain::domain::{Scheme, Site};
+use site_discovery_flea::error::error::DiscoveryError;
+use site_discovery_flea::explain::explain::get_explanation_table;
+use site_discovery_flea::output::output::{get_output, OutputFormat};
+use site_discovery_flea::probe::probe::{DEFAULT_PROBE_METHOD, DEFAULT_PROBE_TIMEOUT_SECONDS, get_probe_json,
+                                        get_probe_timeout, probe_sites, ProbeSettings};
+use site_discovery_flea::sender::sender::{DEFAULT_DISCOVERY_KEY, DEFAULT_ZABBIX_PORT, send_discovery_data, SenderSettings};
+use site_discovery_flea::filter::filter::ServerNamesPolicy;
+use site_discovery_flea::site::site::{DEFAULT_NAME_TEMPLATE, SiteMacro, validate_name_template};
+use site_discovery_flea::source::source::{get_web_server_sources, WebServerSource};
+use site_discovery_flea::template::template::{get_template, TemplateSettings, ZabbixVersion};
+use site_discovery_flea::zabbix::zabbix::{sync_web_scenarios, ZabbixApi, ZabbixApiSettings};
+
+use crate::logging::logging::{get_logging_config, LOG_FILE_PATH};
 
 mod logging;
 
 mod main_tests;
 
-mod webserver;
-mod webserver_tests;
-mod nginx;
-
-mod domain;
-
-mod apache;
-
-mod site;
-
-mod filter;
-mod filter_tests;
-mod site_tests;
-mod nginx_tests;
-mod apache_tests;
-mod test_utils;
-mod test_samples;
-
-const DEFAULT_HTTP_PORT: i32 = 80;
-const DEFAULT_HTTPS_PORT: i32 = 443;
-
 const INCLUDE_DOMAINS_WITH_WWW: &str = "include-www";
+const NO_INCLUDE_DOMAINS_WITH_WWW: &str = "no-include-www";
 const INCLUDE_CUSTOM_PORTS_OPTION: &str = "include-custom-ports";
-
-const WWW_SEARCH_PATTERN: &str = "www.";
+const NO_INCLUDE_CUSTOM_PORTS_OPTION: &str = "no-include-custom-ports";
 
 const WORKDIR: &str = "/etc/zabbix";
 
 const WORK_DIR_ARGUMENT: &str = "work-dir";
 const WORK_DIR_SHORT_ARGUMENT: &str = "d";
 
-const NGINX_VHOSTS_PATH: &str = "/etc/nginx/conf.d";
-const APACHE_VHOSTS_PATH: &str = "/etc/apache2/sites-enabled";
+const CONFIG_ARGUMENT: &str = "config";
 
-const NGINX_VHOSTS_PATH_ARGUMENT: &str = "nginx-vhosts-path";
-const NGINX_VHOSTS_PATH_SHORT_ARGUMENT: &str = "n";
-const APACHE_VHOSTS_PATH_ARGUMENT: &str = "apache-vhosts-path";
-const APACHE_VHOSTS_PATH_SHORT_ARGUMENT: &str = "a";
+const SOURCES_ARGUMENT: &str = "sources";
 
 const USE_DATA_PROPERTY_ARGUMENT: &str = "use-data-property";
 
 const LOG_LEVEL_ARGUMENT: &str = "log-level";
 const LOG_LEVEL_DEFAULT_VALUE: &str = "info";
 
+const LOG_FILE_ARGUMENT: &str = "log-file";
+
+const MAX_DEPTH_ARGUMENT: &str = "max-depth";
+const INCLUDE_FILES_ARGUMENT: &str = "include-files";
+const EXCLUDE_FILES_ARGUMENT: &str = "exclude-files";
+
 const IGNORE_LIST_ARGUMENT: &str = "ignore-list";
 const IGNORE_LIST_SHORT_ARGUMENT: &str = "i";
 
+const ALLOW_LIST_ARGUMENT: &str = "allow-list";
+
 const DETECT_302_REDIRECTS_ARGUMENT: &str = "redirect-302";
+const NO_DETECT_302_REDIRECTS_ARGUMENT: &str = "no-redirect-302";
+const INCLUDE_REDIRECTS_ARGUMENT: &str = "include-redirects";
+const NO_INCLUDE_REDIRECTS_ARGUMENT: &str = "no-include-redirects";
 
 const EXCLUDE_HTTP: &str = "exclude-http";
+const NO_EXCLUDE_HTTP: &str = "no-exclude-http";
+
+const INCLUDE_PROVENANCE_ARGUMENT: &str = "include-provenance";
+const NO_INCLUDE_PROVENANCE_ARGUMENT: &str = "no-include-provenance";
+const INCLUDE_TAGS_ARGUMENT: &str = "include-tags";
+const NO_INCLUDE_TAGS_ARGUMENT: &str = "no-include-tags";
+
+const NAME_TEMPLATE_ARGUMENT: &str = "name-template";
+const MACROS_ARGUMENT: &str = "macros";
+
+const STRICT_ARGUMENT: &str = "strict";
+const NO_STRICT_ARGUMENT: &str = "no-strict";
+
+const SERVER_NAMES_ARGUMENT: &str = "server-names";
+const SERVER_NAMES_DEFAULT_VALUE: &str = "primary";
+
+const PORT_SCHEMES_ARGUMENT: &str = "port-schemes";
+
+const KNOWN_HOSTNAMES_ARGUMENT: &str = "known-hostnames";
+
+const EXPLAIN_COMMAND: &str = "explain";
+const CHECK_COMMAND: &str = "check";
+const SEND_COMMAND: &str = "send";
+
+const ZABBIX_SERVER_ARGUMENT: &str = "zabbix-server";
+const ZABBIX_SERVER_SHORT_ARGUMENT: &str = "z";
+const ZABBIX_PORT_ARGUMENT: &str = "zabbix-port";
+const ZABBIX_PORT_SHORT_ARGUMENT: &str = "p";
+const ZABBIX_HOST_ARGUMENT: &str = "host";
+const ZABBIX_HOST_SHORT_ARGUMENT: &str = "s";
+const ZABBIX_KEY_ARGUMENT: &str = "key";
+const ZABBIX_KEY_SHORT_ARGUMENT: &str = "k";
+
+const ZABBIX_SYNC_COMMAND: &str = "zabbix-sync";
+
+const API_URL_ARGUMENT: &str = "api-url";
+const API_TOKEN_ARGUMENT: &str = "api-token";
+const API_TOKEN_ENV: &str = "ZABBIX_API_TOKEN";
+const DRY_RUN_ARGUMENT: &str = "dry-run";
+
+const TEMPLATE_COMMAND: &str = "template";
+
+const ZABBIX_VERSION_ARGUMENT: &str = "zabbix-version";
+const ZABBIX_VERSION_DEFAULT_VALUE: &str = "7.0";
+const TRAPPER_ARGUMENT: &str = "trapper";
+
+const PROBE_COMMAND: &str = "probe";
+
+const TIMEOUT_ARGUMENT: &str = "timeout";
+const METHOD_ARGUMENT: &str = "method";
+const EXPECTED_STATUS_ARGUMENT: &str = "expected-status";
+const FOLLOW_REDIRECTS_ARGUMENT: &str = "follow-redirects";
+const NO_FOLLOW_REDIRECTS_ARGUMENT: &str = "no-follow-redirects";
+const RESOLVE_TO_ARGUMENT: &str = "resolve-to";
+
+const CERTIFICATES_COMMAND: &str = "certificates";
+
+const OUTPUT_FORMAT_ARGUMENT: &str = "output-format";
 
 const ERROR_EXIT_CODE: i32 = 1;
+const CHECK_ISSUES_EXIT_CODE: i32 = 2;
+
+/// Command line options of web server source.
+struct SourceArguments {
+    vhosts_path: String,
+    vhosts_path_help: String,
+    config: String,
+    config_help: String
+}
 
 fn main() {
-    let matches = App::new("Site Discovery Flea")
+    let sources = get_web_server_sources();
+
+    let source_arguments: Vec<SourceArguments> = sources.iter()
+        .map(|source| get_source_arguments(source.as_ref())).collect();
+
+    let source_names: Vec<&str> = sources.iter().map(|source| source.name()).collect();
+
+    let config_help = format!("set discovery config file, TOML or YAML (default: {}). \
+                               command line options override config values", DEFAULT_CONFIG_PATH);
+
+    let log_file_help = format!("set log file (default: {})", LOG_FILE_PATH);
+
+    let output_format_values: Vec<&str> = OutputFormat::values().iter().map(|format| format.as_str()).collect();
+
+    let output_format_help = format!("set output format: {} (default: {}). prometheus is file_sd_configs json \
+                                      for blackbox exporter", output_format_values.join(", "), OutputFormat::Lld.as_str());
+
+    let name_template_help = format!("set {{#NAME}} template. placeholders: {{domain}}, {{port}}, {{scheme}}, \
+                                      {{port_suffix}} - ':port' for non default port, {{http_suffix}} - '_http' \
+                                      for http on port 80 (default: {})", DEFAULT_NAME_TEMPLATE);
+
+    let zabbix_port_help = format!("set zabbix server or proxy trapper port (default: {})", DEFAULT_ZABBIX_PORT);
+
+    let zabbix_key_help = format!("set discovery rule key (default: {})", DEFAULT_DISCOVERY_KEY);
+
+    let zabbix_version_values: Vec<&str> = ZabbixVersion::values().iter().map(|version| version.as_str()).collect();
+
+    let probe_timeout_help = format!("set request timeout in seconds (default: {})", DEFAULT_PROBE_TIMEOUT_SECONDS);
+
+    let probe_method_help = format!("set request method (default: {})", DEFAULT_PROBE_METHOD);
+
+    let sources_help = format!("set enabled web server sources, comma separated. available: {}",
+                               source_names.join(","));
+
+    let mut app = App::new("Site Discovery Flea")
         .version("1.3.3")
         .author("Eugene Lebedev <duke.tougu@gmail.com>")
         .about("Discover site configs for nginx and apache. \
@@ -88,28 +186,38 @@ fn main() {
                 .long(WORK_DIR_ARGUMENT)
                 .takes_value(true).required(false)
         )
+        .arg(
+            Arg::with_name(CONFIG_ARGUMENT)
+                .long(CONFIG_ARGUMENT)
+                .help(&config_help)
+                .takes_value(true).required(false)
+        )
         .arg(
             Arg::with_name(INCLUDE_DOMAINS_WITH_WWW)
                 .long(INCLUDE_DOMAINS_WITH_WWW)
                 .help("include domains with www")
+                .conflicts_with(NO_INCLUDE_DOMAINS_WITH_WWW)
+        )
+        .arg(
+            Arg::with_name(NO_INCLUDE_DOMAINS_WITH_WWW)
+                .long(NO_INCLUDE_DOMAINS_WITH_WWW)
+                .help("skip domains with www, overrides config file value")
         )
         .arg(
             Arg::with_name(INCLUDE_CUSTOM_PORTS_OPTION)
                 .long(INCLUDE_CUSTOM_PORTS_OPTION)
                 .help("include domains with custom ports")
+                .conflicts_with(NO_INCLUDE_CUSTOM_PORTS_OPTION)
         )
         .arg(
-            Arg::with_name(NGINX_VHOSTS_PATH_ARGUMENT)
-                .short(NGINX_VHOSTS_PATH_SHORT_ARGUMENT)
-                .help("set nginx vhosts root path")
-                .long(NGINX_VHOSTS_PATH_ARGUMENT)
-                .takes_value(true).required(false)
+            Arg::with_name(NO_INCLUDE_CUSTOM_PORTS_OPTION)
+                .long(NO_INCLUDE_CUSTOM_PORTS_OPTION)
+                .help("skip domains with custom ports, overrides config file value")
         )
         .arg(
-            Arg::with_name(APACHE_VHOSTS_PATH_ARGUMENT)
-                .short(APACHE_VHOSTS_PATH_SHORT_ARGUMENT)
-                .help("set apache vhosts root path")
-                .long(APACHE_VHOSTS_PATH_ARGUMENT)
+            Arg::with_name(SOURCES_ARGUMENT)
+                .long(SOURCES_ARGUMENT)
+                .help(&sources_help)
                 .takes_value(true).required(false)
         )
         .arg(
@@ -118,6 +226,14 @@ fn main() {
                 .long(USE_DATA_PROPERTY_ARGUMENT)
                 .takes_value(false).required(false)
         )
+        .arg(
+            Arg::with_name(OUTPUT_FORMAT_ARGUMENT)
+                .long(OUTPUT_FORMAT_ARGUMENT)
+                .help(&output_format_help)
+                .possible_values(&output_format_values)
+                .case_insensitive(true)
+                .takes_value(true).required(false)
+        )
         .arg(
             Arg::with_name(LOG_LEVEL_ARGUMENT)
                 .help("set logging level. possible values: debug, info, error, warn, trace")
@@ -126,6 +242,12 @@ fn main() {
                 .takes_value(true).required(false)
                 .default_value(LOG_LEVEL_DEFAULT_VALUE)
         )
+        .arg(
+            Arg::with_name(LOG_FILE_ARGUMENT)
+                .help(&log_file_help)
+                .long(LOG_FILE_ARGUMENT)
+                .takes_value(true).required(false)
+        )
         .arg(
             Arg::with_name(IGNORE_LIST_ARGUMENT)
                 .short(IGNORE_LIST_SHORT_ARGUMENT)
@@ -133,17 +255,333 @@ fn main() {
                 .long(IGNORE_LIST_ARGUMENT)
                 .takes_value(true).required(false)
         )
+        .arg(
+            Arg::with_name(MAX_DEPTH_ARGUMENT)
+                .long(MAX_DEPTH_ARGUMENT)
+                .help("set directory levels to scan in vhosts root paths, 1 means no recursion (default: 1)")
+                .validator(|value| match value.parse::<usize>() {
+                    Ok(depth) if depth > 0 => Ok(()),
+                    _ => Err(format!("expected number greater than 0, got '{}'", value))
+                })
+                .takes_value(true).required(false)
+        )
+        .arg(
+            Arg::with_name(INCLUDE_FILES_ARGUMENT)
+                .long(INCLUDE_FILES_ARGUMENT)
+                .help("set config file name globs in vhosts root paths, comma separated (default: *.conf). \
+                       example: *.conf,*")
+                .takes_value(true).required(false)
+        )
+        .arg(
+            Arg::with_name(EXCLUDE_FILES_ARGUMENT)
+                .long(EXCLUDE_FILES_ARGUMENT)
+                .help("set file name globs to skip in vhosts root paths, comma separated. example: *.bak,*~")
+                .takes_value(true).required(false)
+        )
+        .arg(
+            Arg::with_name(ALLOW_LIST_ARGUMENT)
+                .help("set site allow list, other sites are skipped. example: *.company.ru,company.ru")
+                .long(ALLOW_LIST_ARGUMENT)
+                .takes_value(true).required(false)
+        )
         .arg(
             Arg::with_name(DETECT_302_REDIRECTS_ARGUMENT)
                 .long(DETECT_302_REDIRECTS_ARGUMENT)
                 .help("detect http code 302 as redirects")
+                .conflicts_with(NO_DETECT_302_REDIRECTS_ARGUMENT)
+        )
+        .arg(
+            Arg::with_name(NO_DETECT_302_REDIRECTS_ARGUMENT)
+                .long(NO_DETECT_302_REDIRECTS_ARGUMENT)
+                .help("don't detect http code 302 as redirects, overrides config file value")
+        )
+        .arg(
+            Arg::with_name(INCLUDE_REDIRECTS_ARGUMENT)
+                .long(INCLUDE_REDIRECTS_ARGUMENT)
+                .help("include redirect vhosts as sites with {#TYPE}=redirect, {#REDIRECT_TARGET} \
+                       and {#REDIRECT_CODE} macros")
+                .conflicts_with(NO_INCLUDE_REDIRECTS_ARGUMENT)
+        )
+        .arg(
+            Arg::with_name(NO_INCLUDE_REDIRECTS_ARGUMENT)
+                .long(NO_INCLUDE_REDIRECTS_ARGUMENT)
+                .help("skip redirect vhosts, overrides config file value")
         )
         .arg(
             Arg::with_name(EXCLUDE_HTTP)
                 .long(EXCLUDE_HTTP)
                 .help("exclude all http domains")
+                .conflicts_with(NO_EXCLUDE_HTTP)
+        )
+        .arg(
+            Arg::with_name(NO_EXCLUDE_HTTP)
+                .long(NO_EXCLUDE_HTTP)
+                .help("keep http domains, overrides config file value")
+        )
+        .arg(
+            Arg::with_name(INCLUDE_PROVENANCE_ARGUMENT)
+                .long(INCLUDE_PROVENANCE_ARGUMENT)
+                .help("add {#SERVER}, {#CONFIG_FILE} and {#CONFIG_LINE} macros with web server \
+                       and config file position of site")
+                .conflicts_with(NO_INCLUDE_PROVENANCE_ARGUMENT)
+        )
+        .arg(
+            Arg::with_name(NO_INCLUDE_PROVENANCE_ARGUMENT)
+                .long(NO_INCLUDE_PROVENANCE_ARGUMENT)
+                .help("don't add web server and config file position macros, overrides config file value")
+        )
+        .arg(
+            Arg::with_name(INCLUDE_TAGS_ARGUMENT)
+                .long(INCLUDE_TAGS_ARGUMENT)
+                .help("add {#TAG_SERVER}, {#TAG_SOURCE_DIR}, {#TAG_TLS} (on/off), {#TAG_ENV} and {#TAG_OWNER} macros. \
+                       env, owner and other {#TAG_<KEY>} values come from config comments \
+                       '# site-discovery-flea: env=production owner=web-team' above server block")
+                .conflicts_with(NO_INCLUDE_TAGS_ARGUMENT)
+        )
+        .arg(
+            Arg::with_name(NO_INCLUDE_TAGS_ARGUMENT)
+                .long(NO_INCLUDE_TAGS_ARGUMENT)
+                .help("don't add {#TAG_*} macros, overrides config file value")
+        )
+        .arg(
+            Arg::with_name(NAME_TEMPLATE_ARGUMENT)
+                .long(NAME_TEMPLATE_ARGUMENT)
+                .help(&name_template_help)
+                .validator(|value| validate_name_template(&value))
+                .takes_value(true).required(false)
+        )
+        .arg(
+            Arg::with_name(MACROS_ARGUMENT)
+                .long(MACROS_ARGUMENT)
+                .help("add macros, comma separated: domain - {#DOMAIN}, port - {#PORT}, scheme - {#SCHEME}, \
+                       server - {#SERVER}, aliases - {#ALIASES}")
+                .validator(|value| get_list_argument_values(&value).iter()
+                    .map(|item| item.parse::<SiteMacro>().map(|_| ())).collect())
+                .takes_value(true).required(false)
+        )
+        .arg(
+            Arg::with_name(STRICT_ARGUMENT)
+                .long(STRICT_ARGUMENT)
+                .help("fail on first unreadable or broken config, such configs are logged and skipped otherwise")
+                .conflicts_with(NO_STRICT_ARGUMENT)
         )
-        .get_matches();
+        .arg(
+            Arg::with_name(NO_STRICT_ARGUMENT)
+                .long(NO_STRICT_ARGUMENT)
+                .help("log and skip unreadable or broken configs, overrides config file value")
+        )
+        .arg(
+            Arg::with_name(SERVER_NAMES_ARGUMENT)
+                .long(SERVER_NAMES_ARGUMENT)
+                .help("set which vhost names become sites: primary - first server_name/ServerName value, \
+                       all - primary names and aliases (server_name values, ServerAlias)")
+                .possible_values(&["primary", "all"])
+                .case_insensitive(true)
+                .takes_value(true).required(false)
+                .default_value(SERVER_NAMES_DEFAULT_VALUE)
+        )
+        .arg(
+            Arg::with_name(PORT_SCHEMES_ARGUMENT)
+                .long(PORT_SCHEMES_ARGUMENT)
+                .help("set scheme for ports, overrides TLS detection. example: 8443=https,8080=http")
+                .takes_value(true).required(false)
+        )
+        .arg(
+            Arg::with_name(KNOWN_HOSTNAMES_ARGUMENT)
+                .long(KNOWN_HOSTNAMES_ARGUMENT)
+                .help("set known host names for wildcard and regex server names. \
+                       example: app1.company.ru,app2.company.ru. \
+                       wildcard names are skipped without this option")
+                .takes_value(true).required(false)
+        )
+        .subcommand(
+            SubCommand::with_name(EXPLAIN_COMMAND)
+                .about("show table with every candidate vhost: source, domain, port, config position \
+                        and verdict - kept or the rule which dropped it. use the same options as for discovery")
+        )
+        .subcommand(
+            SubCommand::with_name(CHECK_COMMAND)
+                .about("check web server configs and show issues as 'file:line: kind: message'. \
+                        exit code is 2 if issues are found, 1 on other errors")
+        )
+        .subcommand(
+            SubCommand::with_name(SEND_COMMAND)
+                .about("send low level discovery data to zabbix server or proxy trapper. \
+                        discovery rule type must be 'Zabbix trapper'. \
+                        exit code is 1 if data can't be sent or isn't processed")
+                .arg(
+                    Arg::with_name(ZABBIX_SERVER_ARGUMENT)
+                        .short(ZABBIX_SERVER_SHORT_ARGUMENT)
+                        .long(ZABBIX_SERVER_ARGUMENT)
+                        .help("set zabbix server or proxy host name or ip")
+                        .takes_value(true).required(false)
+                )
+                .arg(
+                    Arg::with_name(ZABBIX_PORT_ARGUMENT)
+                        .short(ZABBIX_PORT_SHORT_ARGUMENT)
+                        .long(ZABBIX_PORT_ARGUMENT)
+                        .help(&zabbix_port_help)
+                        .validator(|value| match value.parse::<u16>() {
+                            Ok(port) if port > 0 => Ok(()),
+                            _ => Err(format!("expected port number, got '{}'", value))
+                        })
+                        .takes_value(true).required(false)
+                )
+                .arg(
+                    Arg::with_name(ZABBIX_HOST_ARGUMENT)
+                        .short(ZABBIX_HOST_SHORT_ARGUMENT)
+                        .long(ZABBIX_HOST_ARGUMENT)
+                        .help("set host name as configured in zabbix frontend")
+                        .takes_value(true).required(false)
+                )
+                .arg(
+                    Arg::with_name(ZABBIX_KEY_ARGUMENT)
+                        .short(ZABBIX_KEY_SHORT_ARGUMENT)
+                        .long(ZABBIX_KEY_ARGUMENT)
+                        .help(&zabbix_key_help)
+                        .takes_value(true).required(false)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name(ZABBIX_SYNC_COMMAND)
+                .about("create, update and delete web scenarios with triggers of host through zabbix api, \
+                        scenario per site. only scenarios with 'source: site-discovery-flea' tag are changed")
+                .arg(
+                    Arg::with_name(API_URL_ARGUMENT)
+                        .long(API_URL_ARGUMENT)
+                        .help("set zabbix frontend api url, plain http is allowed for loopback address only. \
+                               example: https://zabbix.company.ru/api_jsonrpc.php")
+                        .takes_value(true).required(false)
+                )
+                .arg(
+                    Arg::with_name(API_TOKEN_ARGUMENT)
+                        .long(API_TOKEN_ARGUMENT)
+                        .help("set zabbix api token, zabbix 6.4+")
+                        .env(API_TOKEN_ENV)
+                        .hide_env_values(true)
+                        .takes_value(true).required(false)
+                )
+                .arg(
+                    Arg::with_name(ZABBIX_HOST_ARGUMENT)
+                        .short(ZABBIX_HOST_SHORT_ARGUMENT)
+                        .long(ZABBIX_HOST_ARGUMENT)
+                        .help("set host name as configured in zabbix frontend")
+                        .takes_value(true).required(false)
+                )
+                .arg(
+                    Arg::with_name(DRY_RUN_ARGUMENT)
+                        .long(DRY_RUN_ARGUMENT)
+                        .help("show changes without applying them")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name(TEMPLATE_COMMAND)
+                .about("print zabbix template with discovery rule, site check item and trigger prototypes. \
+                        LLD macros follow the same options as for discovery")
+                .arg(
+                    Arg::with_name(ZABBIX_VERSION_ARGUMENT)
+                        .long(ZABBIX_VERSION_ARGUMENT)
+                        .help("set zabbix version: 5.0 - XML, 6.0, 6.2, 6.4, 7.0 - YAML")
+                        .possible_values(&zabbix_version_values)
+                        .takes_value(true).required(false)
+                        .default_value(ZABBIX_VERSION_DEFAULT_VALUE)
+                )
+                .arg(
+                    Arg::with_name(ZABBIX_KEY_ARGUMENT)
+                        .short(ZABBIX_KEY_SHORT_ARGUMENT)
+                        .long(ZABBIX_KEY_ARGUMENT)
+                        .help(&zabbix_key_help)
+                        .takes_value(true).required(false)
+                )
+                .arg(
+                    Arg::with_name(TRAPPER_ARGUMENT)
+                        .long(TRAPPER_ARGUMENT)
+                        .help("use 'Zabbix trapper' discovery rule for send command \
+                               instead of 'Zabbix agent (active)'")
+                )
+        )
+        .subcommand(
+            SubCommand::with_name(PROBE_COMMAND)
+                .about("request every site and print json with status, ok (1 or 0), latency, \
+                        TLS result and final url by site url. use with dependent items, \
+                        JSONPath example: $['{#URL}'].ok")
+                .arg(
+                    Arg::with_name(TIMEOUT_ARGUMENT)
+                        .long(TIMEOUT_ARGUMENT)
+                        .help(&probe_timeout_help)
+                        .validator(|value| match value.parse().ok().and_then(get_probe_timeout) {
+                            Some(_) => Ok(()),
+                            None => Err(format!("expected timeout in seconds, i.e. 5 or 0.5, got '{}'", value))
+                        })
+                        .takes_value(true).required(false)
+                )
+                .arg(
+                    Arg::with_name(METHOD_ARGUMENT)
+                        .long(METHOD_ARGUMENT)
+                        .help(&probe_method_help)
+                        .takes_value(true).required(false)
+                )
+                .arg(
+                    Arg::with_name(EXPECTED_STATUS_ARGUMENT)
+                        .long(EXPECTED_STATUS_ARGUMENT)
+                        .help("set expected statuses, comma separated. example: 200,301. \
+                               by default redirect sites expect their redirect code, other sites expect 200")
+                        .validator(|value| match get_list_argument_values(&value).iter()
+                                                 .all(|status| status.parse::<u16>().is_ok()) {
+                            true => Ok(()),
+                            false => Err(format!("expected comma separated statuses, got '{}'", value))
+                        })
+                        .takes_value(true).required(false)
+                )
+                .arg(
+                    Arg::with_name(FOLLOW_REDIRECTS_ARGUMENT)
+                        .long(FOLLOW_REDIRECTS_ARGUMENT)
+                        .help("follow redirects for all sites. by default redirects are followed \
+                               except for redirect sites")
+                        .conflicts_with(NO_FOLLOW_REDIRECTS_ARGUMENT)
+                )
+                .arg(
+                    Arg::with_name(NO_FOLLOW_REDIRECTS_ARGUMENT)
+                        .long(NO_FOLLOW_REDIRECTS_ARGUMENT)
+                        .help("don't follow redirects")
+                )
+                .arg(
+                    Arg::with_name(RESOLVE_TO_ARGUMENT)
+                        .long(RESOLVE_TO_ARGUMENT)
+                        .help("connect to address instead of resolved one for every domain. example: 127.0.0.1")
+                        .takes_value(true).required(false)
+                )
+        )
+        .subcommand(
+            SubCommand::with_name(CERTIFICATES_COMMAND)
+                .about("read ssl_certificate/SSLCertificateFile files of https vhosts and print json with subject, \
+                        SANs, issuer, days to expiry and domain coverage by site url. no network requests are made. \
+                        JSONPath example: $['{#URL}'].days_to_expiry")
+        );
+
+    for (source, arguments) in sources.iter().zip(&source_arguments) {
+        let mut vhosts_path_argument = Arg::with_name(&arguments.vhosts_path)
+            .long(&arguments.vhosts_path)
+            .help(&arguments.vhosts_path_help)
+            .takes_value(true).required(false)
+            .multiple(true).number_of_values(1);
+
+        if let Some(short_argument) = source.vhosts_path_short_argument() {
+            vhosts_path_argument = vhosts_path_argument.short(short_argument);
+        }
+
+        app = app.arg(vhosts_path_argument).arg(
+            Arg::with_name(&arguments.config)
+                .long(&arguments.config)
+                .help(&arguments.config_help)
+                .takes_value(true).required(false)
+        );
+    }
+
+    let matches = app.get_matches();
+
+    let config_result = get_config(&matches);
+    let config: Config = config_result.as_ref().ok().cloned().unwrap_or_default();
 
     let working_directory: &Path = get_argument_path_value(
         &matches, WORK_DIR_ARGUMENT, WORK_DIR_SHORT_ARGUMENT, WORKDIR);
@@ -152,55 +590,384 @@ fn main() {
 
     env::set_current_dir(&working_directory).expect("unable to set working directory");
 
-    let logging_level: &str = if matches.is_present(LOG_LEVEL_ARGUMENT) {
+    let logging_level: &str = if matches.occurrences_of(LOG_LEVEL_ARGUMENT) > 0 {
         matches.value_of(LOG_LEVEL_ARGUMENT).unwrap()
-    } else { LOG_LEVEL_DEFAULT_VALUE };
+    } else { config.log.level.as_deref().unwrap_or(LOG_LEVEL_DEFAULT_VALUE) };
 
-    let logging_config = get_logging_config(logging_level);
+    let log_file: &Path = match matches.value_of(LOG_FILE_ARGUMENT) {
+        Some(value) => Path::new(value),
+        None => config.log.file.as_deref().unwrap_or(Path::new(LOG_FILE_PATH))
+    };
+
+    let logging_config = get_logging_config(logging_level, log_file);
     log4rs::init_config(logging_config).unwrap();
 
-    let include_domains_with_www = matches.occurrences_of(INCLUDE_DOMAINS_WITH_WWW) > 0;
-    let include_custom_domains = matches.occurrences_of(INCLUDE_CUSTOM_PORTS_OPTION) > 0;
+    if let Err(e) = config_result {
+        error!("{}", e);
+        exit(ERROR_EXIT_CODE)
+    }
+
+    let output_format_value: &str = if matches.is_present(USE_DATA_PROPERTY_ARGUMENT) {
+        OutputFormat::LldData.as_str()
+    } else if let Some(value) = matches.value_of(OUTPUT_FORMAT_ARGUMENT) {
+        value
+    } else { config.output.format.as_deref().unwrap_or(OutputFormat::Lld.as_str()) };
+
+    let output_format: OutputFormat = match output_format_value.parse() {
+        Ok(output_format) => output_format,
+        Err(e) => {
+            error!("{}, available: {}", e, output_format_values.join(","));
+            exit(ERROR_EXIT_CODE)
+        }
+    };
+
+    let include_custom_domains = get_flag_value(&matches, INCLUDE_CUSTOM_PORTS_OPTION, NO_INCLUDE_CUSTOM_PORTS_OPTION)
+        .unwrap_or(config.sites.include_custom_ports);
+
+    let mut options = config.get_discovery_options()
+        .include_custom_ports(include_custom_domains);
+
+    if let Some(value) = get_flag_value(&matches, INCLUDE_DOMAINS_WITH_WWW, NO_INCLUDE_DOMAINS_WITH_WWW) {
+        options = options.include_www(value);
+    }
+
+    if let Some(value) = get_flag_value(&matches, EXCLUDE_HTTP, NO_EXCLUDE_HTTP) {
+        options = options.exclude_http(value);
+    }
+
+    if let Some(value) = get_flag_value(&matches, DETECT_302_REDIRECTS_ARGUMENT, NO_DETECT_302_REDIRECTS_ARGUMENT) {
+        options = options.detect_302_redirects(value);
+    }
+
+    if let Some(value) = get_flag_value(&matches, INCLUDE_REDIRECTS_ARGUMENT, NO_INCLUDE_REDIRECTS_ARGUMENT) {
+        options = options.include_redirects(value);
+    }
+
+    if let Some(value) = get_flag_value(&matches, INCLUDE_PROVENANCE_ARGUMENT, NO_INCLUDE_PROVENANCE_ARGUMENT) {
+        options = options.include_provenance(value);
+    }
+
+    if let Some(value) = get_flag_value(&matches, INCLUDE_TAGS_ARGUMENT, NO_INCLUDE_TAGS_ARGUMENT) {
+        options = options.include_tags(value);
+    }
+
+    // server and source file labels
+    if output_format == OutputFormat::Prometheus {
+        options = options.include_provenance(true);
+    }
+
+    if let Some(value) = get_flag_value(&matches, STRICT_ARGUMENT, NO_STRICT_ARGUMENT) {
+        options = options.strict(value);
+    }
+
+    if let Some(value) = matches.value_of(IGNORE_LIST_ARGUMENT) {
+        options = options.ignore_list(value.split(",").map(|mask| mask.to_string()).collect());
+    }
+
+    if let Some(value) = matches.value_of(NAME_TEMPLATE_ARGUMENT) {
+        options = options.name_template(value);
+    }
+
+    if let Some(value) = matches.value_of(MACROS_ARGUMENT) {
+        options = options.macros(get_list_argument_values(value).iter()
+            .filter_map(|item| item.parse::<SiteMacro>().ok()).collect());
+    }
+
+    if let Some(value) = matches.value_of(MAX_DEPTH_ARGUMENT) {
+        options = options.max_depth(value.parse().unwrap_or(1));
+    }
+
+    if let Some(value) = matches.value_of(INCLUDE_FILES_ARGUMENT) {
+        options = options.include_files(get_list_argument_values(value));
+    }
+
+    if let Some(value) = matches.value_of(EXCLUDE_FILES_ARGUMENT) {
+        options = options.exclude_files(get_list_argument_values(value));
+    }
+
+    if let Some(value) = matches.value_of(ALLOW_LIST_ARGUMENT) {
+        options = options.allow_list(get_list_argument_values(value));
+    }
+
+    if matches.occurrences_of(SERVER_NAMES_ARGUMENT) > 0 {
+        let server_names_policy: ServerNamesPolicy = matches.value_of(SERVER_NAMES_ARGUMENT)
+            .unwrap_or(SERVER_NAMES_DEFAULT_VALUE).parse().unwrap_or(ServerNamesPolicy::Primary);
+        options = options.server_names_policy(server_names_policy);
+    }
+
+    if let Some(value) = matches.value_of(PORT_SCHEMES_ARGUMENT) {
+        options = options.port_schemes(get_port_schemes(value));
+    }
+
+    if let Some(value) = matches.value_of(KNOWN_HOSTNAMES_ARGUMENT) {
+        options = options.known_hostnames(get_list_argument_values(value));
+    }
+
+    if let Some(value) = matches.value_of(SOURCES_ARGUMENT) {
+        let enabled_sources: Vec<String> = get_list_argument_values(value);
+        let enabled_sources: Vec<&str> = enabled_sources.iter().map(|name| name.as_str()).collect();
+        options = options.sources(&enabled_sources);
+    }
+
+    for (source, arguments) in sources.iter().zip(&source_arguments) {
+        if let Some(vhosts_paths) = matches.values_of(&arguments.vhosts_path) {
+            options = options.vhosts_paths(source.name(), vhosts_paths.map(PathBuf::from).collect());
+        }
+
+        if let Some(config_path) = matches.value_of(&arguments.config) {
+            options = options.config_path(source.name(), config_path);
+        }
+    }
+
+    if matches.subcommand_matches(EXPLAIN_COMMAND).is_some() {
+        match explain_discovery(&options) {
+            Ok(explanations) => println!("{}", get_explanation_table(&explanations)),
+            Err(e) => {
+                error!("{}", e);
+                exit(ERROR_EXIT_CODE)
+            }
+        }
+
+        return
+    }
+
+    if let Some(template_matches) = matches.subcommand_matches(TEMPLATE_COMMAND) {
+        let version: ZabbixVersion = template_matches.value_of(ZABBIX_VERSION_ARGUMENT)
+            .unwrap_or(ZABBIX_VERSION_DEFAULT_VALUE).parse().unwrap_or(ZabbixVersion::V7_0);
+
+        let mut settings = TemplateSettings::new(version, options.get_lld_macros());
+        settings.trapper = template_matches.is_present(TRAPPER_ARGUMENT);
+
+        if let Some(key) = template_matches.value_of(ZABBIX_KEY_ARGUMENT).map(|value| value.to_string())
+                                           .or_else(|| config.zabbix.key.clone()) {
+            settings.key = key;
+        }
+
+        print!("{}", get_template(&settings));
+
+        return
+    }
 
-    let ignore_list: Vec<&str> = if matches.is_present(IGNORE_LIST_ARGUMENT) {
-        matches.value_of(IGNORE_LIST_ARGUMENT).unwrap().split(",").collect()
-    } else { Vec::new() };
+    if matches.subcommand_matches(CHECK_COMMAND).is_some() {
+        match check_configs(&options) {
+            Ok(issues) => {
+                issues.iter().for_each(|issue| println!("{}", issue));
+
+                if !issues.is_empty() {
+                    exit(CHECK_ISSUES_EXIT_CODE)
+                }
+            }
+            Err(e) => {
+                error!("{}", e);
+                exit(ERROR_EXIT_CODE)
+            }
+        }
 
-    let detect_302_redirects = matches.occurrences_of(DETECT_302_REDIRECTS_ARGUMENT) > 0;
+        return
+    }
 
-    let exclude_http = matches.occurrences_of(EXCLUDE_HTTP) > 0;
+    if matches.subcommand_matches(CERTIFICATES_COMMAND).is_some() {
+        match discover_certificates(&options, get_current_timestamp()) {
+            Ok(reports) => println!("{}", get_certificates_json(&reports)),
+            Err(e) => {
+                error!("{}", e);
+                exit(ERROR_EXIT_CODE)
+            }
+        }
 
-    debug!("ignore list '{:?}'", &ignore_list);
+        return
+    }
 
     info!("[~] collect virtual hosts..");
     info!("- include domains with custom ports: {}", include_custom_domains);
-    let mut vhosts: Vec<VirtualHost> = Vec::new();
 
-    let nginx_vhosts_path: &Path = get_nginx_vhosts_path(&matches);
-    debug!("- nginx vhosts root: '{}'", nginx_vhosts_path.display());
+    let sites: Vec<Site> = match discover_sites(&options) {
+        Ok(sites) => sites,
+        Err(e) => {
+            error!("{}", e);
+            exit(ERROR_EXIT_CODE)
+        }
+    };
+
+    if let Some(send_matches) = matches.subcommand_matches(SEND_COMMAND) {
+        let settings: SenderSettings = match get_sender_settings(send_matches, &config) {
+            Ok(settings) => settings,
+            Err(e) => {
+                error!("{}", e);
+                exit(ERROR_EXIT_CODE)
+            }
+        };
+
+        // trapper accepts low level discovery json only
+        let lld_format = if output_format == OutputFormat::LldData { OutputFormat::LldData } else { OutputFormat::Lld };
+
+        match send_discovery_data(&settings, &get_output(sites, lld_format)) {
+            Ok(response) => {
+                info!("zabbix server response: {}", response.info);
+                println!("{}", response.info);
+
+                if response.failed > 0 || response.processed == 0 {
+                    error!("discovery data isn't processed, check host '{}' and trapper item '{}'",
+                           settings.host, settings.key);
+                    exit(ERROR_EXIT_CODE)
+                }
+            }
+            Err(e) => {
+                error!("{}", e);
+                exit(ERROR_EXIT_CODE)
+            }
+        }
+
+        return
+    }
+
+    if let Some(sync_matches) = matches.subcommand_matches(ZABBIX_SYNC_COMMAND) {
+        let (settings, host) = match get_zabbix_api_settings(sync_matches, &config) {
+            Ok(value) => value,
+            Err(e) => {
+                error!("{}", e);
+                exit(ERROR_EXIT_CODE)
+            }
+        };
+
+        let dry_run = sync_matches.is_present(DRY_RUN_ARGUMENT);
+
+        match sync_web_scenarios(&ZabbixApi::new(settings), &host, &sites, dry_run) {
+            Ok(actions) => actions.iter().for_each(|action| println!("{}", action)),
+            Err(e) => {
+                error!("{}", e);
+                exit(ERROR_EXIT_CODE)
+            }
+        }
+
+        return
+    }
+
+    if let Some(probe_matches) = matches.subcommand_matches(PROBE_COMMAND) {
+        let settings = get_probe_settings(probe_matches, &config);
+
+        match probe_sites(&sites, &settings) {
+            Ok(results) => println!("{}", get_probe_json(&results)),
+            Err(e) => {
+                error!("{}", e);
+                exit(ERROR_EXIT_CODE)
+            }
+        }
+
+        return
+    }
+
+    println!("{}", get_output(sites, output_format));
+}
+
+/// Probe settings from `probe` command options, `[probe]` config values are used for missing ones.
+fn get_probe_settings(matches: &ArgMatches, config: &Config) -> ProbeSettings {
+    let mut settings = ProbeSettings::new();
+
+    if let Some(timeout) = matches.value_of(TIMEOUT_ARGUMENT).and_then(|value| value.parse().ok())
+                                  .or(config.probe.timeout).and_then(get_probe_timeout) {
+        settings.timeout = timeout;
+    }
+
+    if let Some(method) = matches.value_of(METHOD_ARGUMENT).map(|value| value.to_string())
+                                 .or_else(|| config.probe.method.clone()) {
+        settings.method = method;
+    }
+
+    if let Some(value) = matches.value_of(EXPECTED_STATUS_ARGUMENT) {
+        settings.expected_statuses = Some(get_list_argument_values(value).iter()
+            .filter_map(|status| status.parse().ok()).collect());
+
+    } else if !config.probe.expected_status.is_empty() {
+        settings.expected_statuses = Some(config.probe.expected_status.clone());
+    }
+
+    settings.follow_redirects = get_flag_value(matches, FOLLOW_REDIRECTS_ARGUMENT, NO_FOLLOW_REDIRECTS_ARGUMENT)
+        .or(config.probe.follow_redirects);
 
-    let nginx_vhosts = get_nginx_vhosts(nginx_vhosts_path, detect_302_redirects);
-    let mut filtered_nginx_vhosts: Vec<VirtualHost> = filter_vhosts(&nginx_vhosts, include_custom_domains, &ignore_list);
-    vhosts.append(&mut filtered_nginx_vhosts);
+    settings.resolve_to = matches.value_of(RESOLVE_TO_ARGUMENT).map(|value| value.to_string());
 
-    let apache_vhosts_path: &Path = get_apache_vhosts_path(&matches);
-    debug!("apache vhosts root: '{}'", apache_vhosts_path.display());
+    settings
+}
+
+/// Sender settings from `send` command options, `[zabbix]` config values are used for missing ones.
+fn get_sender_settings(matches: &ArgMatches, config: &Config) -> Result<SenderSettings, String> {
+    let server = matches.value_of(ZABBIX_SERVER_ARGUMENT).map(|value| value.to_string())
+        .or_else(|| config.zabbix.server.clone())
+        .ok_or_else(|| format!("zabbix server is required, use --{} option or [zabbix] server config value",
+                               ZABBIX_SERVER_ARGUMENT))?;
 
-    let apache_vhosts = get_apache_vhosts(apache_vhosts_path);
-    let mut filtered_apache_vhosts: Vec<VirtualHost> = filter_vhosts(&apache_vhosts, include_custom_domains, &ignore_list);
-    vhosts.append(&mut filtered_apache_vhosts);
+    let host = matches.value_of(ZABBIX_HOST_ARGUMENT).map(|value| value.to_string())
+        .or_else(|| config.zabbix.host.clone())
+        .ok_or_else(|| format!("host name is required, use --{} option or [zabbix] host config value",
+                               ZABBIX_HOST_ARGUMENT))?;
 
-    let sites: Vec<Site> = get_sites_from_vhosts(vhosts, include_domains_with_www, exclude_http);
+    let mut settings = SenderSettings::new(&server, &host);
+
+    if let Some(port) = matches.value_of(ZABBIX_PORT_ARGUMENT).and_then(|value| value.parse().ok())
+                               .or(config.zabbix.port) {
+        settings.port = port;
+    }
 
-    let json;
+    if let Some(key) = matches.value_of(ZABBIX_KEY_ARGUMENT).map(|value| value.to_string())
+                              .or_else(|| config.zabbix.key.clone()) {
+        settings.key = key;
+    }
+
+    Ok(settings)
+}
+
+/// Loads config from `--config` path or from default path if such file exists.
+fn get_config(matches: &ArgMatches) -> Result<Config, DiscoveryError> {
+    match matches.value_of(CONFIG_ARGUMENT) {
+        Some(config_path) => load_config(Path::new(config_path)),
+        None => {
+            let default_config_path = Path::new(DEFAULT_CONFIG_PATH);
+
+            if default_config_path.exists() {
+                load_config(default_config_path)
+            } else {
+                Ok(Config::default())
+            }
+        }
+    }
+}
 
-    if matches.is_present(USE_DATA_PROPERTY_ARGUMENT) {
-        json = get_low_level_discovery_json_with_data_property(sites);
+/// Api settings and host from `zabbix-sync` command options, `[zabbix]` config values are used for missing ones.
+fn get_zabbix_api_settings(matches: &ArgMatches, config: &Config) -> Result<(ZabbixApiSettings, String), String> {
+    let url = matches.value_of(API_URL_ARGUMENT).map(|value| value.to_string())
+        .or_else(|| config.zabbix.api_url.clone())
+        .ok_or_else(|| format!("zabbix api url is required, use --{} option or [zabbix] api_url config value",
+                               API_URL_ARGUMENT))?;
+
+    let token = matches.value_of(API_TOKEN_ARGUMENT).map(|value| value.to_string())
+        .or_else(|| config.zabbix.api_token.clone())
+        .ok_or_else(|| format!("zabbix api token is required, use --{} option, {} environment variable \
+                                or [zabbix] api_token config value", API_TOKEN_ARGUMENT, API_TOKEN_ENV))?;
+
+    let host = matches.value_of(ZABBIX_HOST_ARGUMENT).map(|value| value.to_string())
+        .or_else(|| config.zabbix.host.clone())
+        .ok_or_else(|| format!("host name is required, use --{} option or [zabbix] host config value",
+                               ZABBIX_HOST_ARGUMENT))?;
+
+    Ok((ZabbixApiSettings::new(&url, &token), host))
+}
+
+/// Value of boolean option with `--no-*` counterpart, `None` if neither is given and config file value is used.
+fn get_flag_value(matches: &ArgMatches, argument: &str, negated_argument: &str) -> Option<bool> {
+    if matches.is_present(argument) {
+        Some(true)
+    } else if matches.is_present(negated_argument) {
+        Some(false)
     } else {
-        json = get_low_level_discovery_json(sites);
-    };
+        None
+    }
+}
 
-    println!("{}", json);
+/// Splits comma separated value, empty items are skipped.
+fn get_list_argument_values(value: &str) -> Vec<String> {
+    value.split(',').map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty()).collect()
 }
 
 fn get_argument_path_value<'a>(matches: &'a ArgMatches, long_argument: &str,
@@ -221,24 +988,32 @@ fn get_argument_path_value<'a>(matches: &'a ArgMatches, long_argument: &str,
     return path;
 }
 
-fn get_nginx_vhosts_path<'a>(matches: &'a ArgMatches) -> &'a Path {
-    get_argument_path_value(&matches, NGINX_VHOSTS_PATH_ARGUMENT,
-        NGINX_VHOSTS_PATH_SHORT_ARGUMENT, NGINX_VHOSTS_PATH)
+fn get_source_arguments(source: &dyn WebServerSource) -> SourceArguments {
+    SourceArguments {
+        vhosts_path: format!("{}-vhosts-path", source.name()),
+        vhosts_path_help: format!("set {} vhosts root path, can be repeated (default: {})",
+                                  source.name(), source.default_vhosts_path()),
+        config: format!("{}-config", source.name()),
+        config_help: format!("set {} main config file (example: {}). \
+                              vhosts are discovered by following include directives, \
+                              {} vhosts root path is ignored",
+                             source.name(), source.default_config_path(), source.name())
+    }
 }
 
-fn get_apache_vhosts_path<'a>(matches: &'a ArgMatches) -> &'a Path {
-    get_argument_path_value(&matches, APACHE_VHOSTS_PATH_ARGUMENT,
-                            APACHE_VHOSTS_PATH_SHORT_ARGUMENT, APACHE_VHOSTS_PATH)
-}
+/// Parses port to scheme map, example: `8443=https,8080=http`.
+fn get_port_schemes(value: &str) -> HashMap<i32, Scheme> {
+    let mut port_schemes: HashMap<i32, Scheme> = HashMap::new();
 
-fn get_low_level_discovery_json(sites: Vec<Site>) -> String {
-    let json_structure = json!(sites);
-    let json = serde_json::to_string(&json_structure).unwrap();
-    return json;
-}
+    for item in value.split(',').map(|item| item.trim()).filter(|item| !item.is_empty()) {
+        let parsed_item = item.split_once('=')
+            .and_then(|(port, scheme)| Some((port.trim().parse::<i32>().ok()?, scheme.trim().parse::<Scheme>().ok()?)));
+
+        match parsed_item {
+            Some((port, scheme)) => { port_schemes.insert(port, scheme); }
+            None => error!("unsupported port scheme value '{}', expected format: 8443=https", item)
+        }
+    }
 
-fn get_low_level_discovery_json_with_data_property(sites: Vec<Site>) -> String {
-    let json_structure = json!({"data": sites});
-    let json = serde_json::to_string(&json_structure).unwrap();
-    return json;
+    port_schemes
 }
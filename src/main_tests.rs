@@ -1,8 +1,13 @@
 #[cfg(test)]
 mod main_tests {
-    use crate::{DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT, get_low_level_discovery_json, get_low_level_discovery_json_with_data_property};
-    use crate::domain::domain::{Site, VirtualHost};
-    use crate::site::site::{get_sites_from_vhosts, get_url};
+    use std::collections::HashMap;
+
+    use site_discovery_flea::{DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT};
+    use site_discovery_flea::domain::domain::{Redirect, RedirectScope, Scheme, Site, VirtualHost};
+    use site_discovery_flea::site::site::{get_sites_from_vhosts, get_url, SiteFormat};
+
+    use site_discovery_flea::output::output::{get_low_level_discovery_json,
+                                              get_low_level_discovery_json_with_data_property};
 
     const CUSTOM_VHOST_PORT: i32 = 5382;
 
@@ -11,7 +16,7 @@ mod main_tests {
         let domain = "quarkoman.com";
         let expected_url = format!("https://{}", domain);
 
-        assert_eq!(get_url(domain, DEFAULT_HTTPS_PORT), expected_url)
+        assert_eq!(get_url(domain, DEFAULT_HTTPS_PORT, Scheme::Https), expected_url)
     }
 
     #[test]
@@ -19,7 +24,7 @@ mod main_tests {
         let domain = "quarkoman.com";
         let expected_url = format!("http://{}", domain);
 
-        assert_eq!(get_url(domain, DEFAULT_HTTP_PORT), expected_url)
+        assert_eq!(get_url(domain, DEFAULT_HTTP_PORT, Scheme::Http), expected_url)
     }
 
     #[test]
@@ -27,7 +32,7 @@ mod main_tests {
         let domain = "quarkoman.com";
         let expected_url = format!("http://{}:{}", domain, CUSTOM_VHOST_PORT);
 
-        assert_eq!(get_url(domain, CUSTOM_VHOST_PORT), expected_url)
+        assert_eq!(get_url(domain, CUSTOM_VHOST_PORT, Scheme::Http), expected_url)
     }
 
     #[test]
@@ -36,14 +41,11 @@ mod main_tests {
 
         let domain = String::from("meduttio.uk");
 
-        let vhost = VirtualHost {
-            domain: String::from(&domain),
-            port: DEFAULT_HTTPS_PORT
-        };
+        let vhost = VirtualHost::new(&domain, DEFAULT_HTTPS_PORT);
 
         vhosts.push(vhost);
 
-        let sites: Vec<Site> = get_sites_from_vhosts(vhosts, true);
+        let sites: Vec<Site> = get_sites_from_vhosts(vhosts, true, false, &HashMap::new(), &SiteFormat::default());
 
         let expected_json: &str = r#"[{"{#NAME}":"meduttio.uk","{#URL}":"https://meduttio.uk"}]"#;
 
@@ -58,22 +60,16 @@ mod main_tests {
 
         let domain1 = String::from("meduttio.uk");
 
-        let vhost1 = VirtualHost {
-            domain: String::from(&domain1),
-            port: DEFAULT_HTTPS_PORT
-        };
+        let vhost1 = VirtualHost::new(&domain1, DEFAULT_HTTPS_PORT);
 
         let domain2 = String::from("www.meduttio.uk");
 
-        let vhost2 = VirtualHost {
-            domain: String::from(&domain2),
-            port: DEFAULT_HTTP_PORT
-        };
+        let vhost2 = VirtualHost::new(&domain2, DEFAULT_HTTP_PORT);
 
         vhosts.push(vhost1);
         vhosts.push(vhost2);
 
-        let sites: Vec<Site> = get_sites_from_vhosts(vhosts, true);
+        let sites: Vec<Site> = get_sites_from_vhosts(vhosts, true, false, &HashMap::new(), &SiteFormat::default());
 
         assert_eq!(2, sites.len());
 
@@ -90,22 +86,16 @@ mod main_tests {
 
         let domain1 = String::from("meduttio.uk");
 
-        let vhost1 = VirtualHost {
-            domain: String::from(&domain1),
-            port: DEFAULT_HTTPS_PORT
-        };
+        let vhost1 = VirtualHost::new(&domain1, DEFAULT_HTTPS_PORT);
 
         let domain2 = String::from("www.meduttio.uk");
 
-        let vhost2 = VirtualHost {
-            domain: String::from(&domain2),
-            port: DEFAULT_HTTP_PORT
-        };
+        let vhost2 = VirtualHost::new(&domain2, DEFAULT_HTTP_PORT);
 
         vhosts.push(vhost1);
         vhosts.push(vhost2);
 
-        let sites: Vec<Site> = get_sites_from_vhosts(vhosts, false);
+        let sites: Vec<Site> = get_sites_from_vhosts(vhosts, false, false, &HashMap::new(), &SiteFormat::default());
 
         assert_eq!(1, sites.len());
 
@@ -119,14 +109,11 @@ mod main_tests {
 
         let domain = String::from("meduttio.uk");
 
-        let vhost = VirtualHost {
-            domain: String::from(&domain),
-            port: DEFAULT_HTTPS_PORT
-        };
+        let vhost = VirtualHost::new(&domain, DEFAULT_HTTPS_PORT);
 
         vhosts.push(vhost);
 
-        let sites: Vec<Site> = get_sites_from_vhosts(vhosts, true);
+        let sites: Vec<Site> = get_sites_from_vhosts(vhosts, true, false, &HashMap::new(), &SiteFormat::default());
 
         let expected_json: &str =
             r#"{"data":[{"{#NAME}":"meduttio.uk","{#URL}":"https://meduttio.uk"}]}"#;
@@ -135,4 +122,33 @@ mod main_tests {
 
         assert_eq!(json, expected_json);
     }
+
+    #[test]
+    fn get_low_level_discovery_json_should_contain_wildcard_for_expanded_names() {
+        let mut vhost = VirtualHost::new("app1.whatever.ru", DEFAULT_HTTPS_PORT);
+        vhost.wildcard = Some("*.whatever.ru".to_string());
+
+        let sites: Vec<Site> = get_sites_from_vhosts(vec![vhost], false, false, &HashMap::new(), &SiteFormat::default());
+
+        let expected_json: &str =
+            r#"[{"{#NAME}":"app1.whatever.ru","{#URL}":"https://app1.whatever.ru","{#WILDCARD}":"*.whatever.ru"}]"#;
+
+        assert_eq!(get_low_level_discovery_json(sites), expected_json);
+    }
+
+    #[test]
+    fn get_low_level_discovery_json_should_contain_redirect_macros_for_redirect_vhosts() {
+        let mut vhost = VirtualHost::new("meduttio.uk", DEFAULT_HTTP_PORT);
+        vhost.redirect = Some(Redirect {
+            code: 301, target: "https://$host$request_uri".to_string(), scope: RedirectScope::VirtualHost
+        });
+
+        let sites: Vec<Site> = get_sites_from_vhosts(vec![vhost], false, false, &HashMap::new(), &SiteFormat::default());
+
+        let expected_json: &str = concat!(r#"[{"{#NAME}":"meduttio.uk_http","{#REDIRECT_CODE}":"301","#,
+                                          r#""{#REDIRECT_TARGET}":"https://meduttio.uk/","{#TYPE}":"redirect","#,
+                                          r#""{#URL}":"http://meduttio.uk"}]"#);
+
+        assert_eq!(get_low_level_discovery_json(sites), expected_json);
+    }
 }
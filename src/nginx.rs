@@ -1,75 +1,640 @@
 pub mod nginx {
-    use std::path::Path;
-    use std::process::exit;
+    use std::fs;
+    use std::path::{Path, PathBuf};
 
-    use regex::Regex;
+    use crate::DEFAULT_HTTP_PORT;
+    use crate::domain::domain::{ListenEndpoint, Provenance, Redirect, RedirectScope, TlsConfig, VirtualHost};
+    use crate::error::error::DiscoveryError;
+    use crate::source::source::{SourceSettings, WebServerSource};
+    use crate::webserver::webserver::{apply_tls_config, ConfigScanOptions, Directive, expand_include_pattern,
+                                      get_annotations, get_port, get_server_names, get_vhosts_from_dirs, has_wildcards,
+                                      get_vhost_redirect, get_vhosts_config_root, is_match_all_pattern, is_redirect_url,
+                                      ParseError, REDIRECT_CODES, resolve_certificate_files};
 
-    use crate::domain::domain::VirtualHost;
-    use crate::ERROR_EXIT_CODE;
-    use crate::webserver::webserver::{get_vhost_config_file_list, get_virtual_hosts_from_file};
+    const SERVER_DIRECTIVE: &str = "server";
+    const LISTEN_DIRECTIVE: &str = "listen";
+    const SERVER_NAME_DIRECTIVE: &str = "server_name";
+    const RETURN_DIRECTIVE: &str = "return";
+    const REWRITE_DIRECTIVE: &str = "rewrite";
+    const LOCATION_DIRECTIVE: &str = "location";
+    const INCLUDE_DIRECTIVE: &str = "include";
+    const SSL_DIRECTIVE: &str = "ssl";
+    const SSL_CERTIFICATE_DIRECTIVE: &str = "ssl_certificate";
 
-    pub fn get_nginx_vhosts(nginx_vhosts_path: &Path, detect_302_redirects: bool) -> Vec<VirtualHost> {
+    /// Blocks which may contain `server` blocks unrelated to http virtual hosts.
+    const NON_HTTP_BLOCKS: [&str; 3] = ["stream", "mail", "upstream"];
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Token {
+        Word(String, usize),
+        Semicolon(usize),
+        BlockStart(usize),
+        BlockEnd(usize)
+    }
+
+    pub const NGINX_SOURCE_NAME: &str = "nginx";
+
+    pub const NGINX_VHOSTS_PATH: &str = "/etc/nginx/conf.d";
+    pub const NGINX_CONFIG_PATH: &str = "/etc/nginx/nginx.conf";
+
+    /// nginx source for discovery registry.
+    pub struct NginxSource;
+
+    impl WebServerSource for NginxSource {
+        fn name(&self) -> &'static str {
+            NGINX_SOURCE_NAME
+        }
+
+        fn default_vhosts_path(&self) -> &'static str {
+            NGINX_VHOSTS_PATH
+        }
+
+        fn default_config_path(&self) -> &'static str {
+            NGINX_CONFIG_PATH
+        }
+
+        fn vhosts_path_short_argument(&self) -> Option<&'static str> {
+            Some("n")
+        }
+
+        fn discover(&self, settings: &SourceSettings,
+                    errors: &mut Vec<DiscoveryError>) -> Result<Vec<VirtualHost>, DiscoveryError> {
+            match &settings.config_path {
+                Some(config_path) => get_nginx_vhosts_from_main_config(
+                    config_path, settings.detect_302_redirects, settings.include_redirects, settings.include_unnamed, errors),
+                None => Ok(get_nginx_vhosts(&settings.vhosts_paths, &settings.scan_options,
+                                            settings.detect_302_redirects, settings.include_redirects,
+                                            settings.include_unnamed, errors))
+            }
+        }
+    }
+
+    /// Returns virtual hosts from config files of directories.
+    /// Unreadable directories and problems with single files are added to `errors`.
+    pub fn get_nginx_vhosts(nginx_vhosts_paths: &[PathBuf], scan_options: &ConfigScanOptions,
+                            detect_302_redirects: bool, include_redirects: bool, include_unnamed: bool,
+                            errors: &mut Vec<DiscoveryError>) -> Vec<VirtualHost> {
         debug!("get virtual hosts from nginx configs");
-        debug!("configs path '{}'", nginx_vhosts_path.display());
 
-        let mut vhosts: Vec<VirtualHost> = Vec::new();
+        get_vhosts_from_dirs(nginx_vhosts_paths, scan_options, errors, |vhost_file, errors| {
+            let mut vhosts = get_nginx_vhosts_from_file(vhost_file, detect_302_redirects, include_redirects,
+                                                        include_unnamed, errors)?;
+            resolve_certificate_files(&mut vhosts, &get_vhosts_config_root(nginx_vhosts_paths, vhost_file));
+            Ok(vhosts)
+        })
+    }
 
-        if nginx_vhosts_path.exists() && nginx_vhosts_path.is_dir() {
-            match get_vhost_config_file_list(nginx_vhosts_path) {
-                Ok(vhost_files) => {
-                    for vhost_file in vhost_files {
-                        debug!("processing vhost file '{}'", vhost_file.display());
-
-                        let section_start_regex = get_nginx_vhost_section_start_regex();
-                        let redirect_with_301_regex = get_nginx_redirect_with_301_regex(detect_302_redirects);
-                        let port_search_regex = get_nginx_vhost_port_regex();
-                        let domain_search_regex = get_domain_search_regex_for_nginx_vhost();
-
-                        let vhost_file_path = vhost_file.as_path();
-
-                        if let Ok(nginx_vhosts) = get_virtual_hosts_from_file(
-                            vhost_file_path,
-                            section_start_regex,
-                            redirect_with_301_regex,
-                            port_search_regex,
-                            domain_search_regex,
-                        ) {
-                            for nginx_vhost in nginx_vhosts {
-                                debug!("{}", nginx_vhost.to_string());
-                                vhosts.push(nginx_vhost);
-                            }
+    /// Discovers virtual hosts starting from main nginx config (`nginx.conf`)
+    /// and following `include` directives the same way nginx does.
+    /// Problems with included files are added to `errors`, unreadable main config is returned as error.
+    pub fn get_nginx_vhosts_from_main_config(config_path: &Path, detect_302_redirects: bool, include_redirects: bool,
+                                             include_unnamed: bool, errors: &mut Vec<DiscoveryError>) -> Result<Vec<VirtualHost>, DiscoveryError> {
+        debug!("get virtual hosts from nginx main config '{}'", config_path.display());
 
-                        } else { error!("unable to get virtual hosts form file") }
-                    }
+        let directives = load_nginx_config(config_path, errors)?;
+
+        let mut vhosts = get_nginx_vhosts_from_directives(&directives, detect_302_redirects, include_redirects,
+                                                          include_unnamed, errors);
+        resolve_certificate_files(&mut vhosts, config_path.parent().unwrap_or(Path::new("")));
+        vhosts.iter().for_each(|vhost| debug!("{}", vhost.to_string()));
+
+        Ok(vhosts)
+    }
+
+    /// Reads nginx config file and replaces `include` directives with directives from included files.
+    /// Relative include paths are resolved against config prefix, the directory of main config file.
+    /// Included files which can't be read or parsed are added to `errors` and skipped.
+    pub fn load_nginx_config(config_path: &Path,
+                             errors: &mut Vec<DiscoveryError>) -> Result<Vec<Directive>, DiscoveryError> {
+        let prefix = config_path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let mut include_stack: Vec<PathBuf> = Vec::new();
+        load_nginx_config_file(config_path, &prefix, &mut include_stack, errors)
+    }
+
+    fn load_nginx_config_file(config_path: &Path, prefix: &Path, include_stack: &mut Vec<PathBuf>,
+                              errors: &mut Vec<DiscoveryError>) -> Result<Vec<Directive>, DiscoveryError> {
+        info!("read nginx config '{}'", config_path.display());
+
+        let directives = read_nginx_config(config_path)?;
+
+        let canonical_path = fs::canonicalize(config_path)
+            .map_err(|e| DiscoveryError::from_io_error(config_path, &e))?;
+
+        include_stack.push(canonical_path);
+        let resolved_directives = resolve_includes(directives, prefix, include_stack, errors);
+        include_stack.pop();
+
+        Ok(resolved_directives)
+    }
+
+    fn read_nginx_config(config_path: &Path) -> Result<Vec<Directive>, DiscoveryError> {
+        let content = fs::read_to_string(config_path)
+            .map_err(|e| DiscoveryError::from_io_error(config_path, &e))?;
+
+        Ok(parse_nginx_config(&content, config_path)?)
+    }
+
+    fn resolve_includes(directives: Vec<Directive>, prefix: &Path, include_stack: &mut Vec<PathBuf>,
+                        errors: &mut Vec<DiscoveryError>) -> Vec<Directive> {
+        let mut results: Vec<Directive> = Vec::new();
+
+        for mut directive in directives {
+            if directive.name == INCLUDE_DIRECTIVE && directive.block.is_none() {
+                results.append(&mut get_included_directives(&directive, prefix, include_stack, errors));
+                continue;
+            }
+
+            if let Some(block) = directive.block.take() {
+                directive.block = Some(resolve_includes(block, prefix, include_stack, errors));
+            }
+
+            results.push(directive);
+        }
+
+        results
+    }
+
+    fn get_included_directives(include: &Directive, prefix: &Path, include_stack: &mut Vec<PathBuf>,
+                               errors: &mut Vec<DiscoveryError>) -> Vec<Directive> {
+        let mut directives: Vec<Directive> = Vec::new();
+
+        let pattern = match include.args.first() {
+            Some(pattern) => pattern,
+            None => {
+                errors.push(DiscoveryError::ParseError {
+                    file: include.file.to_path_buf(), line: include.line, message: "include without file".to_string()
+                });
+                return directives
+            }
+        };
+
+        let files = expand_include_pattern(&prefix.join(pattern));
+
+        if files.is_empty() && has_wildcards(pattern) {
+            debug!("no files match include '{}' at {}:{}", pattern, include.file.display(), include.line);
+        }
+
+        for file in files {
+            if let Ok(canonical_path) = fs::canonicalize(&file) {
+                if include_stack.contains(&canonical_path) {
+                    error!("include cycle detected, file '{}' is already included, skip include at {}:{}",
+                           file.display(), include.file.display(), include.line);
+                    continue;
+                }
+            }
+
+            match load_nginx_config_file(&file, prefix, include_stack, errors) {
+                Ok(mut included_directives) => directives.append(&mut included_directives),
+                Err(e) => {
+                    debug!("unable to include '{}' at {}:{}: {}", file.display(), include.file.display(), include.line, e);
+                    errors.push(e);
                 }
-                Err(_error) => {
-                    error!("unable to get vhost file list from '{}', \
-                       possible reason: lack of permissions", nginx_vhosts_path.display());
-                    exit(ERROR_EXIT_CODE)
+            }
+        }
+
+        directives
+    }
+
+    pub fn get_nginx_vhosts_from_file(vhost_file: &Path, detect_302_redirects: bool, include_redirects: bool,
+                                      include_unnamed: bool, errors: &mut Vec<DiscoveryError>) -> Result<Vec<VirtualHost>, DiscoveryError> {
+        info!("get virtual hosts from file '{}'", vhost_file.display());
+
+        let directives = read_nginx_config(vhost_file)?;
+
+        Ok(get_nginx_vhosts_from_directives(&directives, detect_302_redirects, include_redirects, include_unnamed, errors))
+    }
+
+    /// Walks directive tree and returns virtual hosts for every http `server` block.
+    pub fn get_nginx_vhosts_from_directives(directives: &[Directive], detect_302_redirects: bool, include_redirects: bool,
+                                            include_unnamed: bool, errors: &mut Vec<DiscoveryError>) -> Vec<VirtualHost> {
+        let mut vhosts: Vec<VirtualHost> = Vec::new();
+
+        for directive in directives {
+            if directive.block.is_none() || NON_HTTP_BLOCKS.contains(&directive.name.as_str()) {
+                continue;
+            }
+
+            if directive.name == SERVER_DIRECTIVE {
+                if let Some(vhost) = get_vhost_from_server_block(directive, detect_302_redirects, include_redirects,
+                                                                 include_unnamed, errors) {
+                    vhosts.push(vhost);
                 }
+
+            } else {
+                vhosts.append(&mut get_nginx_vhosts_from_directives(directive.children(), detect_302_redirects,
+                                                                  include_redirects, include_unnamed, errors));
+            }
+        }
+
+        vhosts
+    }
+
+    fn get_vhost_from_server_block(server: &Directive, detect_302_redirects: bool, include_redirects: bool,
+                                   include_unnamed: bool, errors: &mut Vec<DiscoveryError>) -> Option<VirtualHost> {
+        trace!("server block at {}:{}", server.file.display(), server.line);
+
+        let redirect = get_vhost_redirect(get_redirects(server, RedirectScope::VirtualHost, detect_302_redirects));
+
+        if redirect.is_some() && !include_redirects {
+            return None
+        }
+
+        let listen_directives = server.find_all(LISTEN_DIRECTIVE);
+
+        let mut listens: Vec<ListenEndpoint> = listen_directives.iter()
+            .filter_map(|listen| get_listen_endpoint(listen, errors)).collect();
+
+        if listen_directives.is_empty() {
+            debug!("listen directive not found, nginx default '*:{}' is used", DEFAULT_HTTP_PORT);
+            listens.push(ListenEndpoint::new(DEFAULT_HTTP_PORT));
+        }
+
+        if listens.is_empty() {
+            return None
+        }
+
+        let tls = get_tls_config(server);
+        apply_tls_config(&mut listens, &tls);
+
+        let raw_server_names: Vec<String> = server.find_all(SERVER_NAME_DIRECTIVE).into_iter()
+            .flat_map(|server_name| server_name.args.iter()).cloned().collect();
+
+        let server_name_values: Vec<String> = raw_server_names.iter()
+            .map(|name| normalize_server_name(name)).collect();
+
+        let mut names = get_server_names(server_name_values);
+
+        if names.is_empty() && !include_unnamed {
+            if listens.iter().any(|listen| listen.default_server) {
+                debug!("catch-all default_server block skipped at {}:{}", server.file.display(), server.line);
+            } else {
+                debug!("server block without server_name skipped at {}:{}", server.file.display(), server.line);
             }
+            return None
+        }
+
+        // unnamed virtual hosts are kept with empty domain for config checks
+        let domain = if names.is_empty() { String::new() } else { names.remove(0) };
+
+        debug!("domain found {}", domain);
+
+        if !names.is_empty() {
+            debug!("aliases found {}", names.join(" "));
         }
 
-        return vhosts;
+        let provenance = Provenance {
+            server: NGINX_SOURCE_NAME.to_string(),
+            file: server.file.to_path_buf(),
+            line: server.line,
+            listen: listen_directives.iter().map(|listen| listen.args.join(" ")).collect(),
+            server_names: raw_server_names
+        };
+
+        let annotations = get_annotations(&server.file, server.line);
+
+        Some(VirtualHost {
+            domain, aliases: names, listens, tls, wildcard: None, redirect, provenance: Some(provenance), annotations
+        })
     }
 
-    fn get_domain_search_regex_for_nginx_vhost() -> Regex {
-        return Regex::new("(?:^|^[^#]+)server_name[\\s\t]+([a-z0-9.\\-]+).*;").unwrap();
+    /// Name with leading dot (`.example.com`) matches exact name and all subdomains, exact name is used.
+    fn normalize_server_name(name: &str) -> String {
+        match name.strip_prefix('.') {
+            Some(exact_name) if !exact_name.is_empty() => {
+                debug!("server name '{}' normalized to '{}'", name, exact_name);
+                exact_name.to_string()
+            }
+            _ => name.to_string()
+        }
     }
 
-    fn get_nginx_vhost_section_start_regex() -> Regex {
-        return Regex::new("(?:^|^[^#]+)server[\\s\t]+\\{").unwrap();
+    fn get_tls_config(server: &Directive) -> TlsConfig {
+        let engine = server.find_all(SSL_DIRECTIVE).into_iter().last()
+            .and_then(|ssl| ssl.args.first())
+            .map(|value| value == "on");
+
+        let certificate_files = server.find_all(SSL_CERTIFICATE_DIRECTIVE).into_iter()
+            .filter_map(|certificate| certificate.args.first().cloned()).collect();
+
+        TlsConfig { engine, certificate_files }
     }
 
-    fn get_nginx_redirect_with_301_regex(detect_302_redirects: bool) -> Regex {
-        return if detect_302_redirects {
-            Regex::new("(?:^|^[^#]+)return[\\s\t]+(?:301|302)[\\s\t]+http.*[\\s\t]*$").unwrap()
+    /// Parses `listen` directive: `80`, `127.0.0.1:8080`, `[::]:443 ssl`, `localhost` etc.
+    /// Address without port means port 80, unix sockets are skipped.
+    fn get_listen_endpoint(listen: &Directive, errors: &mut Vec<DiscoveryError>) -> Option<ListenEndpoint> {
+        let value = listen.args.first()?;
+
+        if value.starts_with("unix:") {
+            debug!("unix socket listen '{}' skipped at {}:{}", value, listen.file.display(), listen.line);
+            return None
+        }
+
+        let (address, port_value, ipv6) = if let Some(ipv6_value) = value.strip_prefix('[') {
+            match ipv6_value.split_once(']') {
+                Some((address, port)) => (Some(address), port.strip_prefix(':'), true),
+                None => {
+                    errors.push(DiscoveryError::ParseError {
+                        file: listen.file.to_path_buf(), line: listen.line,
+                        message: format!("unable to parse listen address '{}'", value)
+                    });
+                    return None
+                }
+            }
+
+        } else if let Some((address, port)) = value.rsplit_once(':') {
+            (Some(address), Some(port), false)
+
+        } else if value.chars().all(|ch| ch.is_ascii_digit()) {
+            (None, Some(value.as_str()), false)
+
         } else {
-            Regex::new("(?:^|^[^#]+)return[\\s\t]+301[\\s\t]+http.*[\\s\t]*$").unwrap()
+            (Some(value.as_str()), None, false)
+        };
+
+        let port = match port_value {
+            Some(port_value) => match get_port(port_value) {
+                Some(port) => port,
+                None => {
+                    errors.push(DiscoveryError::InvalidPort {
+                        file: listen.file.to_path_buf(), line: listen.line, value: port_value.to_string()
+                    });
+                    return None
+                }
+            },
+            None => DEFAULT_HTTP_PORT
+        };
+
+        let mut endpoint = ListenEndpoint::new(port);
+        endpoint.address = address.filter(|address| *address != "*").map(|address| address.to_string());
+        endpoint.ipv6 = ipv6;
+
+        for parameter in &listen.args[1..] {
+            match parameter.as_str() {
+                "default_server" | "default" => endpoint.default_server = true,
+                "ssl" => endpoint.ssl = true,
+                "http2" => endpoint.http2 = true,
+                "quic" => endpoint.quic = true,
+                _ => {}
+            }
         }
+
+        debug!("listen found {}", endpoint);
+
+        Some(endpoint)
+    }
+
+    /// Returns redirects of block. Redirects in `server` context and in the only `location /`
+    /// apply to the whole virtual host, ones from other locations and `if` blocks apply to part of requests.
+    fn get_redirects(block: &Directive, scope: RedirectScope, detect_302_redirects: bool) -> Vec<Redirect> {
+        let mut redirects: Vec<Redirect> = Vec::new();
+
+        let locations_count = block.find_all(LOCATION_DIRECTIVE).len();
+
+        for directive in block.children() {
+            if directive.block.is_some() {
+                let root_location = directive.name == LOCATION_DIRECTIVE && locations_count == 1 &&
+                                    is_root_location(directive);
+
+                let nested_scope = if scope == RedirectScope::VirtualHost && root_location {
+                    RedirectScope::VirtualHost
+                } else {
+                    RedirectScope::Location(format!("{} {}", directive.name, directive.args.join(" ")))
+                };
+
+                redirects.append(&mut get_redirects(directive, nested_scope, detect_302_redirects));
+
+            } else if let Some(redirect) = get_redirect(directive, &scope) {
+                if redirect.is_permanent() || detect_302_redirects {
+                    redirects.push(redirect);
+
+                } else {
+                    debug!("temporary redirect ignored at {}:{}", directive.file.display(), directive.line);
+                }
+            }
+        }
+
+        redirects
+    }
+
+    /// `location /` and `location ^~ /` match any request URI.
+    fn is_root_location(location: &Directive) -> bool {
+        match location.args.as_slice() {
+            [uri] => uri == "/",
+            [modifier, uri] => modifier == "^~" && uri == "/",
+            _ => false
+        }
+    }
+
+    /// Recognizes `return 301 https://..`, `return https://..` (302)
+    /// and `rewrite <regex> <replacement> [permanent|redirect]`.
+    fn get_redirect(directive: &Directive, scope: &RedirectScope) -> Option<Redirect> {
+        if directive.name == RETURN_DIRECTIVE {
+            let (code, target) = match directive.args.as_slice() {
+                [code, target] => (code.parse().ok()?, target),
+                [target] => (302, target),
+                _ => return None
+            };
+
+            if REDIRECT_CODES.contains(&code) && is_redirect_url(target) {
+                return Some(Redirect { code, target: target.to_string(), scope: scope.clone() })
+            }
+
+        } else if directive.name == REWRITE_DIRECTIVE && directive.args.len() > 1 {
+            let pattern = &directive.args[0];
+            let replacement = &directive.args[1];
+
+            let code = match directive.args.get(2).map(|flag| flag.as_str()) {
+                Some("permanent") => 301,
+                Some("redirect") => 302,
+                _ if is_redirect_url(replacement) => 302,
+                _ => return None
+            };
+
+            let scope = if is_match_all_pattern(pattern) {
+                scope.clone()
+            } else {
+                RedirectScope::Location(format!("rewrite {}", pattern))
+            };
+
+            return Some(Redirect { code, target: replacement.to_string(), scope })
+        }
+
+        None
+    }
+
+    /// Parses nginx configuration into directive tree.
+    pub fn parse_nginx_config(content: &str, file: &Path) -> Result<Vec<Directive>, ParseError> {
+        let tokens = tokenize(content, file)?;
+
+        let mut stack: Vec<(Directive, Vec<Directive>)> = Vec::new();
+        let mut current: Vec<Directive> = Vec::new();
+        let mut words: Vec<(String, usize)> = Vec::new();
+
+        for token in tokens {
+            match token {
+                Token::Word(word, line) => words.push((word, line)),
+                Token::Semicolon(line) => {
+                    let directive = get_directive(&mut words, file, line, ';')?;
+                    current.push(directive);
+                }
+                Token::BlockStart(line) => {
+                    let directive = get_directive(&mut words, file, line, '{')?;
+                    stack.push((directive, current));
+                    current = Vec::new();
+                }
+                Token::BlockEnd(line) => {
+                    if !words.is_empty() {
+                        return Err(get_parse_error(file, line, "unexpected '}', expecting ';'"))
+                    }
+
+                    match stack.pop() {
+                        Some((mut directive, parent)) => {
+                            directive.block = Some(current);
+                            current = parent;
+                            current.push(directive);
+                        }
+                        None => return Err(get_parse_error(file, line, "unexpected '}'"))
+                    }
+                }
+            }
+        }
+
+        if let Some((word, line)) = words.first() {
+            return Err(get_parse_error(file, *line,
+                                       &format!("unexpected end of file after '{}', expecting ';' or '{{'", word)))
+        }
+
+        if let Some((directive, _)) = stack.last() {
+            return Err(get_parse_error(file, directive.line,
+                                       &format!("unexpected end of file, block '{}' is not closed", directive.name)))
+        }
+
+        Ok(current)
+    }
+
+    fn get_directive(words: &mut Vec<(String, usize)>, file: &Path,
+                     line: usize, terminator: char) -> Result<Directive, ParseError> {
+        if words.is_empty() {
+            return Err(get_parse_error(file, line, &format!("unexpected '{}'", terminator)))
+        }
+
+        let (name, directive_line) = words.remove(0);
+        let args = words.drain(..).map(|(word, _)| word).collect();
+
+        Ok(Directive { name, args, file: file.to_path_buf(), line: directive_line, block: None })
+    }
+
+    /// Splits nginx configuration into words and control characters.
+    /// Handles comments, quoted values, escapes and `${var}` syntax.
+    pub fn tokenize(content: &str, file: &Path) -> Result<Vec<Token>, ParseError> {
+        let mut tokens: Vec<Token> = Vec::new();
+
+        let mut chars = content.chars().peekable();
+        let mut line: usize = 1;
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\n' => line += 1,
+                ' ' | '\t' | '\r' => {}
+                '#' => {
+                    while let Some(&next) = chars.peek() {
+                        if next == '\n' { break }
+                        chars.next();
+                    }
+                }
+                ';' => tokens.push(Token::Semicolon(line)),
+                '{' => tokens.push(Token::BlockStart(line)),
+                '}' => tokens.push(Token::BlockEnd(line)),
+                '"' | '\'' => {
+                    let start_line = line;
+                    let mut word = String::new();
+                    let mut closed = false;
+
+                    while let Some(next) = chars.next() {
+                        if next == ch {
+                            closed = true;
+                            break
+                        }
+
+                        if next == '\n' { line += 1 }
+
+                        if next == '\\' {
+                            if let Some(escaped) = chars.next() {
+                                if escaped == '\n' { line += 1 }
+                                push_escaped_char(&mut word, escaped, ch);
+                            }
+                        } else {
+                            word.push(next);
+                        }
+                    }
+
+                    if !closed {
+                        return Err(get_parse_error(file, start_line, "unexpected end of file, quote is not closed"))
+                    }
+
+                    tokens.push(Token::Word(word, start_line));
+                }
+                _ => {
+                    let mut word = String::new();
+                    let mut current = ch;
+
+                    loop {
+                        if current == '\\' {
+                            // like nginx, keep backslash unless it escapes quote, backslash or t/r/n,
+                            // regex names such as `~^www\.(.+)$` rely on it
+                            match chars.next() {
+                                Some(escaped) if escaped == '"' || escaped == '\'' => word.push(escaped),
+                                Some(escaped) => push_escaped_char(&mut word, escaped, '\\'),
+                                None => word.push(current)
+                            }
+
+                        } else {
+                            word.push(current);
+
+                            if current == '$' && chars.peek() == Some(&'{') {
+                                for variable_char in chars.by_ref() {
+                                    word.push(variable_char);
+                                    if variable_char == '}' { break }
+                                }
+                            }
+                        }
+
+                        match chars.peek() {
+                            Some(&next) if !is_word_delimiter(next) => {
+                                current = next;
+                                chars.next();
+                            }
+                            _ => break
+                        }
+                    }
+
+                    tokens.push(Token::Word(word, line));
+                }
+            }
+        }
+
+        Ok(tokens)
+    }
+
+    fn push_escaped_char(word: &mut String, escaped: char, quote: char) {
+        match escaped {
+            'n' => word.push('\n'),
+            't' => word.push('\t'),
+            'r' => word.push('\r'),
+            '\\' => word.push('\\'),
+            _ if escaped == quote => word.push(escaped),
+            _ => {
+                word.push('\\');
+                word.push(escaped);
+            }
+        }
+    }
+
+    fn is_word_delimiter(ch: char) -> bool {
+        ch.is_whitespace() || ch == ';' || ch == '{' || ch == '}'
     }
 
-    fn get_nginx_vhost_port_regex() -> Regex {
-        return Regex::new("(?:^|^[^#]+)[\\s\t]*listen[\\s\t]+(\\d+).*;").unwrap();
+    fn get_parse_error(file: &Path, line: usize, message: &str) -> ParseError {
+        ParseError { file: file.to_path_buf(), line, message: message.to_string() }
     }
 }
@@ -1,19 +1,29 @@
 #[cfg(test)]
 pub mod nginx_tests {
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
 
-    use crate::DEFAULT_HTTPS_PORT;
-    use crate::nginx::nginx::get_nginx_vhosts;
+    use crate::{DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT};
+    use crate::domain::domain::VirtualHost;
+    use crate::error::error::DiscoveryError;
+    use crate::nginx::nginx::{get_nginx_vhosts, get_nginx_vhosts_from_main_config, load_nginx_config,
+                              parse_nginx_config};
     use crate::test_utils::test_utils::assert_vhost_in_vec;
+    use crate::webserver::webserver::ConfigScanOptions;
 
     const SAMPLE_DOMAIN: &str = "whatever.ru";
     const SAMPLE_DOMAIN2: &str = "gallery.whatever.ru";
 
+    fn get_vhosts_from_dir(path: &Path, detect_302_redirects: bool, include_redirects: bool,
+                           errors: &mut Vec<DiscoveryError>) -> Vec<VirtualHost> {
+        get_nginx_vhosts(&[path.to_path_buf()], &ConfigScanOptions::default(), detect_302_redirects,
+                         include_redirects, false, errors)
+    }
+
     #[test]
     fn get_nginx_vhosts_from_path() {
         let nginx_vhost_path = Path::new("tests/nginx-vhosts");
 
-        let vhosts = get_nginx_vhosts(&nginx_vhost_path);
+        let vhosts = get_vhosts_from_dir(nginx_vhost_path, false, false, &mut Vec::new());
 
         vhosts.iter().for_each(|vhost| println!("{}", vhost.to_string()));
 
@@ -28,11 +38,305 @@ pub mod nginx_tests {
     fn ignore_vhost_server_without_server_name_property() {
         let nginx_vhost_path = Path::new("tests/nginx-vhosts");
 
-        let vhosts = get_nginx_vhosts(&nginx_vhost_path);
+        let vhosts = get_vhosts_from_dir(nginx_vhost_path, false, false, &mut Vec::new());
 
         vhosts.iter().for_each(|vhost| println!("{}", vhost.to_string()));
 
         let expected_size: usize = 2;
         assert_eq!(&vhosts.len(), &expected_size);
     }
+
+    #[test]
+    fn get_nginx_vhosts_from_single_line_and_multi_line_blocks() {
+        let nginx_vhost_path = Path::new("tests/nginx-parser");
+
+        let vhosts = get_vhosts_from_dir(nginx_vhost_path, false, false, &mut Vec::new());
+
+        vhosts.iter().for_each(|vhost| println!("{}", vhost.to_string()));
+
+        assert_eq!(vhosts.len(), 3);
+
+        assert_vhost_in_vec(&vhosts, "a.whatever.ru", DEFAULT_HTTPS_PORT);
+        assert_vhost_in_vec(&vhosts, "b.whatever.ru", DEFAULT_HTTP_PORT);
+        assert_vhost_in_vec(&vhosts, "c.whatever.ru", 8080);
+    }
+
+    #[test]
+    fn parse_nginx_config_should_return_directive_tree_with_lines() {
+        let config = "http {\n    server { listen 80; server_name a.ru b.ru; }\n\n    server {\n        location / {\n            return 403;\n        }\n    }\n}";
+
+        let directives = parse_nginx_config(config, Path::new("nginx.conf")).unwrap();
+
+        assert_eq!(directives.len(), 1);
+
+        let http = &directives[0];
+        assert_eq!(http.name, "http");
+        assert_eq!(http.line, 1);
+
+        let servers = http.find_all("server");
+        assert_eq!(servers.len(), 2);
+
+        let server_name = servers[0].find_first("server_name").unwrap();
+        assert_eq!(server_name.args, vec!["a.ru", "b.ru"]);
+        assert_eq!(server_name.line, 2);
+
+        let location = servers[1].find_first("location").unwrap();
+        assert_eq!(location.args, vec!["/"]);
+        assert_eq!(location.line, 5);
+
+        let return_directive = location.find_first("return").unwrap();
+        assert_eq!(return_directive.args, vec!["403"]);
+        assert_eq!(return_directive.line, 6);
+    }
+
+    #[test]
+    fn parse_nginx_config_should_support_quotes_comments_and_variables() {
+        let config = "add_header X-Test \"a b; {c}\"; # comment; {\nreturn 301 https://${host}$request_uri;";
+
+        let directives = parse_nginx_config(config, Path::new("nginx.conf")).unwrap();
+
+        assert_eq!(directives.len(), 2);
+        assert_eq!(directives[0].args, vec!["X-Test", "a b; {c}"]);
+        assert_eq!(directives[1].args, vec!["301", "https://${host}$request_uri"]);
+        assert_eq!(directives[1].line, 2);
+    }
+
+    #[test]
+    fn parse_nginx_config_should_return_error_with_line_for_unclosed_block() {
+        let config = "server {\n    listen 80;\n\n    location / {\n}\n";
+
+        let error = parse_nginx_config(config, Path::new("broken.conf")).unwrap_err();
+
+        assert_eq!(error.line, 1);
+        assert_eq!(error.to_string(), "broken.conf:1: unexpected end of file, block 'server' is not closed");
+    }
+
+    #[test]
+    fn parse_nginx_config_should_return_error_for_unexpected_block_end() {
+        let config = "server {\n    listen 80;\n}\n}";
+
+        let error = parse_nginx_config(config, Path::new("broken.conf")).unwrap_err();
+
+        assert_eq!(error.line, 4);
+    }
+
+    #[test]
+    fn get_nginx_vhosts_from_main_config_should_follow_includes() {
+        let vhosts = get_nginx_vhosts_from_main_config(Path::new("tests/nginx-main/nginx.conf"), false, false, false, &mut Vec::new()).unwrap();
+
+        vhosts.iter().for_each(|vhost| println!("{}", vhost.to_string()));
+
+        assert_eq!(vhosts.len(), 3);
+
+        assert_vhost_in_vec(&vhosts, "one.whatever.ru", DEFAULT_HTTP_PORT);
+        assert_vhost_in_vec(&vhosts, "two.whatever.ru", DEFAULT_HTTPS_PORT);
+        assert_vhost_in_vec(&vhosts, "three.whatever.ru", 8081);
+    }
+
+    #[test]
+    fn nginx_vhosts_should_keep_position_of_server_block_from_included_file() {
+        let vhosts = get_nginx_vhosts_from_main_config(Path::new("tests/nginx-main/nginx.conf"), false, false, false, &mut Vec::new()).unwrap();
+
+        let vhost = vhosts.iter().find(|vhost| vhost.domain == "two.whatever.ru").unwrap();
+        let provenance = vhost.provenance.as_ref().unwrap();
+
+        assert_eq!(provenance.server, "nginx");
+        assert_eq!(provenance.file, PathBuf::from("tests/nginx-main/conf.d/two.conf"));
+        assert_eq!(provenance.line, 1);
+        assert_eq!(provenance.listen, vec!["443 ssl"]);
+        assert_eq!(provenance.server_names, vec!["two.whatever.ru"]);
+    }
+
+    #[test]
+    fn load_nginx_config_should_replace_includes_with_file_directives() {
+        let directives = load_nginx_config(Path::new("tests/nginx-main/nginx.conf"), &mut Vec::new()).unwrap();
+
+        let http = directives.iter().find(|directive| directive.name == "http").unwrap();
+
+        assert!(http.find_first("include").is_none());
+
+        let servers = http.find_all("server");
+        assert_eq!(servers.len(), 3);
+
+        let listen = servers[1].find_first("listen").unwrap();
+        assert_eq!(listen.args, vec!["443", "ssl"]);
+        assert_eq!(listen.file, Path::new("tests/nginx-main/snippets/listen-ssl.conf"));
+        assert_eq!(listen.line, 1);
+    }
+
+    #[test]
+    fn load_nginx_config_should_skip_include_cycles() {
+        let directives = load_nginx_config(Path::new("tests/nginx-main/nginx.conf"), &mut Vec::new()).unwrap();
+
+        let http = directives.iter().find(|directive| directive.name == "http").unwrap();
+        let server = http.find_first("server").unwrap();
+
+        assert_eq!(server.find_all("add_header").len(), 1);
+        assert!(server.find_first("include").is_none());
+    }
+
+    #[test]
+    fn nginx_vhost_should_contain_all_server_names() {
+        let vhosts = get_vhosts_from_dir(Path::new("tests/nginx-parser"), false, false, &mut Vec::new());
+
+        let vhost = vhosts.iter().find(|vhost| vhost.domain == "b.whatever.ru").unwrap();
+
+        assert_eq!(vhost.aliases, vec!["www.b.whatever.ru"]);
+    }
+
+    #[test]
+    fn nginx_vhost_should_contain_all_listen_endpoints() {
+        let vhosts = get_vhosts_from_dir(Path::new("tests/nginx-listen"), false, false, &mut Vec::new());
+
+        vhosts.iter().for_each(|vhost| println!("{}", vhost.to_string()));
+
+        assert_eq!(vhosts.len(), 4);
+
+        let dual_stack_vhost = vhosts.iter().find(|vhost| vhost.domain == "dual.whatever.ru").unwrap();
+        assert_eq!(dual_stack_vhost.listens.len(), 4);
+        assert_eq!(dual_stack_vhost.get_ports(), vec![DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT]);
+
+        let ipv6_endpoint = &dual_stack_vhost.listens[3];
+        assert_eq!(ipv6_endpoint.address, Some("::".to_string()));
+        assert_eq!(ipv6_endpoint.port, DEFAULT_HTTPS_PORT);
+        assert!(ipv6_endpoint.ipv6);
+        assert!(ipv6_endpoint.ssl);
+        assert!(ipv6_endpoint.http2);
+
+        let local_vhost = vhosts.iter().find(|vhost| vhost.domain == "local.whatever.ru").unwrap();
+        assert_eq!(local_vhost.listens[0].address, Some("127.0.0.1".to_string()));
+        assert_eq!(local_vhost.listens[0].port, 8080);
+        assert!(local_vhost.listens[0].default_server);
+
+        let quic_vhost = vhosts.iter().find(|vhost| vhost.domain == "quic.whatever.ru").unwrap();
+        assert_eq!(quic_vhost.listens[0].to_string(), "[::1]:8443 quic");
+    }
+
+    #[test]
+    fn nginx_vhost_without_listen_should_use_default_http_port() {
+        let vhosts = get_vhosts_from_dir(Path::new("tests/nginx-listen"), false, false, &mut Vec::new());
+
+        assert_vhost_in_vec(&vhosts, "nolisten.whatever.ru", DEFAULT_HTTP_PORT);
+    }
+
+    #[test]
+    fn nginx_listen_endpoints_should_be_marked_as_tls_from_ssl_directives() {
+        let vhosts = get_vhosts_from_dir(Path::new("tests/nginx-tls"), false, false, &mut Vec::new());
+
+        vhosts.iter().for_each(|vhost| println!("{}", vhost.to_string()));
+
+        let get_ssl_flags = |domain: &str| -> Vec<bool> {
+            let vhost = vhosts.iter().find(|vhost| vhost.domain == domain).unwrap();
+            vhost.listens.iter().map(|listen| listen.ssl).collect()
+        };
+
+        assert_eq!(get_ssl_flags("admin.whatever.ru"), vec![true]);
+        assert_eq!(get_ssl_flags("legacy.whatever.ru"), vec![true]);
+        assert_eq!(get_ssl_flags("cert.whatever.ru"), vec![false, true]);
+        assert_eq!(get_ssl_flags("mixed.whatever.ru"), vec![false, true]);
+
+        let cert_vhost = vhosts.iter().find(|vhost| vhost.domain == "cert.whatever.ru").unwrap();
+        assert_eq!(cert_vhost.tls.certificate_files, vec!["/etc/nginx/ssl/cert.whatever.ru.crt"]);
+    }
+
+    #[test]
+    fn nginx_vhost_should_keep_wildcard_and_regex_names_and_skip_catch_all() {
+        let vhosts = get_vhosts_from_dir(Path::new("tests/nginx-wildcard"), false, false, &mut Vec::new());
+
+        vhosts.iter().for_each(|vhost| println!("{}", vhost.to_string()));
+
+        assert_eq!(vhosts.len(), 3);
+        assert!(vhosts.iter().all(|vhost| vhost.domain != "_"));
+
+        assert!(vhosts.iter().any(|vhost| vhost.domain == "*.apps.whatever.ru"));
+        assert!(vhosts.iter().any(|vhost| vhost.domain == "lead.whatever.ru"));
+
+        let regex_vhost = vhosts.iter().find(|vhost| vhost.domain == "regex.whatever.ru").unwrap();
+        assert_eq!(regex_vhost.aliases, vec!["~^(?<sub>.+)\\.regex\\.whatever\\.ru$"]);
+    }
+
+    #[test]
+    fn nginx_vhosts_redirecting_all_requests_should_be_skipped() {
+        let vhosts = get_vhosts_from_dir(Path::new("tests/nginx-redirect"), false, false, &mut Vec::new());
+
+        vhosts.iter().for_each(|vhost| println!("{}", vhost.to_string()));
+
+        let domains: Vec<&str> = vhosts.iter().map(|vhost| vhost.domain.as_str()).collect();
+
+        assert_eq!(domains, vec!["temp.whatever.ru", "partial.whatever.ru", "conditional.whatever.ru"]);
+    }
+
+    #[test]
+    fn nginx_vhosts_with_temporary_redirects_should_be_skipped_if_option_is_true() {
+        let vhosts = get_vhosts_from_dir(Path::new("tests/nginx-redirect"), true, false, &mut Vec::new());
+
+        assert!(vhosts.iter().all(|vhost| vhost.domain != "temp.whatever.ru"));
+        assert_eq!(vhosts.len(), 2);
+    }
+
+    #[test]
+    fn nginx_redirect_vhosts_should_be_kept_with_redirect_if_option_is_true() {
+        let vhosts = get_vhosts_from_dir(Path::new("tests/nginx-redirect"), false, true, &mut Vec::new());
+
+        assert_eq!(vhosts.len(), 7);
+
+        let vhost = vhosts.iter().find(|vhost| vhost.domain == "host.whatever.ru").unwrap();
+        let redirect = vhost.redirect.as_ref().unwrap();
+        assert_eq!(redirect.code, 301);
+        assert_eq!(redirect.target, "https://$host$request_uri");
+
+        let partial_vhost = vhosts.iter().find(|vhost| vhost.domain == "partial.whatever.ru").unwrap();
+        assert!(partial_vhost.redirect.is_none());
+    }
+
+    #[test]
+    fn nginx_config_problems_should_be_collected_with_file_and_line() {
+        let mut errors = Vec::new();
+
+        let vhosts = get_vhosts_from_dir(Path::new("tests/nginx-broken"), false, false, &mut errors);
+
+        assert!(vhosts.iter().any(|vhost| vhost.domain == "site.company.ru"));
+        assert!(vhosts.iter().all(|vhost| vhost.domain != "broken.company.ru"));
+
+        assert_eq!(errors.len(), 2);
+
+        assert!(errors.iter().any(|e| match e {
+            DiscoveryError::ParseError { file, .. } => file == &PathBuf::from("tests/nginx-broken/broken.conf"),
+            _ => false
+        }));
+
+        assert!(errors.contains(&DiscoveryError::InvalidPort {
+            file: PathBuf::from("tests/nginx-broken/site.conf"), line: 7, value: "99999".to_string()
+        }));
+    }
+
+    #[test]
+    fn missing_nginx_main_config_should_be_returned_as_error() {
+        let result = get_nginx_vhosts_from_main_config(Path::new("tests/missing.conf"), false, false, false, &mut Vec::new());
+
+        match result {
+            Err(DiscoveryError::UnreadableFile { path, .. }) => assert_eq!(path, PathBuf::from("tests/missing.conf")),
+            _ => panic!("unreadable file error expected")
+        }
+    }
+
+    #[test]
+    fn annotations_should_be_read_from_comments_above_and_on_server_line() {
+        let mut errors: Vec<DiscoveryError> = Vec::new();
+        let vhosts = get_vhosts_from_dir(Path::new("tests/nginx-annotations"), false, false, &mut errors);
+
+        assert!(errors.is_empty());
+        assert_eq!(vhosts.len(), 3);
+
+        let shop = vhosts.iter().find(|vhost| vhost.domain == "shop.company.ru").unwrap();
+        assert_eq!(shop.annotations.get("env"), Some(&"production".to_string()));
+        assert_eq!(shop.annotations.get("owner"), Some(&"web-team".to_string()));
+
+        let stage = vhosts.iter().find(|vhost| vhost.domain == "stage.company.ru").unwrap();
+        assert_eq!(stage.annotations.get("env"), Some(&"staging".to_string()));
+        assert_eq!(stage.annotations.get("team-channel"), Some(&"#shop-alerts".to_string()));
+
+        let plain = vhosts.iter().find(|vhost| vhost.domain == "plain.company.ru").unwrap();
+        assert!(plain.annotations.is_empty());
+    }
 }
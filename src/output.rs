@@ -0,0 +1,184 @@
+pub mod output {
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    use serde::{Serialize, Serializer};
+    use serde_json::json;
+
+    use crate::domain::domain::Site;
+
+    const CSV_SEPARATOR: char = ',';
+    const CSV_HEADERS: [&str; 12] = ["name", "url", "domain", "scheme", "port", "wildcard", "type",
+                                     "redirect_target", "redirect_code", "server", "config_file", "config_line"];
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum OutputFormat {
+        /// Zabbix low level discovery JSON array
+        Lld,
+        /// Zabbix low level discovery JSON with `data` property, Zabbix < 4.2
+        LldData,
+        /// Prometheus `file_sd_configs` JSON, target per site url
+        Prometheus,
+        Csv,
+        Yaml,
+        /// Url per line
+        Urls
+    }
+
+    impl OutputFormat {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                OutputFormat::Lld => "lld",
+                OutputFormat::LldData => "lld-data",
+                OutputFormat::Prometheus => "prometheus",
+                OutputFormat::Csv => "csv",
+                OutputFormat::Yaml => "yaml",
+                OutputFormat::Urls => "urls"
+            }
+        }
+
+        pub fn values() -> Vec<OutputFormat> {
+            vec![OutputFormat::Lld, OutputFormat::LldData, OutputFormat::Prometheus,
+                 OutputFormat::Csv, OutputFormat::Yaml, OutputFormat::Urls]
+        }
+    }
+
+    impl FromStr for OutputFormat {
+        type Err = String;
+
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            OutputFormat::values().into_iter()
+                .find(|format| format.as_str().eq_ignore_ascii_case(value.trim()))
+                .ok_or_else(|| format!("unsupported output format '{}'", value))
+        }
+    }
+
+    /// Serializes flag as `1` or `0`, Zabbix triggers compare numbers.
+    pub fn serialize_flag<S: Serializer>(value: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(if *value { 1 } else { 0 })
+    }
+
+    /// Site with plain field names, used for YAML and CSV. Missing values are skipped in YAML.
+    #[derive(Serialize)]
+    struct SiteRecord<'a> {
+        name: &'a str,
+        url: &'a str,
+        domain: &'a str,
+        scheme: &'a str,
+        port: i32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        wildcard: Option<&'a str>,
+        #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+        site_type: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        redirect_target: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        redirect_code: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        server: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        config_file: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        config_line: Option<&'a str>
+    }
+
+    impl<'a> SiteRecord<'a> {
+        fn new(site: &'a Site) -> SiteRecord<'a> {
+            SiteRecord {
+                name: &site.name, url: &site.url, domain: &site.domain, scheme: site.scheme.as_str(), port: site.port,
+                wildcard: site.wildcard.as_deref(), site_type: site.site_type.as_deref(),
+                redirect_target: site.redirect_target.as_deref(), redirect_code: site.redirect_code.as_deref(),
+                server: site.server.as_deref(), config_file: site.config_file.as_deref(),
+                config_line: site.config_line.as_deref()
+            }
+        }
+
+        /// Values in `CSV_HEADERS` order, missing values are empty.
+        fn get_csv_values(&self) -> Vec<String> {
+            let optional_values = [self.wildcard, self.site_type, self.redirect_target, self.redirect_code,
+                                   self.server, self.config_file, self.config_line];
+
+            let mut values: Vec<String> = vec![self.name.to_string(), self.url.to_string(), self.domain.to_string(),
+                                               self.scheme.to_string(), self.port.to_string()];
+            values.extend(optional_values.iter().map(|value| value.unwrap_or_default().to_string()));
+            values
+        }
+    }
+
+    pub fn get_output(sites: Vec<Site>, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Lld => get_low_level_discovery_json(sites),
+            OutputFormat::LldData => get_low_level_discovery_json_with_data_property(sites),
+            OutputFormat::Prometheus => get_prometheus_file_sd_json(&sites),
+            OutputFormat::Csv => get_csv(&sites),
+            OutputFormat::Yaml => get_yaml(&sites),
+            OutputFormat::Urls => get_url_list(&sites)
+        }
+    }
+
+    pub fn get_low_level_discovery_json(sites: Vec<Site>) -> String {
+        let json_structure = json!(sites);
+        let json = serde_json::to_string(&json_structure).unwrap();
+        return json;
+    }
+
+    pub fn get_low_level_discovery_json_with_data_property(sites: Vec<Site>) -> String {
+        let json_structure = json!({"data": sites});
+        let json = serde_json::to_string(&json_structure).unwrap();
+        return json;
+    }
+
+    /// Target group per site for `file_sd_configs`, url is the target for blackbox exporter.
+    /// `server` and `source_file` labels are set if site has provenance.
+    pub fn get_prometheus_file_sd_json(sites: &[Site]) -> String {
+        let target_groups: Vec<serde_json::Value> = sites.iter().map(|site| {
+            let mut labels: BTreeMap<&str, String> = BTreeMap::new();
+            labels.insert("domain", site.domain.to_string());
+            labels.insert("scheme", site.scheme.as_str().to_string());
+            labels.insert("port", site.port.to_string());
+
+            if let Some(server) = &site.server {
+                labels.insert("server", server.to_string());
+            }
+
+            if let Some(config_file) = &site.config_file {
+                labels.insert("source_file", config_file.to_string());
+            }
+
+            json!({"targets": [site.url], "labels": labels})
+        }).collect();
+
+        serde_json::to_string(&target_groups).unwrap()
+    }
+
+    /// CSV with header, values with separator, quotes or line breaks are quoted.
+    pub fn get_csv(sites: &[Site]) -> String {
+        let mut lines: Vec<String> = vec![CSV_HEADERS.join(&CSV_SEPARATOR.to_string())];
+
+        for site in sites {
+            let values: Vec<String> = SiteRecord::new(site).get_csv_values().iter()
+                .map(|value| get_csv_value(value)).collect();
+            lines.push(values.join(&CSV_SEPARATOR.to_string()));
+        }
+
+        lines.join("\n")
+    }
+
+    pub fn get_yaml(sites: &[Site]) -> String {
+        let records: Vec<SiteRecord> = sites.iter().map(SiteRecord::new).collect();
+        serde_yaml::to_string(&records).unwrap()
+    }
+
+    pub fn get_url_list(sites: &[Site]) -> String {
+        let urls: Vec<&str> = sites.iter().map(|site| site.url.as_str()).collect();
+        urls.join("\n")
+    }
+
+    fn get_csv_value(value: &str) -> String {
+        if value.contains(&[CSV_SEPARATOR, '"', '\n', '\r'][..]) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+}
@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod output_tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use crate::{DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT};
+    use crate::domain::domain::{Provenance, Site, VirtualHost};
+    use crate::output::output::{get_csv, get_output, get_prometheus_file_sd_json, get_url_list, get_yaml,
+                                OutputFormat};
+    use crate::site::site::{get_sites_from_vhosts, SiteFormat};
+
+    fn get_sites() -> Vec<Site> {
+        let mut vhost = VirtualHost::new("app.company.ru", DEFAULT_HTTPS_PORT);
+        vhost.provenance = Some(Provenance {
+            server: "nginx".to_string(), file: PathBuf::from("/etc/nginx/conf.d/app,v2.conf"), line: 3,
+            listen: vec!["443 ssl".to_string()], server_names: vec!["app.company.ru".to_string()]
+        });
+
+        let vhosts = vec![vhost, VirtualHost::new("old.company.ru", DEFAULT_HTTP_PORT)];
+
+        let site_format = SiteFormat { include_provenance: true, ..SiteFormat::default() };
+
+        get_sites_from_vhosts(vhosts, false, false, &HashMap::new(), &site_format)
+    }
+
+    #[test]
+    fn output_format_should_be_parsed_case_insensitive() {
+        assert_eq!("Prometheus".parse::<OutputFormat>(), Ok(OutputFormat::Prometheus));
+        assert_eq!("lld-data".parse::<OutputFormat>(), Ok(OutputFormat::LldData));
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn prometheus_output_should_contain_target_group_per_site() {
+        let expected_json = concat!(
+            r#"[{"labels":{"domain":"app.company.ru","port":"443","scheme":"https","server":"nginx","#,
+            r#""source_file":"/etc/nginx/conf.d/app,v2.conf"},"targets":["https://app.company.ru"]},"#,
+            r#"{"labels":{"domain":"old.company.ru","port":"80","scheme":"http"},"targets":["http://old.company.ru"]}]"#
+        );
+
+        assert_eq!(get_prometheus_file_sd_json(&get_sites()), expected_json);
+    }
+
+    #[test]
+    fn csv_output_should_contain_header_and_quoted_values() {
+        let expected_csv = "\
+name,url,domain,scheme,port,wildcard,type,redirect_target,redirect_code,server,config_file,config_line
+app.company.ru,https://app.company.ru,app.company.ru,https,443,,,,,nginx,\"/etc/nginx/conf.d/app,v2.conf\",3
+old.company.ru_http,http://old.company.ru,old.company.ru,http,80,,,,,,,";
+
+        assert_eq!(get_csv(&get_sites()), expected_csv);
+    }
+
+    #[test]
+    fn yaml_output_should_use_plain_field_names() {
+        let yaml = get_yaml(&get_sites());
+
+        assert!(yaml.contains("- name: app.company.ru\n"));
+        assert!(yaml.contains("  url: \"http://old.company.ru\"\n"));
+        assert!(yaml.contains("  port: 443\n"));
+        assert!(yaml.contains("  config_line: \"3\"\n"));
+        assert!(!yaml.contains("{#"));
+        assert!(!yaml.contains("wildcard"));
+    }
+
+    #[test]
+    fn url_list_output_should_contain_url_per_line() {
+        assert_eq!(get_url_list(&get_sites()), "https://app.company.ru\nhttp://old.company.ru");
+        assert_eq!(get_output(get_sites(), OutputFormat::Urls), get_url_list(&get_sites()));
+    }
+}
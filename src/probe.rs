@@ -0,0 +1,413 @@
+pub mod probe {
+    use std::collections::BTreeMap;
+    use std::error::Error;
+    use std::io;
+    use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+    use rustls::client::WebPkiServerVerifier;
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::crypto::CryptoProvider;
+    use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+    use serde::{Serialize, Serializer};
+    use ureq::{Agent, AgentBuilder};
+    use url::Url;
+
+    use crate::DEFAULT_HTTP_PORT;
+    use crate::domain::domain::Site;
+    use crate::error::error::ProbeError;
+    use crate::output::output::serialize_flag;
+
+    pub const DEFAULT_PROBE_TIMEOUT_SECONDS: u64 = 10;
+    pub const DEFAULT_PROBE_METHOD: &str = "GET";
+    pub const DEFAULT_EXPECTED_STATUS: u16 = 200;
+
+    /// Sites probed at the same time.
+    pub const PROBE_CONCURRENCY: usize = 8;
+
+    const MAX_REDIRECTS: u32 = 10;
+
+    const LOCATION_HEADER: &str = "Location";
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct ProbeSettings {
+        /// Timeout of site request including redirects
+        pub timeout: Duration,
+        pub method: String,
+        /// Statuses which mean site is available. If not set, redirect sites expect their redirect code
+        /// and other sites expect `200`
+        pub expected_statuses: Option<Vec<u16>>,
+        /// If not set, redirects are followed for all sites except redirect ones
+        pub follow_redirects: Option<bool>,
+        /// Address to connect to instead of resolved one for every domain, i.e. `127.0.0.1`
+        pub resolve_to: Option<String>
+    }
+
+    impl Default for ProbeSettings {
+        fn default() -> Self {
+            ProbeSettings {
+                timeout: Duration::from_secs(DEFAULT_PROBE_TIMEOUT_SECONDS),
+                method: DEFAULT_PROBE_METHOD.to_string(),
+                expected_statuses: None,
+                follow_redirects: None,
+                resolve_to: None
+            }
+        }
+    }
+
+    impl ProbeSettings {
+        pub fn new() -> ProbeSettings {
+            ProbeSettings::default()
+        }
+
+        /// Expected statuses and redirect following for site, the same rules as for web scenarios.
+        pub fn get_site_check(&self, site: &Site) -> (Vec<u16>, bool) {
+            let redirect_code: Option<u16> = site.redirect_code.as_ref().and_then(|code| code.parse().ok());
+
+            let follow_redirects = self.follow_redirects.unwrap_or(redirect_code.is_none());
+
+            let expected_statuses = match (&self.expected_statuses, redirect_code) {
+                (Some(statuses), _) => statuses.clone(),
+                (None, Some(code)) if !follow_redirects => vec![code],
+                (None, _) => vec![DEFAULT_EXPECTED_STATUS]
+            };
+
+            (expected_statuses, follow_redirects)
+        }
+    }
+
+    /// Timeout of fractional seconds, i.e. `0.5`. Returns `None` for zero, negative and too large values.
+    pub fn get_probe_timeout(seconds: f64) -> Option<Duration> {
+        Duration::try_from_secs_f64(seconds).ok().filter(|timeout| !timeout.is_zero())
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum TlsResult {
+        /// Handshake is done and certificate is valid
+        Ok,
+        /// Handshake or certificate verification failed
+        Failed,
+        /// Plain http or connection isn't established
+        None
+    }
+
+    impl TlsResult {
+        pub fn as_str(&self) -> &str {
+            match self {
+                TlsResult::Ok => "ok",
+                TlsResult::Failed => "failed",
+                TlsResult::None => "none"
+            }
+        }
+    }
+
+    impl Serialize for TlsResult {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(self.as_str())
+        }
+    }
+
+    /// Probe result of site. Values are never null, so every value can be taken with JSONPath
+    /// by dependent item: status is `0` and error isn't empty if response isn't received.
+    #[derive(Clone, Debug, PartialEq, Serialize)]
+    pub struct ProbeResult {
+        pub name: String,
+        #[serde(skip)]
+        pub url: String,
+        pub status: u16,
+        /// `1` if status is expected and TLS isn't failed, otherwise `0`
+        #[serde(serialize_with = "serialize_flag")]
+        pub ok: bool,
+        pub latency_ms: u64,
+        pub tls: TlsResult,
+        pub final_url: String,
+        pub redirects: u32,
+        pub error: String
+    }
+
+    /// What happened with site request and its redirects.
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct ProbeResponse {
+        /// Status of the last response, `None` if response isn't received
+        pub status: Option<u16>,
+        /// Connection, TLS handshake or timeout error
+        pub error: Option<String>,
+        /// TLS handshake failed before certificate was received
+        pub handshake_failed: bool,
+        /// Certificate verification result of the first failed or the last TLS connection,
+        /// `None` if there were no TLS connections
+        pub verification: Option<Result<(), String>>,
+        pub latency: Duration,
+        pub final_url: String,
+        pub redirects: u32
+    }
+
+    /// Probes sites, `PROBE_CONCURRENCY` at a time: every worker takes the next site when its request is done.
+    /// Results are in order of sites. Returns error only if `resolve_to` address can't be resolved
+    /// or TLS can't be set up, unavailable sites are results with `ok: 0`.
+    pub fn probe_sites(sites: &[Site], settings: &ProbeSettings) -> Result<Vec<ProbeResult>, ProbeError> {
+        let addresses = match &settings.resolve_to {
+            Some(address) => Some(get_addresses(address)?),
+            None => None
+        };
+
+        let verifier = get_certificate_verifier()?;
+
+        let next_site = AtomicUsize::new(0);
+        let results: Mutex<Vec<Option<ProbeResult>>> = Mutex::new(vec![None; sites.len()]);
+
+        thread::scope(|scope| {
+            for _ in 0..PROBE_CONCURRENCY.min(sites.len()) {
+                scope.spawn(|| loop {
+                    let index = next_site.fetch_add(1, Ordering::SeqCst);
+
+                    let site = match sites.get(index) {
+                        Some(site) => site,
+                        None => break
+                    };
+
+                    let result = probe_site(site, settings, &addresses, &verifier);
+
+                    if let Ok(mut results) = results.lock() {
+                        results[index] = Some(result);
+                    }
+                });
+            }
+        });
+
+        let results = results.into_inner().unwrap_or_else(|e| e.into_inner());
+
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    fn probe_site(site: &Site, settings: &ProbeSettings, addresses: &Option<Vec<IpAddr>>,
+                  verifier: &Arc<WebPkiServerVerifier>) -> ProbeResult {
+        let (expected_statuses, follow_redirects) = settings.get_site_check(site);
+
+        debug!("probe site '{}', expected statuses {:?}, follow redirects: {}",
+               site.url, expected_statuses, follow_redirects);
+
+        let verifier = Arc::new(RecordingVerifier { verifier: verifier.clone(), result: Mutex::new(None) });
+        let agent = get_agent(settings, addresses, verifier.clone());
+
+        let mut response = send_request(&agent, &site.url, settings, follow_redirects);
+        response.verification = verifier.result.lock().ok().and_then(|result| result.clone());
+
+        let result = get_probe_result(site, &expected_statuses, &response);
+
+        debug!("site '{}' probe result: status {}, ok: {}, tls: {}, error: '{}'",
+               site.url, result.status, result.ok, result.tls.as_str(), result.error);
+
+        result
+    }
+
+    /// Sends request and follows redirects if enabled, `timeout` is shared by all requests.
+    fn send_request(agent: &Agent, url: &str, settings: &ProbeSettings, follow_redirects: bool) -> ProbeResponse {
+        let started = Instant::now();
+
+        let mut response = ProbeResponse { final_url: url.to_string(), ..ProbeResponse::default() };
+
+        loop {
+            let timeout = match settings.timeout.checked_sub(started.elapsed()) {
+                Some(timeout) if !timeout.is_zero() => timeout,
+                _ => {
+                    response.error = Some(format!("timeout {} ms is exceeded", settings.timeout.as_millis()));
+                    break
+                }
+            };
+
+            trace!("{} {}", settings.method.to_uppercase(), response.final_url);
+
+            let request_response = match agent.request(&settings.method.to_uppercase(), &response.final_url)
+                                              .timeout(timeout).call() {
+                Ok(request_response) | Err(ureq::Error::Status(_, request_response)) => request_response,
+                Err(ureq::Error::Transport(e)) => {
+                    response.handshake_failed = is_tls_error(&e);
+                    response.error = Some(e.to_string());
+                    break
+                }
+            };
+
+            let status = request_response.status();
+            response.status = Some(status);
+
+            let location = request_response.header(LOCATION_HEADER)
+                .and_then(|location| Url::parse(&response.final_url).ok()?.join(location).ok());
+
+            match location {
+                Some(location) if follow_redirects && (300..400).contains(&status) &&
+                                  response.redirects < MAX_REDIRECTS => {
+                    debug!("follow redirect {} to '{}'", status, location);
+                    response.redirects += 1;
+                    response.final_url = location.to_string();
+                }
+                _ => break
+            }
+        }
+
+        response.latency = started.elapsed();
+        response
+    }
+
+    pub fn get_probe_result(site: &Site, expected_statuses: &[u16], response: &ProbeResponse) -> ProbeResult {
+        let status = response.status.unwrap_or(0);
+
+        let mut error = response.error.clone().unwrap_or_default();
+
+        let tls = match &response.verification {
+            Some(Ok(())) if !response.handshake_failed => TlsResult::Ok,
+            Some(Err(message)) => {
+                if error.is_empty() {
+                    error = format!("certificate verification failed: {}", message);
+                }
+                TlsResult::Failed
+            }
+            _ if response.handshake_failed => TlsResult::Failed,
+            _ => TlsResult::None
+        };
+
+        if error.is_empty() && status > 0 && !expected_statuses.contains(&status) {
+            error = format!("unexpected status {}, expected {}", status, expected_statuses.iter()
+                .map(|status| status.to_string()).collect::<Vec<String>>().join(","));
+        }
+
+        ProbeResult {
+            name: site.name.to_string(),
+            url: site.url.to_string(),
+            status,
+            ok: expected_statuses.contains(&status) && tls != TlsResult::Failed,
+            latency_ms: response.latency.as_millis() as u64,
+            tls,
+            final_url: response.final_url.to_string(),
+            redirects: response.redirects,
+            error
+        }
+    }
+
+    /// Object with results by site url, i.e. JSONPath `$['{#URL}'].ok` for item prototype.
+    pub fn get_probe_json(results: &[ProbeResult]) -> String {
+        let results: BTreeMap<&str, &ProbeResult> = results.iter()
+            .map(|result| (result.url.as_str(), result)).collect();
+
+        serde_json::to_string(&results).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// IP address as is, host name is resolved once for all sites.
+    fn get_addresses(address: &str) -> Result<Vec<IpAddr>, ProbeError> {
+        if let Ok(ip_address) = address.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+            return Ok(vec![ip_address])
+        }
+
+        let addresses: Vec<IpAddr> = (address, DEFAULT_HTTP_PORT as u16).to_socket_addrs()
+            .map_err(|e| ProbeError::InvalidAddress { address: address.to_string(), message: e.to_string() })?
+            .map(|socket_address| socket_address.ip()).collect();
+
+        match addresses.is_empty() {
+            true => Err(ProbeError::InvalidAddress {
+                address: address.to_string(), message: "no addresses found".to_string()
+            }),
+            false => Ok(addresses)
+        }
+    }
+
+    /// Agent without redirects and connection reuse, redirects are followed by `send_request`.
+    fn get_agent(settings: &ProbeSettings, addresses: &Option<Vec<IpAddr>>, verifier: Arc<RecordingVerifier>) -> Agent {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+        let tls_config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map(|builder| builder.dangerous().with_custom_certificate_verifier(verifier).with_no_client_auth());
+
+        let mut builder = AgentBuilder::new()
+            .timeout_connect(settings.timeout)
+            .redirects(0)
+            .max_idle_connections(0);
+
+        match tls_config {
+            Ok(tls_config) => builder = builder.tls_config(Arc::new(tls_config)),
+            Err(e) => warn!("unable to set up TLS certificate verification: {}", e)
+        }
+
+        if let Some(addresses) = addresses.clone() {
+            builder = builder.resolver(move |netloc: &str| -> io::Result<Vec<SocketAddr>> {
+                let port = netloc.rsplit_once(':').and_then(|(_, port)| port.parse().ok())
+                    .unwrap_or(DEFAULT_HTTP_PORT as u16);
+
+                Ok(addresses.iter().map(|address| SocketAddr::new(*address, port)).collect())
+            });
+        }
+
+        builder.build()
+    }
+
+    /// Mozilla root certificates, the same as ureq uses.
+    fn get_certificate_verifier() -> Result<Arc<WebPkiServerVerifier>, ProbeError> {
+        let roots = RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+        let provider: Arc<CryptoProvider> = Arc::new(rustls::crypto::ring::default_provider());
+
+        WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider).build()
+            .map_err(|e| ProbeError::Tls { message: e.to_string() })
+    }
+
+    /// rustls error is wrapped into io error by TLS stream.
+    fn is_tls_error(error: &ureq::Transport) -> bool {
+        let mut source = error.source();
+
+        while let Some(current) = source {
+            let io_error_source = current.downcast_ref::<io::Error>().and_then(|io_error| io_error.get_ref());
+
+            if current.is::<rustls::Error>() || io_error_source.map(|e| e.is::<rustls::Error>()).unwrap_or(false) {
+                return true
+            }
+
+            source = current.source();
+        }
+
+        false
+    }
+
+    /// Verifies certificate, but doesn't stop handshake: result is kept and reported as TLS result,
+    /// so status is known for sites with invalid certificates too.
+    #[derive(Debug)]
+    struct RecordingVerifier {
+        verifier: Arc<WebPkiServerVerifier>,
+        result: Mutex<Option<Result<(), String>>>
+    }
+
+    impl ServerCertVerifier for RecordingVerifier {
+        fn verify_server_cert(&self, end_entity: &CertificateDer<'_>, intermediates: &[CertificateDer<'_>],
+                              server_name: &ServerName<'_>, ocsp_response: &[u8],
+                              now: UnixTime) -> Result<ServerCertVerified, rustls::Error> {
+            let verification = self.verifier
+                .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+                .map(|_| ()).map_err(|e| e.to_string());
+
+            if let Ok(mut result) = self.result.lock() {
+                // the first failure is kept for redirects
+                if !matches!(*result, Some(Err(_))) {
+                    *result = Some(verification);
+                }
+            }
+
+            Ok(ServerCertVerified::assertion())
+        }
+
+        fn verify_tls12_signature(&self, message: &[u8], certificate: &CertificateDer<'_>,
+                                  signature: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+            self.verifier.verify_tls12_signature(message, certificate, signature)
+        }
+
+        fn verify_tls13_signature(&self, message: &[u8], certificate: &CertificateDer<'_>,
+                                  signature: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, rustls::Error> {
+            self.verifier.verify_tls13_signature(message, certificate, signature)
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.verifier.supported_verify_schemes()
+        }
+    }
+}
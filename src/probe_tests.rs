@@ -0,0 +1,268 @@
+#[cfg(test)]
+mod probe_tests {
+    use std::collections::HashMap;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::time::Duration;
+
+    use serde_json::Value;
+
+    use crate::DEFAULT_HTTP_PORT;
+    use crate::domain::domain::{Redirect, RedirectScope, Site, VirtualHost};
+    use crate::error::error::ProbeError;
+    use crate::probe::probe::{get_probe_json, get_probe_result, get_probe_timeout, probe_sites, ProbeResponse,
+                              ProbeSettings, TlsResult};
+    use crate::site::site::{get_sites_from_vhosts, SiteFormat};
+
+    const LOCALHOST: &str = "127.0.0.1";
+
+    /// Local web server for any domain: `old.company.ru` redirects to `app.company.ru`,
+    /// `/missing` path isn't found, other requests are ok. Returns port.
+    fn start_test_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                handle_request(stream, port);
+            }
+        });
+
+        port
+    }
+
+    fn handle_request(mut stream: TcpStream, port: u16) {
+        let mut request: Vec<u8> = Vec::new();
+        let mut buffer = [0u8; 1024];
+
+        while !String::from_utf8_lossy(&request).contains("\r\n\r\n") {
+            match stream.read(&mut buffer) {
+                Ok(0) | Err(_) => return,
+                Ok(size) => request.extend_from_slice(&buffer[..size])
+            }
+        }
+
+        let request = String::from_utf8_lossy(&request).to_string();
+        let path = request.split_whitespace().nth(1).unwrap_or("/");
+        let host = request.lines()
+            .find_map(|line| line.strip_prefix("Host:").map(|value| value.trim().to_string()))
+            .unwrap_or_default();
+
+        let response = if host.starts_with("old.company.ru") {
+            format!("HTTP/1.1 301 Moved Permanently\r\nLocation: http://app.company.ru:{}/\r\n\
+                     Content-Length: 0\r\nConnection: close\r\n\r\n", port)
+
+        } else if path == "/missing" {
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+
+        } else {
+            "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok".to_string()
+        };
+
+        let _ = stream.write_all(response.as_bytes());
+    }
+
+    fn get_site(domain: &str, port: u16, path: &str) -> Site {
+        let mut vhost = VirtualHost::new(domain, DEFAULT_HTTP_PORT);
+
+        if domain.starts_with("old.") {
+            vhost.redirect = Some(Redirect {
+                code: 301, target: "http://app.company.ru".to_string(), scope: RedirectScope::VirtualHost
+            });
+        }
+
+        let mut sites = get_sites_from_vhosts(vec![vhost], false, false, &HashMap::new(), &SiteFormat::default());
+
+        let mut site = sites.remove(0);
+        site.url = format!("http://{}:{}{}", domain, port, path);
+        site
+    }
+
+    fn get_settings() -> ProbeSettings {
+        let mut settings = ProbeSettings::new();
+        settings.resolve_to = Some(LOCALHOST.to_string());
+        settings
+    }
+
+    #[test]
+    fn sites_should_be_probed_with_resolver_override() {
+        let port = start_test_server();
+
+        let sites = vec![
+            get_site("app.company.ru", port, "/"),
+            get_site("app.company.ru", port, "/missing"),
+            get_site("old.company.ru", port, "/")
+        ];
+
+        let results = probe_sites(&sites, &get_settings()).unwrap();
+
+        assert_eq!(results.len(), 3);
+
+        assert_eq!(results[0].status, 200);
+        assert!(results[0].ok);
+        assert_eq!(results[0].tls, TlsResult::None);
+        assert_eq!(results[0].final_url, format!("http://app.company.ru:{}/", port));
+        assert!(results[0].error.is_empty());
+
+        assert_eq!(results[1].status, 404);
+        assert!(!results[1].ok);
+        assert_eq!(results[1].error, "unexpected status 404, expected 200");
+
+        // redirect site expects its redirect code without following it
+        assert_eq!(results[2].name, "old.company.ru_http");
+        assert_eq!(results[2].status, 301);
+        assert!(results[2].ok);
+        assert_eq!(results[2].redirects, 0);
+    }
+
+    #[test]
+    fn redirects_should_be_followed_if_enabled() {
+        let port = start_test_server();
+
+        let mut settings = get_settings();
+        settings.follow_redirects = Some(true);
+
+        let results = probe_sites(&[get_site("old.company.ru", port, "/")], &settings).unwrap();
+
+        assert_eq!(results[0].status, 200);
+        assert!(results[0].ok);
+        assert_eq!(results[0].redirects, 1);
+        assert_eq!(results[0].final_url, format!("http://app.company.ru:{}/", port));
+    }
+
+    #[test]
+    fn expected_statuses_should_override_site_defaults() {
+        let port = start_test_server();
+
+        let mut settings = get_settings();
+        settings.method = "head".to_string();
+        settings.expected_statuses = Some(vec![200, 404]);
+
+        let results = probe_sites(&[get_site("app.company.ru", port, "/missing")], &settings).unwrap();
+
+        assert_eq!(results[0].status, 404);
+        assert!(results[0].ok);
+    }
+
+    #[test]
+    fn unavailable_site_should_have_zero_status_and_error() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+        let results = probe_sites(&[get_site("app.company.ru", port, "/")], &get_settings()).unwrap();
+
+        assert_eq!(results[0].status, 0);
+        assert!(!results[0].ok);
+        assert!(!results[0].error.is_empty());
+    }
+
+    #[test]
+    fn sub_second_timeout_should_stop_request() {
+        // accepts connections, but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let streams: Vec<TcpStream> = listener.incoming().flatten().collect();
+            drop(streams);
+        });
+
+        let mut settings = get_settings();
+        settings.timeout = get_probe_timeout(0.3).unwrap();
+
+        let results = probe_sites(&[get_site("app.company.ru", port, "/")], &settings).unwrap();
+
+        assert_eq!(results[0].status, 0);
+        assert!(!results[0].ok);
+        assert!(!results[0].error.is_empty());
+        assert!(results[0].latency_ms < 2000);
+    }
+
+    #[test]
+    fn probe_timeout_should_allow_fractional_seconds() {
+        assert_eq!(get_probe_timeout(0.5), Some(Duration::from_millis(500)));
+        assert_eq!(get_probe_timeout(10.0), Some(Duration::from_secs(10)));
+        assert_eq!(get_probe_timeout(0.0), None);
+        assert_eq!(get_probe_timeout(-1.0), None);
+    }
+
+    #[test]
+    fn failed_tls_handshake_should_be_reported() {
+        // plain http server answers without waiting for request, like web servers do for TLS handshake
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || for mut stream in listener.incoming().flatten() {
+            let _ = stream.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+        });
+
+        let mut site = get_site("app.company.ru", port, "/");
+        site.url = format!("https://app.company.ru:{}/", port);
+
+        let results = probe_sites(&[site], &get_settings()).unwrap();
+
+        assert_eq!(results[0].status, 0);
+        assert_eq!(results[0].tls, TlsResult::Failed);
+        assert!(!results[0].ok);
+        assert!(!results[0].error.is_empty());
+    }
+
+    #[test]
+    fn unresolved_address_should_be_returned_as_error() {
+        let mut settings = get_settings();
+        settings.resolve_to = Some("missing.invalid".to_string());
+
+        assert!(matches!(probe_sites(&[get_site("app.company.ru", 80, "/")], &settings),
+                         Err(ProbeError::InvalidAddress { .. })));
+    }
+
+    #[test]
+    fn tls_result_should_follow_handshake_and_verification() {
+        let site = get_site("app.company.ru", 443, "/");
+
+        let mut response = ProbeResponse {
+            status: Some(200), verification: Some(Ok(())), latency: Duration::from_millis(120),
+            final_url: "https://app.company.ru/".to_string(), ..ProbeResponse::default()
+        };
+
+        let result = get_probe_result(&site, &[200], &response);
+        assert_eq!(result.tls, TlsResult::Ok);
+        assert_eq!(result.latency_ms, 120);
+        assert!(result.ok);
+
+        response.verification = Some(Err("invalid peer certificate: UnknownIssuer".to_string()));
+        let result = get_probe_result(&site, &[200], &response);
+        assert_eq!(result.tls, TlsResult::Failed);
+        assert_eq!(result.status, 200);
+        assert_eq!(result.error, "certificate verification failed: invalid peer certificate: UnknownIssuer");
+        assert!(!result.ok);
+
+        let response = ProbeResponse {
+            error: Some("received corrupt message".to_string()), handshake_failed: true,
+            final_url: site.url.to_string(), ..ProbeResponse::default()
+        };
+        let result = get_probe_result(&site, &[200], &response);
+        assert_eq!(result.tls, TlsResult::Failed);
+        assert_eq!(result.status, 0);
+        assert_eq!(result.error, "received corrupt message");
+        assert_eq!(result.final_url, site.url);
+    }
+
+    #[test]
+    fn probe_json_should_contain_results_by_url() {
+        let site = get_site("app.company.ru", 443, "/");
+        let response = ProbeResponse {
+            status: Some(200), verification: Some(Ok(())), final_url: "https://app.company.ru/".to_string(),
+            ..ProbeResponse::default()
+        };
+        let result = get_probe_result(&site, &[200], &response);
+
+        let json: Value = serde_json::from_str(&get_probe_json(&[result])).unwrap();
+        let value = &json[&site.url];
+
+        assert_eq!(value["name"], "app.company.ru_http");
+        assert_eq!(value["status"], 200);
+        assert_eq!(value["ok"], 1);
+        assert_eq!(value["tls"], "ok");
+        assert_eq!(value["error"], "");
+        assert!(value.get("url").is_none());
+    }
+}
@@ -0,0 +1,192 @@
+pub mod sender {
+    use std::io::{Read, Write};
+    use std::net::{TcpStream, ToSocketAddrs};
+    use std::time::Duration;
+
+    use regex::Regex;
+    use serde_json::{json, Value};
+
+    use crate::error::error::SenderError;
+
+    pub const DEFAULT_ZABBIX_PORT: u16 = 10051;
+    pub const DEFAULT_DISCOVERY_KEY: &str = "site.discovery";
+    pub const DEFAULT_TIMEOUT_SECONDS: u64 = 10;
+
+    const PROTOCOL_HEADER: &[u8; 4] = b"ZBXD";
+    const PROTOCOL_FLAG_ZABBIX: u8 = 0x01;
+    const PROTOCOL_FLAG_COMPRESSION: u8 = 0x02;
+    const PROTOCOL_FLAG_LARGE_PACKET: u8 = 0x04;
+
+    /// Response size limit, server answers with short JSON.
+    const MAX_RESPONSE_LENGTH: u64 = 1024 * 1024;
+
+    const SUCCESS_RESPONSE: &str = "success";
+
+    /// Zabbix server or proxy trapper and item which receives discovery data.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct SenderSettings {
+        pub server: String,
+        pub port: u16,
+        /// Host name as configured in Zabbix frontend
+        pub host: String,
+        /// Key of discovery rule with `Zabbix trapper` type
+        pub key: String,
+        pub timeout: Duration
+    }
+
+    impl SenderSettings {
+        pub fn new(server: &str, host: &str) -> SenderSettings {
+            SenderSettings {
+                server: server.to_string(),
+                port: DEFAULT_ZABBIX_PORT,
+                host: host.to_string(),
+                key: DEFAULT_DISCOVERY_KEY.to_string(),
+                timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECONDS)
+            }
+        }
+
+        pub fn get_address(&self) -> String {
+            format!("{}:{}", self.server, self.port)
+        }
+    }
+
+    /// Server summary of processed values, i.e. `processed: 1; failed: 0; total: 1; seconds spent: 0.000055`.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct SenderResponse {
+        pub processed: u32,
+        pub failed: u32,
+        pub total: u32,
+        pub seconds_spent: f64,
+        pub info: String
+    }
+
+    /// Sends value (low level discovery JSON) to trapper item of host.
+    /// Returns error if server can't be reached or response can't be parsed,
+    /// values failed on server side are reported by `failed` counter.
+    pub fn send_discovery_data(settings: &SenderSettings, value: &str) -> Result<SenderResponse, SenderError> {
+        let address = settings.get_address();
+        debug!("send discovery data to '{}', host '{}', key '{}'", address, settings.host, settings.key);
+
+        let connection_error = |message: String| SenderError::Connection {
+            address: address.to_string(), message
+        };
+
+        let socket_address = address.to_socket_addrs()
+            .map_err(|e| connection_error(e.to_string()))?
+            .next()
+            .ok_or_else(|| connection_error("unable to resolve address".to_string()))?;
+
+        let mut stream = TcpStream::connect_timeout(&socket_address, settings.timeout)
+            .map_err(|e| connection_error(e.to_string()))?;
+
+        stream.set_read_timeout(Some(settings.timeout)).map_err(|e| connection_error(e.to_string()))?;
+        stream.set_write_timeout(Some(settings.timeout)).map_err(|e| connection_error(e.to_string()))?;
+
+        let request = get_sender_request(&settings.host, &settings.key, value);
+        trace!("sender request: '{}'", request);
+
+        stream.write_all(&get_packet(&request)).map_err(|e| connection_error(e.to_string()))?;
+
+        let response = read_packet(&mut stream)?;
+        trace!("sender response: '{}'", response);
+
+        get_sender_response(&response)
+    }
+
+    /// `sender data` request with single value.
+    pub fn get_sender_request(host: &str, key: &str, value: &str) -> String {
+        let request = json!({
+            "request": "sender data",
+            "data": [{"host": host, "key": key, "value": value}]
+        });
+
+        serde_json::to_string(&request).unwrap()
+    }
+
+    /// Zabbix protocol packet: header, flags, data length, reserved length and data.
+    pub fn get_packet(data: &str) -> Vec<u8> {
+        let mut packet: Vec<u8> = Vec::with_capacity(data.len() + 13);
+        packet.extend_from_slice(PROTOCOL_HEADER);
+        packet.push(PROTOCOL_FLAG_ZABBIX);
+        packet.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        packet.extend_from_slice(&0u32.to_le_bytes());
+        packet.extend_from_slice(data.as_bytes());
+        packet
+    }
+
+    /// Reads Zabbix protocol packet and returns its data. Compressed packets aren't supported.
+    pub fn read_packet(stream: &mut dyn Read) -> Result<String, SenderError> {
+        let invalid_response = |message: String| SenderError::InvalidResponse { message };
+
+        let mut header = [0u8; 5];
+        stream.read_exact(&mut header)
+            .map_err(|e| invalid_response(format!("unable to read header: {}", e)))?;
+
+        if &header[..4] != PROTOCOL_HEADER {
+            return Err(invalid_response("missing ZBXD header".to_string()))
+        }
+
+        let flags = header[4];
+
+        if flags & PROTOCOL_FLAG_COMPRESSION != 0 {
+            return Err(invalid_response("compressed response isn't supported".to_string()))
+        }
+
+        let length = if flags & PROTOCOL_FLAG_LARGE_PACKET != 0 {
+            let mut lengths = [0u8; 16];
+            stream.read_exact(&mut lengths)
+                .map_err(|e| invalid_response(format!("unable to read data length: {}", e)))?;
+            let mut length = [0u8; 8];
+            length.copy_from_slice(&lengths[..8]);
+            u64::from_le_bytes(length)
+
+        } else {
+            let mut lengths = [0u8; 8];
+            stream.read_exact(&mut lengths)
+                .map_err(|e| invalid_response(format!("unable to read data length: {}", e)))?;
+            let mut length = [0u8; 4];
+            length.copy_from_slice(&lengths[..4]);
+            u32::from_le_bytes(length) as u64
+        };
+
+        if length > MAX_RESPONSE_LENGTH {
+            return Err(invalid_response(format!("data length {} exceeds limit", length)))
+        }
+
+        let mut data = vec![0u8; length as usize];
+        stream.read_exact(&mut data)
+            .map_err(|e| invalid_response(format!("unable to read data: {}", e)))?;
+
+        String::from_utf8(data).map_err(|e| invalid_response(e.to_string()))
+    }
+
+    /// Parses server response JSON, i.e. `{"response":"success","info":"processed: 1; ..."}`.
+    pub fn get_sender_response(response: &str) -> Result<SenderResponse, SenderError> {
+        let invalid_response = |message: String| SenderError::InvalidResponse { message };
+
+        let json: Value = serde_json::from_str(response).map_err(|e| invalid_response(e.to_string()))?;
+
+        let info = json["info"].as_str().unwrap_or_default().to_string();
+
+        match json["response"].as_str() {
+            Some(SUCCESS_RESPONSE) => {}
+            Some(_) => return Err(SenderError::Rejected { info }),
+            None => return Err(invalid_response("missing 'response' property".to_string()))
+        }
+
+        let counter_pattern = Regex::new(
+            r"processed:\s*(\d+);\s*failed:\s*(\d+);\s*total:\s*(\d+);\s*seconds spent:\s*([\d.]+)"
+        ).unwrap();
+
+        match counter_pattern.captures(&info) {
+            Some(groups) => Ok(SenderResponse {
+                processed: groups[1].parse().unwrap_or_default(),
+                failed: groups[2].parse().unwrap_or_default(),
+                total: groups[3].parse().unwrap_or_default(),
+                seconds_spent: groups[4].parse().unwrap_or_default(),
+                info: info.to_string()
+            }),
+            None => Err(invalid_response(format!("unexpected info '{}'", info)))
+        }
+    }
+}
@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod sender_tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::thread::JoinHandle;
+
+    use serde_json::Value;
+
+    use crate::error::error::SenderError;
+    use crate::sender::sender::{get_packet, get_sender_request, get_sender_response, read_packet,
+                                send_discovery_data, SenderSettings};
+
+    const SUCCESS_RESPONSE: &str = r#"{"response":"success","info":"processed: 1; failed: 0; total: 1; seconds spent: 0.000055"}"#;
+
+    /// Fake trapper accepting single connection, returns received request data.
+    fn start_fake_trapper(response: Vec<u8>) -> (u16, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let request = read_packet(&mut stream).unwrap();
+            stream.write_all(&response).unwrap();
+            request
+        });
+
+        (port, handle)
+    }
+
+    fn get_settings(port: u16) -> SenderSettings {
+        let mut settings = SenderSettings::new("127.0.0.1", "web-1");
+        settings.port = port;
+        settings
+    }
+
+    #[test]
+    fn discovery_data_should_be_sent_to_trapper() {
+        let response = get_packet(SUCCESS_RESPONSE);
+        let (port, handle) = start_fake_trapper(response);
+
+        let value = r#"[{"{#NAME}":"whatever.ru","{#URL}":"https://whatever.ru"}]"#;
+
+        let sender_response = send_discovery_data(&get_settings(port), value).unwrap();
+
+        assert_eq!(sender_response.processed, 1);
+        assert_eq!(sender_response.failed, 0);
+        assert_eq!(sender_response.total, 1);
+
+        let request: Value = serde_json::from_str(&handle.join().unwrap()).unwrap();
+
+        assert_eq!(request["request"], "sender data");
+        assert_eq!(request["data"][0]["host"], "web-1");
+        assert_eq!(request["data"][0]["key"], "site.discovery");
+        assert_eq!(request["data"][0]["value"], value);
+    }
+
+    #[test]
+    fn failed_values_should_be_returned_in_response() {
+        let response = get_packet(
+            r#"{"response":"success","info":"processed: 0; failed: 1; total: 1; seconds spent: 0.000021"}"#);
+        let (port, handle) = start_fake_trapper(response);
+
+        let sender_response = send_discovery_data(&get_settings(port), "[]").unwrap();
+        handle.join().unwrap();
+
+        assert_eq!(sender_response.processed, 0);
+        assert_eq!(sender_response.failed, 1);
+    }
+
+    #[test]
+    fn response_without_protocol_header_should_be_invalid() {
+        let (port, handle) = start_fake_trapper(b"HTTP/1.1 400 Bad Request\r\n\r\n".to_vec());
+
+        match send_discovery_data(&get_settings(port), "[]") {
+            Err(SenderError::InvalidResponse { .. }) => {}
+            result => panic!("invalid response error expected, got {:?}", result)
+        }
+
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn unavailable_server_should_be_returned_as_connection_error() {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+        match send_discovery_data(&get_settings(port), "[]") {
+            Err(SenderError::Connection { address, .. }) => assert_eq!(address, format!("127.0.0.1:{}", port)),
+            result => panic!("connection error expected, got {:?}", result)
+        }
+    }
+
+    #[test]
+    fn packet_should_contain_header_and_data_length() {
+        let request = get_sender_request("web-1", "site.discovery", "[]");
+        let packet = get_packet(&request);
+
+        assert_eq!(&packet[..5], b"ZBXD\x01");
+        assert_eq!(&packet[5..9], &(request.len() as u32).to_le_bytes());
+        assert_eq!(&packet[9..13], &[0, 0, 0, 0]);
+        assert_eq!(&packet[13..], request.as_bytes());
+
+        let mut reader: &[u8] = &packet;
+        assert_eq!(read_packet(&mut reader).unwrap(), request);
+        assert_eq!(reader.read(&mut [0u8; 1]).unwrap(), 0);
+    }
+
+    #[test]
+    fn failure_response_should_be_rejected() {
+        match get_sender_response(r#"{"response":"failed","info":"host not found"}"#) {
+            Err(SenderError::Rejected { info }) => assert_eq!(info, "host not found"),
+            result => panic!("rejected error expected, got {:?}", result)
+        }
+
+        assert!(get_sender_response(r#"{"info":"processed: 1"}"#).is_err());
+    }
+}
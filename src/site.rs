@@ -1,47 +1,310 @@
 pub mod site {
-    use crate::{DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT, WWW_SEARCH_PATTERN};
-    use crate::domain::domain::{Site, VirtualHost};
+    use std::collections::{BTreeMap, HashMap};
+    use std::str::FromStr;
 
-    pub fn get_sites_from_vhosts(vhosts: Vec<VirtualHost>, include_domains_with_www: bool, exclude_http: bool) -> Vec<Site> {
-        let sites: Vec<Site> = vhosts.iter()
-            .filter(|vhost| {
-                if vhost.port == DEFAULT_HTTP_PORT && exclude_http {
-                    return false
-                }
+    use regex::Regex;
+
+    use crate::{DEFAULT_HTTPS_PORT, WWW_SEARCH_PATTERN};
+    use crate::domain::domain::{Redirect, Scheme, Site, VirtualHost};
+    use crate::explain::explain::Verdict;
+
+    const REDIRECT_SITE_TYPE: &str = "redirect";
+
+    /// Variables of redirect target replaced for request of site root.
+    const HOST_VARIABLES: [&str; 5] = ["$host", "$server_name", "$http_host", "%{SERVER_NAME}", "%{HTTP_HOST}"];
+    const SCHEME_VARIABLES: [&str; 2] = ["$scheme", "%{REQUEST_SCHEME}"];
+    const URI_VARIABLES: [&str; 3] = ["$request_uri", "$uri", "%{REQUEST_URI}"];
+
+    /// `domain:port` for non default port of scheme, `domain_http` for http on port 80, `domain` otherwise.
+    pub const DEFAULT_NAME_TEMPLATE: &str = "{domain}{port_suffix}{http_suffix}";
+
+    /// Site name template placeholders: `{port_suffix}` is `:port` for non default port of scheme,
+    /// `{http_suffix}` is `_http` for http site on port 80.
+    const NAME_TEMPLATE_PLACEHOLDERS: [&str; 5] = ["domain", "port", "scheme", "port_suffix", "http_suffix"];
+
+    const ALIASES_SEPARATOR: &str = ",";
+
+    /// Tag macros which are always set if tags are included: web server, config directory, `on`/`off` TLS
+    /// and `env`/`owner` annotations, empty if not annotated. Other annotations add `{#TAG_<KEY>}` macros.
+    pub const TAG_MACROS: [&str; 5] = ["{#TAG_SERVER}", "{#TAG_SOURCE_DIR}", "{#TAG_TLS}", "{#TAG_ENV}", "{#TAG_OWNER}"];
+
+    const ENV_ANNOTATION: &str = "env";
+    const OWNER_ANNOTATION: &str = "owner";
+
+    /// Optional LLD macros of site.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum SiteMacro {
+        /// `{#DOMAIN}`
+        Domain,
+        /// `{#PORT}`
+        Port,
+        /// `{#SCHEME}`
+        Scheme,
+        /// `{#SERVER}`, web server source name
+        Server,
+        /// `{#ALIASES}`, comma separated other names of virtual host
+        Aliases
+    }
+
+    impl SiteMacro {
+        pub fn get_lld_macro(&self) -> &'static str {
+            match self {
+                SiteMacro::Domain => "{#DOMAIN}",
+                SiteMacro::Port => "{#PORT}",
+                SiteMacro::Scheme => "{#SCHEME}",
+                SiteMacro::Server => "{#SERVER}",
+                SiteMacro::Aliases => "{#ALIASES}"
+            }
+        }
+    }
+
+    impl FromStr for SiteMacro {
+        type Err = String;
+
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            match value.trim().to_lowercase().as_str() {
+                "domain" => Ok(SiteMacro::Domain),
+                "port" => Ok(SiteMacro::Port),
+                "scheme" => Ok(SiteMacro::Scheme),
+                "server" => Ok(SiteMacro::Server),
+                "aliases" => Ok(SiteMacro::Aliases),
+                _ => Err(format!("unsupported macro '{}', available: domain,port,scheme,server,aliases", value))
+            }
+        }
+    }
+
+    /// Site name and macros settings.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct SiteFormat {
+        /// `{#NAME}` template, see `DEFAULT_NAME_TEMPLATE`
+        pub name_template: String,
+        pub macros: Vec<SiteMacro>,
+        /// Add `{#SERVER}`, `{#CONFIG_FILE}` and `{#CONFIG_LINE}` macros
+        pub include_provenance: bool,
+        /// Add `{#TAG_*}` macros, see `TAG_MACROS`
+        pub include_tags: bool
+    }
+
+    impl Default for SiteFormat {
+        fn default() -> Self {
+            SiteFormat {
+                name_template: DEFAULT_NAME_TEMPLATE.to_string(), macros: Vec::new(),
+                include_provenance: false, include_tags: false
+            }
+        }
+    }
+
+    /// LLD macros of sites: `{#NAME}` and `{#URL}` are always set, others depend on options.
+    /// Redirect and wildcard macros are set only for such sites.
+    pub fn get_lld_macros(site_format: &SiteFormat, include_redirects: bool, include_wildcards: bool) -> Vec<&'static str> {
+        let mut macros: Vec<&'static str> = vec!["{#NAME}", "{#URL}"];
+
+        if include_wildcards {
+            macros.push("{#WILDCARD}");
+        }
 
-                let domain_in_lowercase = vhost.domain.to_lowercase();
+        if include_redirects {
+            macros.extend(&["{#TYPE}", "{#REDIRECT_TARGET}", "{#REDIRECT_CODE}"]);
+        }
 
-                let domain_starts_with_www = domain_in_lowercase.starts_with(WWW_SEARCH_PATTERN);
+        if site_format.include_provenance {
+            macros.extend(&["{#SERVER}", "{#CONFIG_FILE}", "{#CONFIG_LINE}"]);
+        }
 
-                (!include_domains_with_www && !domain_starts_with_www) ||
-                (include_domains_with_www && domain_starts_with_www) ||
-                !domain_starts_with_www
+        for site_macro in &site_format.macros {
+            if !macros.contains(&site_macro.get_lld_macro()) {
+                macros.push(site_macro.get_lld_macro());
+            }
+        }
+
+        if site_format.include_tags {
+            macros.extend(&TAG_MACROS);
+        }
+
+        macros
+    }
 
-            }).map(get_site_from_vhost).collect();
+    /// Site names and optional macros are set according to `site_format`.
+    pub fn get_sites_from_vhosts(vhosts: Vec<VirtualHost>, include_domains_with_www: bool, exclude_http: bool,
+                                 port_schemes: &HashMap<i32, Scheme>, site_format: &SiteFormat) -> Vec<Site> {
+        let sites: Vec<Site> = vhosts.iter()
+            .filter(|vhost| get_site_verdict(vhost, include_domains_with_www, exclude_http, port_schemes).is_kept())
+            .map(|vhost| get_site_from_vhost(vhost, port_schemes, site_format)).collect();
 
         return sites;
     }
 
-    fn get_site_from_vhost(vhost: &VirtualHost) -> Site {
-        let url = get_url(&vhost.domain, vhost.port);
-        Site { name: get_site_name(&vhost.domain, vhost.port), url }
+    /// Decision whether virtual host becomes site: http sites can be excluded, names with `www.` are
+    /// excluded unless included explicitly.
+    pub fn get_site_verdict(vhost: &VirtualHost, include_domains_with_www: bool, exclude_http: bool,
+                            port_schemes: &HashMap<i32, Scheme>) -> Verdict {
+        if exclude_http && get_scheme(vhost, port_schemes) == Scheme::Http {
+            return Verdict::HttpExcluded
+        }
+
+        let domain_starts_with_www = vhost.domain.to_lowercase().starts_with(WWW_SEARCH_PATTERN);
+
+        if domain_starts_with_www && !include_domains_with_www {
+            return Verdict::WwwPolicy
+        }
+
+        Verdict::Kept
     }
 
-    fn get_site_name(domain: &str, port: i32) -> String {
-        if port == DEFAULT_HTTP_PORT {
-            String::from(format!("{}_http", domain))
-        } else if port == DEFAULT_HTTPS_PORT {
-            String::from(domain)
+    /// Scheme for virtual host port: user defined scheme for port first, then TLS configuration.
+    /// Port 443 is treated as https unless TLS is explicitly disabled.
+    pub fn get_scheme(vhost: &VirtualHost, port_schemes: &HashMap<i32, Scheme>) -> Scheme {
+        let port = vhost.get_port();
+
+        if let Some(scheme) = port_schemes.get(&port) {
+            return *scheme
+        }
+
+        let default_https_port = port == DEFAULT_HTTPS_PORT && vhost.tls.engine != Some(false);
+
+        if vhost.is_tls_enabled() || default_https_port {
+            Scheme::Https
         } else {
-            String::from(format!("{}:{}", domain, port))
+            Scheme::Http
+        }
+    }
+
+    fn get_site_from_vhost(vhost: &VirtualHost, port_schemes: &HashMap<i32, Scheme>,
+                           site_format: &SiteFormat) -> Site {
+        let scheme = get_scheme(vhost, port_schemes);
+        let port = vhost.get_port();
+        let url = get_url(&vhost.domain, port, scheme);
+        let mut site = Site {
+            name: get_site_name(&site_format.name_template, &vhost.domain, port, scheme),
+            url, wildcard: vhost.wildcard.clone(),
+            site_type: None, redirect_target: None, redirect_code: None,
+            server: None, config_file: None, config_line: None,
+            domain_macro: None, port_macro: None, scheme_macro: None, aliases_macro: None, tags: BTreeMap::new(),
+            domain: vhost.domain.to_string(), scheme, port
+        };
+
+        for site_macro in &site_format.macros {
+            match site_macro {
+                SiteMacro::Domain => site.domain_macro = Some(vhost.domain.to_string()),
+                SiteMacro::Port => site.port_macro = Some(port.to_string()),
+                SiteMacro::Scheme => site.scheme_macro = Some(scheme.as_str().to_string()),
+                SiteMacro::Server => site.server = vhost.provenance.as_ref()
+                    .map(|provenance| provenance.server.to_string()),
+                SiteMacro::Aliases => site.aliases_macro = Some(vhost.aliases.join(ALIASES_SEPARATOR))
+            }
+        }
+
+        if let Some(redirect) = &vhost.redirect {
+            site.site_type = Some(REDIRECT_SITE_TYPE.to_string());
+            site.redirect_target = Some(get_redirect_target(redirect, &vhost.domain, scheme));
+            site.redirect_code = Some(redirect.code.to_string());
+        }
+
+        if site_format.include_provenance {
+            if let Some(provenance) = &vhost.provenance {
+                site.server = Some(provenance.server.to_string());
+                site.config_file = Some(provenance.file.display().to_string());
+                site.config_line = Some(provenance.line.to_string());
+            }
+        }
+
+        if site_format.include_tags {
+            site.tags = get_site_tags(vhost, scheme);
+        }
+
+        site
+    }
+
+    /// Returns `{#TAG_*}` macros of site, see `TAG_MACROS`.
+    pub fn get_site_tags(vhost: &VirtualHost, scheme: Scheme) -> BTreeMap<String, String> {
+        let (server, source_dir) = match &vhost.provenance {
+            Some(provenance) => (provenance.server.to_string(),
+                                 provenance.file.parent().map(|dir| dir.display().to_string()).unwrap_or_default()),
+            None => (String::new(), String::new())
+        };
+
+        let tls = if scheme == Scheme::Https { "on" } else { "off" };
+
+        let mut tags: BTreeMap<String, String> = BTreeMap::new();
+
+        for (tag, value) in TAG_MACROS.iter().zip(&[server, source_dir, tls.to_string()]) {
+            tags.insert(tag.to_string(), value.to_string());
+        }
+
+        for annotation in &[ENV_ANNOTATION, OWNER_ANNOTATION] {
+            tags.insert(get_tag_macro(annotation), vhost.annotations.get(*annotation).cloned().unwrap_or_default());
+        }
+
+        for (key, value) in &vhost.annotations {
+            tags.entry(get_tag_macro(key)).or_insert_with(|| value.to_string());
+        }
+
+        tags
+    }
+
+    /// `{#TAG_<KEY>}` macro for annotation key, chars except letters and digits are replaced with `_`.
+    fn get_tag_macro(key: &str) -> String {
+        let key: String = key.chars()
+            .map(|ch| if ch.is_ascii_alphanumeric() { ch.to_ascii_uppercase() } else { '_' }).collect();
+        format!("{{#TAG_{}}}", key)
+    }
+
+    /// Returns redirect target for request of site root: `https://$host$request_uri` becomes
+    /// `https://example.com/`. Other variables are kept as is.
+    pub fn get_redirect_target(redirect: &Redirect, domain: &str, scheme: Scheme) -> String {
+        let mut target = redirect.target.to_string();
+
+        for variable in HOST_VARIABLES.iter() {
+            target = target.replace(variable, domain);
+        }
+
+        for variable in SCHEME_VARIABLES.iter() {
+            target = target.replace(variable, scheme.as_str());
+        }
+
+        for variable in URI_VARIABLES.iter() {
+            target = target.replace(variable, "/");
+        }
+
+        if target.ends_with("//") && !target.ends_with("://") {
+            target.pop();
         }
+
+        target
+    }
+
+    /// Returns site name for template, see `NAME_TEMPLATE_PLACEHOLDERS`.
+    pub fn get_site_name(template: &str, domain: &str, port: i32, scheme: Scheme) -> String {
+        let port_suffix = if port != scheme.get_default_port() { format!(":{}", port) } else { String::new() };
+
+        let http_suffix = if port == scheme.get_default_port() && scheme == Scheme::Http { "_http" } else { "" };
+
+        template.replace("{domain}", domain)
+            .replace("{port}", &port.to_string())
+            .replace("{scheme}", scheme.as_str())
+            .replace("{port_suffix}", &port_suffix)
+            .replace("{http_suffix}", http_suffix)
     }
 
-    pub fn get_url(domain: &str, vhost_port: i32) -> String {
-        match vhost_port {
-            DEFAULT_HTTP_PORT => String::from(format!("http://{}", domain)),
-            DEFAULT_HTTPS_PORT => String::from(format!("https://{}", domain)),
-            _ => String::from(format!("http://{}:{}", domain, vhost_port))
+    /// Checks that template has only known placeholders.
+    pub fn validate_name_template(template: &str) -> Result<(), String> {
+        let placeholder_regex = Regex::new(r"\{([^{}]*)\}").unwrap();
+
+        for captures in placeholder_regex.captures_iter(template) {
+            if !NAME_TEMPLATE_PLACEHOLDERS.contains(&&captures[1]) {
+                return Err(format!("unsupported placeholder '{{{}}}' in name template '{}', available: {}",
+                                   &captures[1], template, NAME_TEMPLATE_PLACEHOLDERS.join(",")))
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_url(domain: &str, vhost_port: i32, scheme: Scheme) -> String {
+        if vhost_port == scheme.get_default_port() {
+            String::from(format!("{}://{}", scheme.as_str(), domain))
+        } else {
+            String::from(format!("{}://{}:{}", scheme.as_str(), domain, vhost_port))
         }
     }
 }
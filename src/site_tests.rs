@@ -1,15 +1,19 @@
 #[cfg(test)]
 mod site_tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
     use crate::{DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT};
-    use crate::domain::domain::{Site, VirtualHost};
-    use crate::site::site::get_sites_from_vhosts;
+    use crate::domain::domain::{Provenance, Scheme, Site, VirtualHost};
+    use crate::site::site::{DEFAULT_NAME_TEMPLATE, get_scheme, get_sites_from_vhosts, SiteFormat, SiteMacro,
+                            validate_name_template};
     use crate::test_samples::test_samples::{get_4_sample_vhosts, SAMPLE_DOMAIN1, SAMPLE_DOMAIN2, SAMPLE_DOMAIN3, SAMPLE_DOMAIN4};
 
     #[test]
     fn without_www_domains_result_should_not_contain_domains_with_www_lol() {
         let vhosts = get_4_sample_vhosts();
 
-        let results = get_sites_from_vhosts(vhosts, false);
+        let results = get_sites_from_vhosts(vhosts, false, false, &HashMap::new(), &SiteFormat::default());
 
         assert_eq!(results.len(), 3);
 
@@ -23,7 +27,7 @@ mod site_tests {
     fn with_www_domains_results_should_contain_domains_with_www() {
         let vhosts = get_4_sample_vhosts();
 
-        let results = get_sites_from_vhosts(vhosts, true);
+        let results = get_sites_from_vhosts(vhosts, true, false, &HashMap::new(), &SiteFormat::default());
 
         assert_eq!(results.len(), 4);
 
@@ -33,10 +37,10 @@ mod site_tests {
 
     #[test]
     fn vhost_with_https_port_should_contain_https_prefix_for_url() {
-        let vhost1 = VirtualHost { domain: SAMPLE_DOMAIN2.to_string(), port: DEFAULT_HTTPS_PORT };
+        let vhost1 = VirtualHost::new(SAMPLE_DOMAIN2, DEFAULT_HTTPS_PORT);
         let vhosts = vec![vhost1.clone()];
 
-        let results = get_sites_from_vhosts(vhosts, false);
+        let results = get_sites_from_vhosts(vhosts, false, false, &HashMap::new(), &SiteFormat::default());
 
         assert_eq!(results.len(), 1);
 
@@ -47,10 +51,10 @@ mod site_tests {
 
     #[test]
     fn vhost_with_standard_http_port_should_contain_http_prefix_for_url() {
-        let vhost1 = VirtualHost { domain: SAMPLE_DOMAIN3.to_string(), port: DEFAULT_HTTP_PORT };
+        let vhost1 = VirtualHost::new(SAMPLE_DOMAIN3, DEFAULT_HTTP_PORT);
         let vhosts = vec![vhost1.clone()];
 
-        let results = get_sites_from_vhosts(vhosts, false);
+        let results = get_sites_from_vhosts(vhosts, false, false, &HashMap::new(), &SiteFormat::default());
 
         assert_eq!(results.len(), 1);
 
@@ -63,10 +67,10 @@ mod site_tests {
     fn vhost_with_non_standard_port_should_contain_http_prefix_for_url() {
         let domain = SAMPLE_DOMAIN1;
         let custom_port = 2345;
-        let vhost1 = VirtualHost { domain: domain.to_string(), port: custom_port };
+        let vhost1 = VirtualHost::new(domain, custom_port);
         let vhosts = vec![vhost1.clone()];
 
-        let results = get_sites_from_vhosts(vhosts, false);
+        let results = get_sites_from_vhosts(vhosts, false, false, &HashMap::new(), &SiteFormat::default());
 
         assert_eq!(results.len(), 1);
 
@@ -77,10 +81,10 @@ mod site_tests {
 
     #[test]
     fn site_name_without_https_should_contain_http_postfix() {
-        let vhost1 = VirtualHost { domain: SAMPLE_DOMAIN3.to_string(), port: DEFAULT_HTTP_PORT };
+        let vhost1 = VirtualHost::new(SAMPLE_DOMAIN3, DEFAULT_HTTP_PORT);
         let vhosts = vec![vhost1.clone()];
 
-        let results = get_sites_from_vhosts(vhosts, false);
+        let results = get_sites_from_vhosts(vhosts, false, false, &HashMap::new(), &SiteFormat::default());
 
         assert_eq!(results.len(), 1);
 
@@ -90,7 +94,139 @@ mod site_tests {
         assert!(site_found.is_some())
     }
 
-    fn assert_site_with_url(sites: &Vec<Site>, url: &str) {
+    #[test]
+    fn vhost_with_tls_on_custom_port_should_contain_https_prefix_for_url() {
+        let mut vhost = VirtualHost::new(SAMPLE_DOMAIN1, 8443);
+        vhost.listens[0].ssl = true;
+
+        let results = get_sites_from_vhosts(vec![vhost], false, false, &HashMap::new(), &SiteFormat::default());
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].url, format!("https://{}:8443", SAMPLE_DOMAIN1));
+        assert_eq!(results[0].name, format!("{}:8443", SAMPLE_DOMAIN1));
+    }
+
+    #[test]
+    fn vhost_on_https_port_with_disabled_tls_should_contain_http_prefix_for_url() {
+        let mut vhost = VirtualHost::new(SAMPLE_DOMAIN1, DEFAULT_HTTPS_PORT);
+        vhost.tls.engine = Some(false);
+
+        assert_eq!(get_scheme(&vhost, &HashMap::new()), Scheme::Http);
+
+        let results = get_sites_from_vhosts(vec![vhost], false, false, &HashMap::new(), &SiteFormat::default());
+
+        assert_site_with_url(&results, &format!("http://{}:443", SAMPLE_DOMAIN1));
+    }
+
+    #[test]
+    fn port_scheme_should_override_tls_detection() {
+        let vhost = VirtualHost::new(SAMPLE_DOMAIN1, 9443);
+
+        let mut port_schemes: HashMap<i32, Scheme> = HashMap::new();
+        port_schemes.insert(9443, Scheme::Https);
+
+        let results = get_sites_from_vhosts(vec![vhost], false, false, &port_schemes, &SiteFormat::default());
+
+        assert_site_with_url(&results, &format!("https://{}:9443", SAMPLE_DOMAIN1));
+    }
+
+    #[test]
+    fn exclude_http_should_exclude_http_sites_on_custom_ports() {
+        let vhost1 = VirtualHost::new(SAMPLE_DOMAIN1, 8080);
+        let vhost2 = VirtualHost::new(SAMPLE_DOMAIN2, DEFAULT_HTTPS_PORT);
+
+        let results = get_sites_from_vhosts(vec![vhost1, vhost2], false, true, &HashMap::new(), &SiteFormat::default());
+
+        assert_eq!(results.len(), 1);
+        assert_site_with_url(&results, &format!("https://{}", SAMPLE_DOMAIN2));
+    }
+
+    #[test]
+    fn provenance_macros_should_be_set_only_if_included() {
+        let mut vhost = VirtualHost::new(SAMPLE_DOMAIN1, DEFAULT_HTTPS_PORT);
+        vhost.provenance = Some(Provenance {
+            server: "nginx".to_string(), file: PathBuf::from("/etc/nginx/conf.d/site.conf"), line: 12,
+            listen: vec!["443 ssl".to_string()], server_names: vec![SAMPLE_DOMAIN1.to_string()]
+        });
+
+        let results = get_sites_from_vhosts(vec![vhost.clone()], false, false, &HashMap::new(), &SiteFormat::default());
+        assert!(results[0].server.is_none());
+        assert!(results[0].config_file.is_none());
+
+        let site_format = SiteFormat { include_provenance: true, ..SiteFormat::default() };
+
+        let results = get_sites_from_vhosts(vec![vhost], false, false, &HashMap::new(), &site_format);
+        assert_eq!(results[0].server, Some("nginx".to_string()));
+        assert_eq!(results[0].config_file, Some("/etc/nginx/conf.d/site.conf".to_string()));
+        assert_eq!(results[0].config_line, Some("12".to_string()));
+    }
+
+    #[test]
+    fn site_name_should_follow_name_template() {
+        let vhosts = vec![VirtualHost::new(SAMPLE_DOMAIN1, DEFAULT_HTTP_PORT), VirtualHost::new(SAMPLE_DOMAIN2, 8080)];
+
+        let site_format = SiteFormat { name_template: "{scheme}-{domain}{port_suffix}".to_string(), ..SiteFormat::default() };
+
+        let names: Vec<String> = get_sites_from_vhosts(vhosts, false, false, &HashMap::new(), &site_format)
+            .into_iter().map(|site| site.name).collect();
+
+        assert_eq!(names, vec![format!("http-{}", SAMPLE_DOMAIN1), format!("http-{}:8080", SAMPLE_DOMAIN2)]);
+    }
+
+    #[test]
+    fn name_template_with_unknown_placeholder_should_be_invalid() {
+        assert!(validate_name_template(DEFAULT_NAME_TEMPLATE).is_ok());
+        assert!(validate_name_template("{domain}_{port}").is_ok());
+        assert!(validate_name_template("{host}{port_suffix}").is_err());
+    }
+
+    #[test]
+    fn selected_macros_should_be_set() {
+        let mut vhost = VirtualHost::new(SAMPLE_DOMAIN1, DEFAULT_HTTPS_PORT);
+        vhost.aliases = vec![format!("www.{}", SAMPLE_DOMAIN1), format!("m.{}", SAMPLE_DOMAIN1)];
+
+        let site_format = SiteFormat {
+            macros: vec![SiteMacro::Domain, SiteMacro::Port, SiteMacro::Scheme, SiteMacro::Aliases],
+            ..SiteFormat::default()
+        };
+
+        let results = get_sites_from_vhosts(vec![vhost], false, false, &HashMap::new(), &site_format);
+
+        assert_eq!(results[0].domain_macro, Some(SAMPLE_DOMAIN1.to_string()));
+        assert_eq!(results[0].port_macro, Some("443".to_string()));
+        assert_eq!(results[0].scheme_macro, Some("https".to_string()));
+        assert_eq!(results[0].aliases_macro, Some(format!("www.{},m.{}", SAMPLE_DOMAIN1, SAMPLE_DOMAIN1)));
+        assert!(results[0].server.is_none());
+    }
+
+    #[test]
+    fn tags_should_be_serialized_as_tag_macros() {
+        let mut vhost = VirtualHost::new(SAMPLE_DOMAIN1, DEFAULT_HTTPS_PORT);
+        vhost.provenance = Some(Provenance {
+            server: "nginx".to_string(), file: PathBuf::from("/etc/nginx/conf.d/site.conf"), line: 3,
+            listen: vec!["443".to_string()], server_names: vec![SAMPLE_DOMAIN1.to_string()]
+        });
+        vhost.annotations.insert("owner".to_string(), "web-team".to_string());
+        vhost.annotations.insert("team-channel".to_string(), "#web".to_string());
+
+        let results = get_sites_from_vhosts(vec![vhost.clone()], false, false, &HashMap::new(), &SiteFormat::default());
+        assert!(results[0].tags.is_empty());
+
+        let site_format = SiteFormat { include_tags: true, ..SiteFormat::default() };
+
+        let results = get_sites_from_vhosts(vec![vhost], false, false, &HashMap::new(), &site_format);
+
+        let json: serde_json::Value = serde_json::to_value(&results[0]).unwrap();
+
+        assert_eq!(json["{#TAG_SERVER}"], "nginx");
+        assert_eq!(json["{#TAG_SOURCE_DIR}"], "/etc/nginx/conf.d");
+        assert_eq!(json["{#TAG_TLS}"], "on");
+        assert_eq!(json["{#TAG_ENV}"], "");
+        assert_eq!(json["{#TAG_OWNER}"], "web-team");
+        assert_eq!(json["{#TAG_TEAM_CHANNEL}"], "#web");
+    }
+
+        fn assert_site_with_url(sites: &Vec<Site>, url: &str) {
         let site_found = sites.iter().find(|site| site.url == url);
         assert!(site_found.is_some())
     }
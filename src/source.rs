@@ -0,0 +1,48 @@
+pub mod source {
+    use std::path::PathBuf;
+
+    use crate::apache::apache::ApacheSource;
+    use crate::domain::domain::VirtualHost;
+    use crate::error::error::DiscoveryError;
+    use crate::nginx::nginx::NginxSource;
+    use crate::webserver::webserver::ConfigScanOptions;
+
+    /// Discovery settings of web server source.
+    #[derive(Clone, Debug)]
+    pub struct SourceSettings {
+        /// Virtual host configs directories, files are selected with `scan_options`
+        pub vhosts_paths: Vec<PathBuf>,
+        pub scan_options: ConfigScanOptions,
+        /// Main config file, includes are followed from it and `vhosts_paths` are ignored
+        pub config_path: Option<PathBuf>,
+        pub detect_302_redirects: bool,
+        pub include_redirects: bool,
+        /// Keep virtual hosts without server names, with empty domain
+        pub include_unnamed: bool
+    }
+
+    /// Web server which virtual hosts can be discovered from configuration files.
+    pub trait WebServerSource {
+        /// Source name, used in logs and command line options (`--<name>-vhosts-path`, `--<name>-config`)
+        fn name(&self) -> &'static str;
+
+        fn default_vhosts_path(&self) -> &'static str;
+
+        fn default_config_path(&self) -> &'static str;
+
+        /// Short option for vhosts path, kept for backward compatibility of nginx and apache sources
+        fn vhosts_path_short_argument(&self) -> Option<&'static str> {
+            None
+        }
+
+        /// Returns virtual hosts, one per server block or section. Error means source
+        /// configuration can't be read at all, problems with single files are added to `errors`.
+        fn discover(&self, settings: &SourceSettings,
+                    errors: &mut Vec<DiscoveryError>) -> Result<Vec<VirtualHost>, DiscoveryError>;
+    }
+
+    /// Returns all supported sources in discovery order.
+    pub fn get_web_server_sources() -> Vec<Box<dyn WebServerSource>> {
+        vec![Box::new(NginxSource), Box::new(ApacheSource)]
+    }
+}
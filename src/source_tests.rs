@@ -0,0 +1,57 @@
+#[cfg(test)]
+mod source_tests {
+    use std::path::PathBuf;
+
+    use crate::source::source::{get_web_server_sources, SourceSettings};
+    use crate::test_utils::test_utils::assert_vhost_in_vec;
+    use crate::webserver::webserver::ConfigScanOptions;
+
+    fn get_settings(vhosts_path: &str, config_path: Option<&str>) -> SourceSettings {
+        SourceSettings {
+            vhosts_paths: vec![PathBuf::from(vhosts_path)],
+            scan_options: ConfigScanOptions::default(),
+            config_path: config_path.map(PathBuf::from),
+            detect_302_redirects: false,
+            include_redirects: false,
+            include_unnamed: false
+        }
+    }
+
+    #[test]
+    fn registry_should_contain_nginx_and_apache_sources() {
+        let names: Vec<&str> = get_web_server_sources().iter().map(|source| source.name()).collect();
+
+        assert_eq!(names, vec!["nginx", "apache"]);
+    }
+
+    #[test]
+    fn source_should_discover_vhosts_from_vhosts_path_or_main_config() {
+        let sources = get_web_server_sources();
+        let nginx_source = sources.iter().find(|source| source.name() == "nginx").unwrap();
+
+        let vhosts = nginx_source.discover(&get_settings("tests/nginx-vhosts", None), &mut Vec::new()).unwrap();
+        assert_vhost_in_vec(&vhosts, "gallery.whatever.ru", 23512);
+
+        let settings = get_settings("tests/nginx-vhosts", Some("tests/nginx-main/nginx.conf"));
+        let main_config_vhosts = nginx_source.discover(&settings, &mut Vec::new()).unwrap();
+        assert!(main_config_vhosts.iter().all(|vhost| vhost.domain != "gallery.whatever.ru"));
+    }
+
+    #[test]
+    fn source_should_return_error_for_missing_main_config() {
+        let sources = get_web_server_sources();
+        let apache_source = sources.iter().find(|source| source.name() == "apache").unwrap();
+
+        let result = apache_source.discover(&get_settings("tests/apache-vhosts", Some("tests/missing.conf")), &mut Vec::new());
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn source_should_return_no_vhosts_for_missing_vhosts_path() {
+        for source in get_web_server_sources() {
+            let vhosts = source.discover(&get_settings("tests/missing-dir", None), &mut Vec::new()).unwrap();
+            assert!(vhosts.is_empty());
+        }
+    }
+}
@@ -0,0 +1,670 @@
+pub mod template {
+    use std::str::FromStr;
+
+    use serde::Serialize;
+
+    use crate::sender::sender::DEFAULT_DISCOVERY_KEY;
+
+    pub const TEMPLATE_NAME: &str = "Virtual Hosts";
+    const TEMPLATE_GROUP: &str = "Templates";
+    const TEMPLATE_DESCRIPTION: &str = "Sites from nginx\\apache config files, generated by site-discovery-flea.\n\n\
+                                        https://github.com/lebe-dev/site-discovery-flea";
+
+    const DISCOVERY_RULE_NAME: &str = "Virtual hosts discovery";
+    /// Existing template schedule: every 30 minutes at work hours, rarely at night
+    const DISCOVERY_DELAY: &str = "30m;30m/1-7,09:00-19:00;1h/1-7,19:01-23:59;3h/1-7,00:01-08:59";
+    const DISCOVERY_LIFETIME: &str = "7d";
+
+    /// Dormant calculated item of static template, kept unchanged for existing installations
+    /// and wszl tool which creates web scenarios from these items
+    const LEGACY_ITEM_NAME: &str = "Vhost {#URL} item";
+    const LEGACY_ITEM_KEY: &str = "vhost.item[{#URL}]";
+    const LEGACY_ITEM_DELAY: &str = "5m";
+    const LEGACY_ITEM_FORMULA: &str = "0";
+
+    const CHECK_ITEM_NAME: &str = "Site {#NAME} response";
+    const CHECK_ITEM_KEY: &str = "site.check[{#URL}]";
+    const CHECK_ITEM_DELAY: &str = "1m";
+    const CHECK_ITEM_HISTORY: &str = "7d";
+    const CHECK_STATUS_CODES: &str = "200";
+
+    /// Check of redirect sites, expects `{#REDIRECT_CODE}` without following redirect.
+    /// It's discovered instead of common check by redirect override.
+    const REDIRECT_CHECK_ITEM_NAME: &str = "Site {#NAME} redirect";
+    const REDIRECT_CHECK_ITEM_KEY: &str = "site.redirect.check[{#URL}]";
+    const REDIRECT_CHECK_STATUS_CODES: &str = "{#REDIRECT_CODE}";
+
+    const REDIRECT_OVERRIDE_NAME: &str = "Redirect sites";
+    const REDIRECT_TYPE_PATTERN: &str = "^redirect$";
+
+    const TRIGGER_NAME: &str = "Site {#NAME} is unavailable";
+    const TRIGGER_NODATA_PERIOD: &str = "10m";
+    const TRIGGER_PRIORITY: &str = "HIGH";
+
+    /// Application of template items, Zabbix 5.0
+    const APPLICATION_NAME: &str = "SITE";
+
+    /// Item prototype tags (Zabbix 6.0+) per LLD macro, problems inherit them.
+    const MACRO_TAGS: [(&str, &str); 10] = [
+        ("{#NAME}", "site"), ("{#DOMAIN}", "domain"), ("{#PORT}", "port"), ("{#SCHEME}", "scheme"),
+        ("{#SERVER}", "server"), ("{#TAG_SERVER}", "webserver"), ("{#TAG_SOURCE_DIR}", "source_dir"),
+        ("{#TAG_TLS}", "tls"), ("{#TAG_ENV}", "env"), ("{#TAG_OWNER}", "owner")
+    ];
+
+    /// LLD override which lowers trigger severity for sites with such `env` annotation
+    const NON_PRODUCTION_OVERRIDE_NAME: &str = "Non-production sites";
+    const NON_PRODUCTION_ENV_PATTERN: &str = "^(dev|development|test|testing|stage|staging)$";
+    const NON_PRODUCTION_TRIGGER_PRIORITY: &str = "WARNING";
+
+    /// Application prototypes (Zabbix 5.0) per LLD macro.
+    const MACRO_APPLICATIONS: [&str; 2] = ["{#DOMAIN}", "{#SERVER}"];
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum ZabbixVersion {
+        /// XML export
+        V5_0,
+        /// YAML export, template groups are `groups`
+        V6_0,
+        V6_2,
+        V6_4,
+        V7_0
+    }
+
+    impl ZabbixVersion {
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                ZabbixVersion::V5_0 => "5.0",
+                ZabbixVersion::V6_0 => "6.0",
+                ZabbixVersion::V6_2 => "6.2",
+                ZabbixVersion::V6_4 => "6.4",
+                ZabbixVersion::V7_0 => "7.0"
+            }
+        }
+
+        pub fn values() -> Vec<ZabbixVersion> {
+            vec![ZabbixVersion::V5_0, ZabbixVersion::V6_0, ZabbixVersion::V6_2, ZabbixVersion::V6_4, ZabbixVersion::V7_0]
+        }
+    }
+
+    impl FromStr for ZabbixVersion {
+        type Err = String;
+
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            ZabbixVersion::values().into_iter()
+                .find(|version| version.as_str() == value.trim())
+                .ok_or_else(|| format!("unsupported zabbix version '{}'", value))
+        }
+    }
+
+    /// Template options. LLD macros are the ones discovery produces, see `DiscoveryOptions::get_lld_macros`.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct TemplateSettings {
+        pub version: ZabbixVersion,
+        /// Discovery rule key, the same as `UserParameter` or `send` key
+        pub key: String,
+        /// `Zabbix trapper` discovery rule for `send` command, `Zabbix agent (active)` otherwise
+        pub trapper: bool,
+        pub lld_macros: Vec<&'static str>
+    }
+
+    impl TemplateSettings {
+        pub fn new(version: ZabbixVersion, lld_macros: Vec<&'static str>) -> TemplateSettings {
+            TemplateSettings { version, key: DEFAULT_DISCOVERY_KEY.to_string(), trapper: false, lld_macros }
+        }
+
+        fn get_discovery_rule_type(&self) -> &'static str {
+            if self.trapper { "TRAP" } else { "ZABBIX_ACTIVE" }
+        }
+
+        fn get_discovery_delay(&self) -> Option<&'static str> {
+            if self.trapper { None } else { Some(DISCOVERY_DELAY) }
+        }
+
+        fn get_discovery_rule_description(&self) -> String {
+            format!("LLD macros: {}", self.lld_macros.join(", "))
+        }
+
+        fn get_item_description(&self) -> String {
+            if self.lld_macros.contains(&"{#CONFIG_FILE}") {
+                "Config: {#CONFIG_FILE}:{#CONFIG_LINE}".to_string()
+            } else {
+                String::new()
+            }
+        }
+
+        /// HTTP agent item prototypes, redirect check is added if redirect sites are discovered.
+        fn get_check_items(&self) -> Vec<CheckItem> {
+            let mut items = vec![CheckItem {
+                name: CHECK_ITEM_NAME, key: CHECK_ITEM_KEY, status_codes: CHECK_STATUS_CODES,
+                follow_redirects: true, discover: true
+            }];
+
+            if self.has_redirects() {
+                items.push(CheckItem {
+                    name: REDIRECT_CHECK_ITEM_NAME, key: REDIRECT_CHECK_ITEM_KEY,
+                    status_codes: REDIRECT_CHECK_STATUS_CODES, follow_redirects: false, discover: false
+                });
+            }
+
+            items
+        }
+
+        fn has_redirects(&self) -> bool {
+            self.lld_macros.contains(&"{#REDIRECT_CODE}")
+        }
+
+        fn get_tags(&self) -> Vec<YamlTag> {
+            MACRO_TAGS.iter()
+                .filter(|(lld_macro, _)| self.lld_macros.contains(lld_macro))
+                .map(|(lld_macro, tag)| YamlTag { tag: tag.to_string(), value: lld_macro.to_string() })
+                .collect()
+        }
+
+        /// Redirect override swaps common check for redirect one, it uses `{#TYPE}` macro.
+        /// Non-production override uses `{#TAG_ENV}` macro, Zabbix 6.0+.
+        fn get_overrides(&self) -> Vec<YamlOverride> {
+            let mut overrides: Vec<YamlOverride> = Vec::new();
+
+            if self.has_redirects() {
+                overrides.push(get_redirect_override());
+            }
+
+            if self.lld_macros.contains(&"{#TAG_ENV}") {
+                overrides.push(YamlOverride {
+                    name: NON_PRODUCTION_OVERRIDE_NAME.to_string(),
+                    step: (overrides.len() + 1).to_string(),
+                    filter: YamlOverrideFilter {
+                        conditions: vec![YamlOverrideCondition {
+                            lld_macro: "{#TAG_ENV}".to_string(),
+                            value: NON_PRODUCTION_ENV_PATTERN.to_string(),
+                            formulaid: "A".to_string()
+                        }]
+                    },
+                    operations: vec![YamlOverrideOperation {
+                        operationobject: "TRIGGER_PROTOTYPE".to_string(),
+                        operator: "LIKE".to_string(),
+                        value: TRIGGER_NAME.to_string(),
+                        severity: Some(NON_PRODUCTION_TRIGGER_PRIORITY.to_string()),
+                        discover: None
+                    }]
+                });
+            }
+
+            overrides
+        }
+
+        fn get_application_prototypes(&self) -> Vec<&'static str> {
+            MACRO_APPLICATIONS.iter().filter(|lld_macro| self.lld_macros.contains(lld_macro)).copied().collect()
+        }
+    }
+
+    /// Returns template export: XML for Zabbix 5.0, YAML for later versions.
+    /// Site check is HTTP agent item prototype with trigger prototype on missing data,
+    /// dormant `vhost.item[{#URL}]` prototype of static template is kept as is.
+    pub fn get_template(settings: &TemplateSettings) -> String {
+        match settings.version {
+            ZabbixVersion::V5_0 => get_xml_template(settings),
+            _ => get_yaml_template(settings)
+        }
+    }
+
+    struct CheckItem {
+        name: &'static str,
+        key: &'static str,
+        status_codes: &'static str,
+        follow_redirects: bool,
+        /// Prototype is disabled by default and enabled by override
+        discover: bool
+    }
+
+    fn get_redirect_override() -> YamlOverride {
+        let get_operation = |name: &str, discover: &str| YamlOverrideOperation {
+            operationobject: "ITEM_PROTOTYPE".to_string(),
+            operator: "EQUAL".to_string(),
+            value: name.to_string(),
+            severity: None,
+            discover: Some(discover.to_string())
+        };
+
+        YamlOverride {
+            name: REDIRECT_OVERRIDE_NAME.to_string(),
+            step: "1".to_string(),
+            filter: YamlOverrideFilter {
+                conditions: vec![YamlOverrideCondition {
+                    lld_macro: "{#TYPE}".to_string(),
+                    value: REDIRECT_TYPE_PATTERN.to_string(),
+                    formulaid: "A".to_string()
+                }]
+            },
+            operations: vec![
+                get_operation(CHECK_ITEM_NAME, "NO_DISCOVER"),
+                get_operation(REDIRECT_CHECK_ITEM_NAME, "DISCOVER")
+            ]
+        }
+    }
+
+    #[derive(Serialize)]
+    struct YamlExport {
+        zabbix_export: YamlExportContent
+    }
+
+    #[derive(Serialize)]
+    struct YamlExportContent {
+        version: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        groups: Option<Vec<YamlGroup>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        template_groups: Option<Vec<YamlGroup>>,
+        templates: Vec<YamlTemplate>
+    }
+
+    #[derive(Serialize)]
+    struct YamlGroup {
+        uuid: String,
+        name: String
+    }
+
+    #[derive(Serialize)]
+    struct YamlGroupName {
+        name: String
+    }
+
+    #[derive(Serialize)]
+    struct YamlTemplate {
+        uuid: String,
+        template: String,
+        name: String,
+        description: String,
+        groups: Vec<YamlGroupName>,
+        discovery_rules: Vec<YamlDiscoveryRule>
+    }
+
+    #[derive(Serialize)]
+    struct YamlDiscoveryRule {
+        uuid: String,
+        name: String,
+        #[serde(rename = "type")]
+        rule_type: String,
+        key: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        delay: Option<String>,
+        lifetime: String,
+        description: String,
+        item_prototypes: Vec<YamlItemPrototype>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        overrides: Vec<YamlOverride>
+    }
+
+    #[derive(Serialize)]
+    struct YamlOverride {
+        name: String,
+        step: String,
+        filter: YamlOverrideFilter,
+        operations: Vec<YamlOverrideOperation>
+    }
+
+    #[derive(Serialize)]
+    struct YamlOverrideFilter {
+        conditions: Vec<YamlOverrideCondition>
+    }
+
+    #[derive(Serialize)]
+    struct YamlOverrideCondition {
+        #[serde(rename = "macro")]
+        lld_macro: String,
+        value: String,
+        formulaid: String
+    }
+
+    #[derive(Serialize)]
+    struct YamlOverrideOperation {
+        operationobject: String,
+        operator: String,
+        value: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        severity: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        discover: Option<String>
+    }
+
+    /// HTTP agent or calculated item prototype, values of other type are skipped.
+    #[derive(Default, Serialize)]
+    struct YamlItemPrototype {
+        uuid: String,
+        name: String,
+        #[serde(rename = "type")]
+        item_type: String,
+        key: String,
+        delay: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        discover: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        history: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        trends: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        value_type: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        params: Option<String>,
+        #[serde(skip_serializing_if = "String::is_empty")]
+        description: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        follow_redirects: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        retrieve_mode: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        status_codes: Option<String>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        tags: Vec<YamlTag>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        trigger_prototypes: Vec<YamlTriggerPrototype>
+    }
+
+    #[derive(Serialize)]
+    struct YamlTag {
+        tag: String,
+        value: String
+    }
+
+    #[derive(Serialize)]
+    struct YamlTriggerPrototype {
+        uuid: String,
+        expression: String,
+        name: String,
+        priority: String
+    }
+
+    fn get_yaml_template(settings: &TemplateSettings) -> String {
+        let groups = vec![YamlGroup { uuid: get_uuid(&["group", TEMPLATE_GROUP]), name: TEMPLATE_GROUP.to_string() }];
+
+        let (groups, template_groups) = match settings.version {
+            ZabbixVersion::V6_0 => (Some(groups), None),
+            _ => (None, Some(groups))
+        };
+
+        let legacy_item_prototype = YamlItemPrototype {
+            uuid: get_uuid(&[TEMPLATE_NAME, LEGACY_ITEM_KEY]),
+            name: LEGACY_ITEM_NAME.to_string(),
+            item_type: "CALCULATED".to_string(),
+            key: LEGACY_ITEM_KEY.to_string(),
+            delay: LEGACY_ITEM_DELAY.to_string(),
+            params: Some(LEGACY_ITEM_FORMULA.to_string()),
+            ..YamlItemPrototype::default()
+        };
+
+        let mut item_prototypes = vec![legacy_item_prototype];
+
+        for item in settings.get_check_items() {
+            let trigger_prototype = YamlTriggerPrototype {
+                uuid: get_uuid(&[TEMPLATE_NAME, item.key, TRIGGER_NAME]),
+                expression: format!("nodata(/{}/{},{})=1", TEMPLATE_NAME, item.key, TRIGGER_NODATA_PERIOD),
+                name: TRIGGER_NAME.to_string(),
+                priority: TRIGGER_PRIORITY.to_string()
+            };
+
+            item_prototypes.push(YamlItemPrototype {
+                uuid: get_uuid(&[TEMPLATE_NAME, item.key]),
+                name: item.name.to_string(),
+                item_type: "HTTP_AGENT".to_string(),
+                key: item.key.to_string(),
+                delay: CHECK_ITEM_DELAY.to_string(),
+                discover: if item.discover { None } else { Some("NO_DISCOVER".to_string()) },
+                history: Some(CHECK_ITEM_HISTORY.to_string()),
+                trends: Some("0".to_string()),
+                value_type: Some("TEXT".to_string()),
+                params: None,
+                description: settings.get_item_description(),
+                url: Some("{#URL}".to_string()),
+                follow_redirects: if item.follow_redirects { None } else { Some("NO".to_string()) },
+                retrieve_mode: Some("HEADERS".to_string()),
+                status_codes: Some(item.status_codes.to_string()),
+                tags: settings.get_tags(),
+                trigger_prototypes: vec![trigger_prototype]
+            });
+        }
+
+        let discovery_rule = YamlDiscoveryRule {
+            uuid: get_uuid(&[TEMPLATE_NAME, &settings.key]),
+            name: DISCOVERY_RULE_NAME.to_string(),
+            rule_type: settings.get_discovery_rule_type().to_string(),
+            key: settings.key.to_string(),
+            delay: settings.get_discovery_delay().map(|delay| delay.to_string()),
+            lifetime: DISCOVERY_LIFETIME.to_string(),
+            description: settings.get_discovery_rule_description(),
+            item_prototypes,
+            overrides: settings.get_overrides()
+        };
+
+        let template = YamlTemplate {
+            uuid: get_uuid(&["template", TEMPLATE_NAME]),
+            template: TEMPLATE_NAME.to_string(),
+            name: TEMPLATE_NAME.to_string(),
+            description: TEMPLATE_DESCRIPTION.to_string(),
+            groups: vec![YamlGroupName { name: TEMPLATE_GROUP.to_string() }],
+            discovery_rules: vec![discovery_rule]
+        };
+
+        let export = YamlExport {
+            zabbix_export: YamlExportContent {
+                version: settings.version.as_str().to_string(), groups, template_groups, templates: vec![template]
+            }
+        };
+
+        serde_yaml::to_string(&export).unwrap()
+    }
+
+    fn get_xml_template(settings: &TemplateSettings) -> String {
+        let delay = settings.get_discovery_delay()
+            .map(|delay| format!("\n                    <delay>{}</delay>", delay)).unwrap_or_default();
+
+        let mut item_prototypes = vec![get_xml_legacy_item_prototype()];
+        item_prototypes.extend(settings.get_check_items().iter().map(|item| get_xml_item_prototype(settings, item)));
+
+        let overrides = if settings.has_redirects() { get_xml_override(&get_redirect_override()) } else { String::new() };
+
+        format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<zabbix_export>
+    <version>5.0</version>
+    <groups>
+        <group>
+            <name>{group}</name>
+        </group>
+    </groups>
+    <templates>
+        <template>
+            <template>{template}</template>
+            <name>{template}</name>
+            <description>{template_description}</description>
+            <groups>
+                <group>
+                    <name>{group}</name>
+                </group>
+            </groups>
+            <applications>
+                <application>
+                    <name>{application}</name>
+                </application>
+            </applications>
+            <discovery_rules>
+                <discovery_rule>
+                    <name>{rule_name}</name>
+                    <type>{rule_type}</type>
+                    <key>{key}</key>{delay}
+                    <lifetime>{lifetime}</lifetime>
+                    <description>{rule_description}</description>
+                    <item_prototypes>{item_prototypes}
+                    </item_prototypes>{overrides}
+                </discovery_rule>
+            </discovery_rules>
+        </template>
+    </templates>
+</zabbix_export>
+"#,
+                group = TEMPLATE_GROUP,
+                template = TEMPLATE_NAME,
+                template_description = get_xml_value(TEMPLATE_DESCRIPTION),
+                application = APPLICATION_NAME,
+                rule_name = DISCOVERY_RULE_NAME,
+                rule_type = settings.get_discovery_rule_type(),
+                key = get_xml_value(&settings.key),
+                delay = delay,
+                lifetime = DISCOVERY_LIFETIME,
+                rule_description = get_xml_value(&settings.get_discovery_rule_description()),
+                item_prototypes = item_prototypes.join(""),
+                overrides = overrides)
+    }
+
+    /// The same prototype as in static template.
+    fn get_xml_legacy_item_prototype() -> String {
+        format!(r#"
+                        <item_prototype>
+                            <name>{name}</name>
+                            <type>CALCULATED</type>
+                            <key>{key}</key>
+                            <delay>{delay}</delay>
+                            <params>{formula}</params>
+                            <applications>
+                                <application>
+                                    <name>{application}</name>
+                                </application>
+                            </applications>
+                        </item_prototype>"#,
+                name = LEGACY_ITEM_NAME,
+                key = get_xml_value(LEGACY_ITEM_KEY),
+                delay = LEGACY_ITEM_DELAY,
+                formula = LEGACY_ITEM_FORMULA,
+                application = APPLICATION_NAME)
+    }
+
+    fn get_xml_item_prototype(settings: &TemplateSettings, item: &CheckItem) -> String {
+        let description = settings.get_item_description();
+        let description = if description.is_empty() { String::new() } else {
+            format!("\n                            <description>{}</description>", get_xml_value(&description))
+        };
+
+        let discover = if item.discover { "" } else { "\n                            <discover>NO_DISCOVER</discover>" };
+
+        let follow_redirects = if item.follow_redirects { "" } else {
+            "\n                            <follow_redirects>NO</follow_redirects>"
+        };
+
+        let application_prototypes: Vec<String> = settings.get_application_prototypes().iter()
+            .map(|name| format!("\n                                <application_prototype>\
+                                 \n                                    <name>{}</name>\
+                                 \n                                </application_prototype>", get_xml_value(name)))
+            .collect();
+
+        let application_prototypes = if application_prototypes.is_empty() { String::new() } else {
+            format!("\n                            <application_prototypes>{}\
+                     \n                            </application_prototypes>", application_prototypes.join(""))
+        };
+
+        let expression = format!("{{{}:{}.nodata({})}}=1", TEMPLATE_NAME, item.key, TRIGGER_NODATA_PERIOD);
+
+        format!(r#"
+                        <item_prototype>
+                            <name>{item_name}</name>
+                            <type>HTTP_AGENT</type>
+                            <key>{item_key}</key>
+                            <delay>{item_delay}</delay>{discover}
+                            <history>{item_history}</history>
+                            <trends>0</trends>
+                            <value_type>TEXT</value_type>{item_description}
+                            <applications>
+                                <application>
+                                    <name>{application}</name>
+                                </application>
+                            </applications>{application_prototypes}
+                            <url>{{#URL}}</url>{follow_redirects}
+                            <retrieve_mode>HEADERS</retrieve_mode>
+                            <status_codes>{status_codes}</status_codes>
+                            <trigger_prototypes>
+                                <trigger_prototype>
+                                    <expression>{expression}</expression>
+                                    <name>{trigger_name}</name>
+                                    <priority>{trigger_priority}</priority>
+                                </trigger_prototype>
+                            </trigger_prototypes>
+                        </item_prototype>"#,
+                item_name = item.name,
+                item_key = get_xml_value(item.key),
+                item_delay = CHECK_ITEM_DELAY,
+                discover = discover,
+                item_history = CHECK_ITEM_HISTORY,
+                item_description = description,
+                application = APPLICATION_NAME,
+                application_prototypes = application_prototypes,
+                follow_redirects = follow_redirects,
+                status_codes = item.status_codes,
+                expression = get_xml_value(&expression),
+                trigger_name = TRIGGER_NAME,
+                trigger_priority = TRIGGER_PRIORITY)
+    }
+
+    fn get_xml_override(override_rule: &YamlOverride) -> String {
+        let conditions: Vec<String> = override_rule.filter.conditions.iter()
+            .map(|condition| format!("\n                                    <condition>\
+                                      \n                                        <macro>{}</macro>\
+                                      \n                                        <value>{}</value>\
+                                      \n                                        <formulaid>{}</formulaid>\
+                                      \n                                    </condition>",
+                                     condition.lld_macro, get_xml_value(&condition.value), condition.formulaid))
+            .collect();
+
+        let operations: Vec<String> = override_rule.operations.iter()
+            .map(|operation| {
+                let discover = operation.discover.as_ref()
+                    .map(|discover| format!("\n                                    <discover>{}</discover>", discover))
+                    .unwrap_or_default();
+
+                format!("\n                                <operation>\
+                         \n                                    <operationobject>{}</operationobject>\
+                         \n                                    <operator>{}</operator>\
+                         \n                                    <value>{}</value>{}\
+                         \n                                </operation>",
+                        operation.operationobject, operation.operator, get_xml_value(&operation.value), discover)
+            })
+            .collect();
+
+        format!(r#"
+                    <overrides>
+                        <override>
+                            <name>{name}</name>
+                            <step>{step}</step>
+                            <filter>
+                                <conditions>{conditions}
+                                </conditions>
+                            </filter>
+                            <operations>{operations}
+                            </operations>
+                        </override>
+                    </overrides>"#,
+                name = override_rule.name,
+                step = override_rule.step,
+                conditions = conditions.join(""),
+                operations = operations.join(""))
+    }
+
+    fn get_xml_value(value: &str) -> String {
+        value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+            .replace('"', "&quot;").replace('\n', "&#13;\n")
+    }
+
+    /// Stable UUID v4 formatted id of template entity, the same template is imported as update.
+    /// Two FNV-1a hashes of entity path give 128 bits.
+    fn get_uuid(path: &[&str]) -> String {
+        let value = path.join("/");
+
+        let get_hash = |seed: u64| value.bytes().fold(seed, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+
+        let high = get_hash(0xcbf29ce484222325);
+        let low = get_hash(0x84222325cbf29ce4);
+
+        let high = (high & 0xffff_ffff_ffff_0fff) | 0x0000_0000_0000_4000;
+        let low = (low & 0x3fff_ffff_ffff_ffff) | 0x8000_0000_0000_0000;
+
+        format!("{:016x}{:016x}", high, low)
+    }
+}
@@ -0,0 +1,164 @@
+#[cfg(test)]
+mod template_tests {
+    use std::fs;
+
+    use crate::discovery::discovery::DiscoveryOptions;
+    use crate::site::site::SiteMacro;
+    use crate::template::template::{get_template, TemplateSettings, ZabbixVersion};
+
+    fn get_settings(version: ZabbixVersion) -> TemplateSettings {
+        let options = DiscoveryOptions::new()
+            .include_provenance(true)
+            .include_redirects(true)
+            .macros(vec![SiteMacro::Domain, SiteMacro::Server]);
+
+        TemplateSettings::new(version, options.get_lld_macros())
+    }
+
+    #[test]
+    fn lld_macros_should_follow_discovery_options() {
+        assert_eq!(DiscoveryOptions::new().get_lld_macros(), vec!["{#NAME}", "{#URL}"]);
+
+        assert_eq!(get_settings(ZabbixVersion::V7_0).lld_macros, vec![
+            "{#NAME}", "{#URL}", "{#TYPE}", "{#REDIRECT_TARGET}", "{#REDIRECT_CODE}",
+            "{#SERVER}", "{#CONFIG_FILE}", "{#CONFIG_LINE}", "{#DOMAIN}"
+        ]);
+    }
+
+    #[test]
+    fn yaml_template_should_contain_discovery_rule_with_prototypes() {
+        let template = get_template(&get_settings(ZabbixVersion::V7_0));
+
+        let export: serde_yaml::Value = serde_yaml::from_str(&template).unwrap();
+        let export = &export["zabbix_export"];
+
+        assert_eq!(export["version"], "7.0");
+        assert_eq!(export["template_groups"][0]["name"], "Templates");
+
+        let rule = &export["templates"][0]["discovery_rules"][0];
+        assert_eq!(rule["type"], "ZABBIX_ACTIVE");
+        assert_eq!(rule["key"], "site.discovery");
+        assert!(rule["description"].as_str().unwrap().contains("{#CONFIG_FILE}"));
+
+        let legacy_item = &rule["item_prototypes"][0];
+        assert_eq!(legacy_item["type"], "CALCULATED");
+        assert_eq!(legacy_item["key"], "vhost.item[{#URL}]");
+        assert_eq!(legacy_item["params"], "0");
+
+        let item = &rule["item_prototypes"][1];
+        assert_eq!(item["type"], "HTTP_AGENT");
+        assert_eq!(item["key"], "site.check[{#URL}]");
+        assert_eq!(item["url"], "{#URL}");
+        assert_eq!(item["status_codes"], "200");
+        assert!(item["discover"].is_null());
+        assert_eq!(item["description"], "Config: {#CONFIG_FILE}:{#CONFIG_LINE}");
+
+        let tags: Vec<&str> = item["tags"].as_sequence().unwrap().iter()
+            .map(|tag| tag["tag"].as_str().unwrap()).collect();
+        assert_eq!(tags, vec!["site", "domain", "server"]);
+
+        let trigger = &item["trigger_prototypes"][0];
+        assert_eq!(trigger["expression"], "nodata(/Virtual Hosts/site.check[{#URL}],10m)=1");
+        assert_eq!(trigger["uuid"].as_str().unwrap().len(), 32);
+    }
+
+    #[test]
+    fn redirect_sites_should_be_checked_with_redirect_code() {
+        let export: serde_yaml::Value = serde_yaml::from_str(&get_template(&get_settings(ZabbixVersion::V7_0))).unwrap();
+        let rule = &export["zabbix_export"]["templates"][0]["discovery_rules"][0];
+
+        let item = &rule["item_prototypes"][2];
+        assert_eq!(item["key"], "site.redirect.check[{#URL}]");
+        assert_eq!(item["status_codes"], "{#REDIRECT_CODE}");
+        assert_eq!(item["follow_redirects"], "NO");
+        assert_eq!(item["discover"], "NO_DISCOVER");
+
+        let override_rule = &rule["overrides"][0];
+        assert_eq!(override_rule["filter"]["conditions"][0]["macro"], "{#TYPE}");
+        assert_eq!(override_rule["operations"][0]["value"], "Site {#NAME} response");
+        assert_eq!(override_rule["operations"][0]["discover"], "NO_DISCOVER");
+        assert_eq!(override_rule["operations"][1]["value"], "Site {#NAME} redirect");
+        assert_eq!(override_rule["operations"][1]["discover"], "DISCOVER");
+
+        let settings = TemplateSettings::new(ZabbixVersion::V7_0, DiscoveryOptions::new().get_lld_macros());
+        assert!(!get_template(&settings).contains("site.redirect.check"));
+    }
+
+    #[test]
+    fn zabbix_6_0_template_should_use_groups() {
+        let template = get_template(&get_settings(ZabbixVersion::V6_0));
+
+        let export: serde_yaml::Value = serde_yaml::from_str(&template).unwrap();
+
+        assert_eq!(export["zabbix_export"]["groups"][0]["name"], "Templates");
+        assert!(export["zabbix_export"]["template_groups"].is_null());
+    }
+
+    #[test]
+    fn trapper_template_should_not_have_discovery_delay() {
+        let mut settings = get_settings(ZabbixVersion::V6_4);
+        settings.trapper = true;
+        settings.key = "web.discovery".to_string();
+
+        let export: serde_yaml::Value = serde_yaml::from_str(&get_template(&settings)).unwrap();
+        let rule = &export["zabbix_export"]["templates"][0]["discovery_rules"][0];
+
+        assert_eq!(rule["type"], "TRAP");
+        assert_eq!(rule["key"], "web.discovery");
+        assert!(rule["delay"].is_null());
+    }
+
+    #[test]
+    fn xml_template_should_be_zabbix_5_0_export() {
+        let template = get_template(&get_settings(ZabbixVersion::V5_0));
+
+        assert!(template.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<zabbix_export>\n    <version>5.0</version>"));
+        assert!(template.contains("<key>site.check[{#URL}]</key>"));
+        assert!(template.contains("<expression>{Virtual Hosts:site.check[{#URL}].nodata(10m)}=1</expression>"));
+        assert!(template.contains("<status_codes>{#REDIRECT_CODE}</status_codes>"));
+        assert!(template.contains("<macro>{#TYPE}</macro>"));
+        assert!(template.contains("<name>{#SERVER}</name>"));
+        assert!(template.contains("<type>ZABBIX_ACTIVE</type>"));
+    }
+
+    #[test]
+    fn template_should_be_the_same_for_the_same_settings() {
+        let settings = get_settings(ZabbixVersion::V7_0);
+
+        assert_eq!(get_template(&settings), get_template(&settings));
+    }
+
+    #[test]
+    fn static_template_item_prototype_should_be_kept_in_generated_template() {
+        let settings = TemplateSettings::new(ZabbixVersion::V5_0, DiscoveryOptions::new().get_lld_macros());
+
+        let static_template = fs::read_to_string("files/vhost-discovery-template.xml").unwrap();
+
+        let start = static_template.find("                        <item_prototype>").unwrap();
+        let end = static_template.find("</item_prototype>").unwrap() + "</item_prototype>".len();
+        let static_item_prototype = &static_template[start..end];
+
+        assert!(static_item_prototype.contains("<type>CALCULATED</type>"));
+        assert!(get_template(&settings).contains(static_item_prototype));
+    }
+
+    #[test]
+    fn template_with_tags_should_contain_tag_macros_and_override() {
+        let options = DiscoveryOptions::new().include_tags(true);
+        let settings = TemplateSettings::new(ZabbixVersion::V6_4, options.get_lld_macros());
+
+        let export: serde_yaml::Value = serde_yaml::from_str(&get_template(&settings)).unwrap();
+        let rule = &export["zabbix_export"]["templates"][0]["discovery_rules"][0];
+
+        let tags: Vec<&str> = rule["item_prototypes"][1]["tags"].as_sequence().unwrap().iter()
+            .map(|tag| tag["tag"].as_str().unwrap()).collect();
+        assert_eq!(tags, vec!["site", "webserver", "source_dir", "tls", "env", "owner"]);
+
+        let override_rule = &rule["overrides"][0];
+        assert_eq!(override_rule["filter"]["conditions"][0]["macro"], "{#TAG_ENV}");
+        assert_eq!(override_rule["operations"][0]["severity"], "WARNING");
+
+        let settings = TemplateSettings::new(ZabbixVersion::V6_4, DiscoveryOptions::new().get_lld_macros());
+        assert!(!get_template(&settings).contains("overrides"));
+    }
+}
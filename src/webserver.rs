@@ -1,137 +1,522 @@
 pub mod webserver {
-    use std::{fs, io};
-    use std::fs::{DirEntry, File};
-    use std::io::{BufRead, BufReader};
+    use std::{fmt, fs};
+    use std::collections::{BTreeMap, HashSet};
     use std::path::{Path, PathBuf};
 
-    use regex::Regex;
+    use wildmatch::WildMatch;
 
-    use crate::domain::domain::VirtualHost;
+    use crate::DEFAULT_HTTP_PORT;
+    use crate::certificate::certificate::is_supported_certificate_path;
+    use crate::error::error::DiscoveryError;
+    use crate::domain::domain::{get_name_kind, ListenEndpoint, NameKind, Redirect, RedirectScope, TlsConfig,
+                                VirtualHost};
 
-    const VHOST_CONFIG_FILE_EXTENSION: &str = ".conf";
+    const VHOST_CONFIG_FILE_PATTERN: &str = "*.conf";
 
-    pub fn get_vhost_config_file_list(vhost_root_path: &Path) -> Result<Vec<PathBuf>,io::Error> {
-        let paths = fs::read_dir(&vhost_root_path)?;
+    const WILDCARD_CHARS: [char; 3] = ['*', '?', '['];
 
-        let mut vhost_files: Vec<PathBuf> = Vec::new();
+    /// Redirect codes, 302, 303 and 307 are temporary ones.
+    pub const REDIRECT_CODES: [i32; 5] = [301, 302, 303, 307, 308];
 
-        for path in paths {
-            let file = path.unwrap();
+    /// Target prefixes of redirects to absolute URL.
+    const REDIRECT_URL_PREFIXES: [&str; 4] = ["http://", "https://", "$scheme://", "%{request_scheme}://"];
+
+    /// Comment prefix of config annotations, i.e. `# site-discovery-flea: env=production owner=web-team`.
+    pub const ANNOTATION_PREFIX: &str = "site-discovery-flea:";
+
+    /// Rewrite patterns which match any request URI.
+    const MATCH_ALL_PATTERNS: [&str; 18] = [
+        "^", "^/", "/", ".*", "^.*", "^.*$", "^/.*", "^/.*$", "(.*)", "^(.*)", "^(.*)$",
+        "/(.*)", "^/(.*)", "^/(.*)$", "^(/.*)", "^(/.*)$", "^/?(.*)", "^/?(.*)$"
+    ];
+
+    /// How virtual host configs directory is scanned.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct ConfigScanOptions {
+        /// Directory levels to walk, `1` means files of directory itself only
+        pub max_depth: usize,
+        /// File name globs to parse, i.e. `*.conf`
+        pub include_patterns: Vec<String>,
+        /// File name globs to skip, checked after include ones
+        pub exclude_patterns: Vec<String>
+    }
+
+    impl Default for ConfigScanOptions {
+        fn default() -> Self {
+            ConfigScanOptions {
+                max_depth: 1,
+                include_patterns: vec![VHOST_CONFIG_FILE_PATTERN.to_string()],
+                exclude_patterns: Vec::new()
+            }
+        }
+    }
+
+    impl ConfigScanOptions {
+        pub fn is_file_included(&self, file_name: &str) -> bool {
+            self.include_patterns.iter().any(|pattern| WildMatch::new(pattern).is_match(file_name)) &&
+            !self.exclude_patterns.iter().any(|pattern| WildMatch::new(pattern).is_match(file_name))
+        }
+    }
+
+    /// Single configuration directive with its arguments and position.
+    /// Block directives (`server { .. }`, `<VirtualHost>`) keep nested directives in `block`.
+    #[derive(Clone, Debug)]
+    pub struct Directive {
+        pub name: String,
+        pub args: Vec<String>,
+        pub file: PathBuf,
+        pub line: usize,
+        pub block: Option<Vec<Directive>>
+    }
 
-            match get_vhost_file_from_dir(vhost_root_path, &file) {
-                Some(file_path) => vhost_files.push(file_path),
-                None => {}
+    impl Directive {
+        pub fn children(&self) -> &[Directive] {
+            match &self.block {
+                Some(directives) => directives,
+                None => &[]
             }
         }
 
+        pub fn find_first(&self, name: &str) -> Option<&Directive> {
+            self.children().iter().find(|directive| directive.name == name)
+        }
+
+        pub fn find_all(&self, name: &str) -> Vec<&Directive> {
+            self.children().iter().filter(|directive| directive.name == name).collect()
+        }
+
+        pub fn find_all_ignore_case(&self, name: &str) -> Vec<&Directive> {
+            self.children().iter().filter(|directive| directive.name.eq_ignore_ascii_case(name)).collect()
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct ParseError {
+        pub file: PathBuf,
+        pub line: usize,
+        pub message: String
+    }
+
+    impl fmt::Display for ParseError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}:{}: {}", self.file.display(), self.line, self.message)
+        }
+    }
+
+    /// Returns config files of directory and its subdirectories up to `max_depth`, sorted by name
+    /// within directory.
+    /// Files and directories are followed through symlinks, every file is returned once.
+    /// Unreadable directory is returned as error, unreadable subdirectories are added to `errors`.
+    pub fn get_vhost_config_file_list(vhost_root_path: &Path, scan_options: &ConfigScanOptions,
+                                      errors: &mut Vec<DiscoveryError>) -> Result<Vec<PathBuf>, DiscoveryError> {
+        let mut visited_paths: HashSet<PathBuf> = HashSet::new();
+        let mut vhost_files: Vec<PathBuf> = Vec::new();
+
+        collect_vhost_config_files(vhost_root_path, scan_options, 1, &mut visited_paths,
+                                   &mut vhost_files, errors)?;
+
         Ok(vhost_files)
     }
 
-    pub fn get_virtual_hosts_from_file(vhost_file: &Path,
-                                       section_start_pattern: Regex,
-                                       redirect_with_301_pattern: Regex,
-                                       port_search_pattern: Regex,
-                                       domain_search_pattern: Regex) -> Result<Vec<VirtualHost>, io::Error> {
-        let mut hosts: Vec<VirtualHost> = Vec::new();
+    fn collect_vhost_config_files(dir_path: &Path, scan_options: &ConfigScanOptions, depth: usize,
+                                  visited_paths: &mut HashSet<PathBuf>, vhost_files: &mut Vec<PathBuf>,
+                                  errors: &mut Vec<DiscoveryError>) -> Result<(), DiscoveryError> {
+        let canonical_dir_path = fs::canonicalize(dir_path)
+            .map_err(|e| DiscoveryError::from_io_error(dir_path, &e))?;
 
-        let vhost_file_name = vhost_file.to_str().unwrap();
+        if !visited_paths.insert(canonical_dir_path) {
+            debug!("directory '{}' is already scanned, skip", dir_path.display());
+            return Ok(())
+        }
 
-        info!("get virtual hosts from file '{}'", vhost_file_name);
+        let entries = fs::read_dir(dir_path).map_err(|e| DiscoveryError::from_io_error(dir_path, &e))?;
 
-        let input = File::open(vhost_file)?;
-        let buffered = BufReader::new(input);
+        let mut paths: Vec<PathBuf> = Vec::new();
 
-        let mut inside_server_section = false;
-        let mut redirect_to_url = false;
-        let mut port: Option<i32> = None;
-        let mut domain: Option<String> = None;
+        for entry in entries {
+            let entry = entry.map_err(|e| DiscoveryError::from_io_error(dir_path, &e))?;
+            paths.push(dir_path.join(entry.file_name()));
+        }
 
-        for line in buffered.lines() {
-            let row = line.unwrap_or(String::new());
+        paths.sort();
 
-            trace!("row '{}'", row);
+        let mut dir_paths: Vec<PathBuf> = Vec::new();
 
-            if section_start_pattern.is_match(&row) {
-                if domain.is_none() && port.is_some() {
-                    domain = None;
-                    port = None;
+        for path in paths {
+            // follows symlinks, broken ones are skipped
+            let metadata = match fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    debug!("skip '{}': {}", path.display(), e);
+                    continue;
                 }
+            };
+
+            if metadata.is_dir() {
+                dir_paths.push(path);
+                continue;
+            }
 
-                if port.is_some() && domain.is_some() && !redirect_to_url {
-                    let vhost = get_virtual_host(domain, port);
+            let file_name = match path.file_name().and_then(|file_name| file_name.to_str()) {
+                Some(file_name) => file_name,
+                None => continue
+            };
 
-                    hosts.push(vhost);
+            if !scan_options.is_file_included(file_name) {
+                trace!("skip file '{}', doesn't match file patterns", path.display());
+                continue;
+            }
 
-                    domain = None;
-                    port = None;
+            match fs::canonicalize(&path) {
+                Ok(canonical_path) => {
+                    if visited_paths.insert(canonical_path) {
+                        vhost_files.push(path);
+                    } else {
+                        debug!("file '{}' is already found by other path, skip", path.display());
+                    }
                 }
+                Err(e) => errors.push(DiscoveryError::from_io_error(&path, &e))
+            }
+        }
 
-                inside_server_section = true;
-                redirect_to_url = false;
+        // files of directory go before files of subdirectories, so direct paths win over symlinks
+        if depth < scan_options.max_depth {
+            for dir_path in dir_paths {
+                if let Err(e) = collect_vhost_config_files(&dir_path, scan_options, depth + 1, visited_paths,
+                                                           vhost_files, errors) {
+                    errors.push(e);
+                }
             }
+        }
+
+        Ok(())
+    }
 
-            if inside_server_section {
-                if redirect_with_301_pattern.is_match(&row) {
-                    debug!("redirect detected");
-                    redirect_to_url = true;
-                    inside_server_section = false;
+    /// Returns virtual hosts from config files of directories. Files which can't be read or parsed and
+    /// unreadable directories are added to `errors` and skipped, missing directory means there are no virtual hosts.
+    /// File reached from several directories or through several symlinks is parsed once.
+    pub fn get_vhosts_from_dirs<F>(vhosts_paths: &[PathBuf], scan_options: &ConfigScanOptions,
+                                   errors: &mut Vec<DiscoveryError>,
+                                   get_vhosts_from_file: F) -> Vec<VirtualHost>
+        where F: Fn(&Path, &mut Vec<DiscoveryError>) -> Result<Vec<VirtualHost>, DiscoveryError> {
+        let mut vhosts: Vec<VirtualHost> = Vec::new();
+        let mut visited_files: HashSet<PathBuf> = HashSet::new();
+
+        for vhosts_path in vhosts_paths {
+            debug!("configs path '{}'", vhosts_path.display());
+
+            if !vhosts_path.is_dir() {
+                debug!("configs path doesn't exist, skip");
+                continue;
+            }
 
-                    domain = None;
-                    port = None;
+            let vhost_files = match get_vhost_config_file_list(vhosts_path, scan_options, errors) {
+                Ok(vhost_files) => vhost_files,
+                Err(e) => {
+                    errors.push(e);
+                    continue;
                 }
+            };
 
-                if port.is_none() && port_search_pattern.is_match(&row) {
-                    let vhost_port_str = get_first_group_match_as_string(&row, &port_search_pattern);
-                    if let Ok(vhost_port) = vhost_port_str.parse() {
-                        debug!("port found {}", vhost_port);
-                        port = Some(vhost_port);
+            for vhost_file in vhost_files {
+                let canonical_path = fs::canonicalize(&vhost_file).unwrap_or_else(|_| vhost_file.to_path_buf());
 
-                    } else { error!("unable to parse port value '{}'", vhost_port_str); }
+                if !visited_files.insert(canonical_path) {
+                    debug!("file '{}' is already processed, skip", vhost_file.display());
+                    continue;
                 }
 
-                if domain.is_none() && domain_search_pattern.is_match(&row) {
-                    let domain_name = get_first_group_match_as_string(&row, &domain_search_pattern);
-                    debug!("domain found {}", domain_name);
-                    domain = Some(domain_name);
+                debug!("processing vhost file '{}'", vhost_file.display());
+
+                match get_vhosts_from_file(&vhost_file, errors) {
+                    Ok(file_vhosts) => {
+                        for vhost in file_vhosts {
+                            debug!("{}", vhost.to_string());
+                            vhosts.push(vhost);
+                        }
+                    }
+                    Err(e) => {
+                        debug!("unable to get virtual hosts from file: {}", e);
+                        errors.push(e);
+                    }
                 }
             }
+        }
 
+        vhosts
+    }
+
+    /// Returns unique server names without catch-all (`_`) and invalid ones.
+    /// Exact names go first, so wildcard or regex name becomes primary only if there are no exact names.
+    pub fn get_server_names(values: Vec<String>) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        let mut pattern_names: Vec<String> = Vec::new();
+
+        for name in values {
+            match get_name_kind(&name) {
+                NameKind::CatchAll => debug!("catch-all server name '{}' skipped", name),
+                NameKind::Invalid => debug!("invalid server name '{}' skipped", name),
+                NameKind::Exact => {
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+                NameKind::Wildcard | NameKind::Regex => {
+                    if !pattern_names.contains(&name) {
+                        pattern_names.push(name);
+                    }
+                }
+            }
         }
 
-        if port.is_some() && domain.is_some() && !redirect_to_url {
-            hosts.push(get_virtual_host(domain, port));
+        names.append(&mut pattern_names);
+        names
+    }
+
+    /// Returns redirect of all requests if any, partial redirects are only logged.
+    pub fn get_vhost_redirect(redirects: Vec<Redirect>) -> Option<Redirect> {
+        let mut vhost_redirect: Option<Redirect> = None;
+
+        for redirect in redirects {
+            if redirect.scope == RedirectScope::VirtualHost {
+                debug!("redirect detected: {}", redirect);
+
+                if vhost_redirect.is_none() {
+                    vhost_redirect = Some(redirect);
+                }
+
+            } else {
+                debug!("location redirect doesn't affect virtual host: {}", redirect);
+            }
         }
 
-        Ok(hosts)
+        vhost_redirect
     }
 
-    fn get_vhost_file_from_dir(vhost_root_path: &Path,
-                               dir_entry: &DirEntry) -> Option<PathBuf> {
-        let mut result: Option<PathBuf> = None;
+    pub fn is_redirect_url(target: &str) -> bool {
+        let target = target.to_lowercase();
+        REDIRECT_URL_PREFIXES.iter().any(|prefix| target.starts_with(prefix))
+    }
 
-        if let Ok(file_type) = dir_entry.file_type() {
-            if file_type.is_file() || file_type.is_symlink() {
-                if let Ok(file_name) = dir_entry.file_name().into_string() {
-                    if file_name.ends_with(VHOST_CONFIG_FILE_EXTENSION) {
-                        let vhost_file = vhost_root_path.join(file_name);
-                        result = Some(vhost_file)
+    /// Checks if rewrite or `RedirectMatch` pattern matches any request URI.
+    pub fn is_match_all_pattern(pattern: &str) -> bool {
+        MATCH_ALL_PATTERNS.contains(&pattern)
+    }
+
+    /// Marks listen endpoints as TLS ones according to vhost TLS directives.
+    /// Certificate without explicit `ssl`/`SSLEngine` means TLS for all ports except 80,
+    /// such directive is often defined on upper level or in included file.
+    pub fn apply_tls_config(listens: &mut [ListenEndpoint], tls: &TlsConfig) {
+        match tls.engine {
+            Some(true) => listens.iter_mut().for_each(|listen| listen.ssl = true),
+            Some(false) => {}
+            None => {
+                let explicit_ssl = listens.iter().any(|listen| listen.ssl);
+
+                if !tls.certificate_files.is_empty() && !explicit_ssl {
+                    for listen in listens.iter_mut().filter(|listen| listen.port != DEFAULT_HTTP_PORT) {
+                        debug!("certificate is configured, assume TLS for port {}", listen.port);
+                        listen.ssl = true;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Resolves relative certificate paths against config root: nginx prefix or apache `ServerRoot`,
+    /// the same way web servers do. Paths with variables are kept, they are reported as unsupported.
+    pub fn resolve_certificate_files(vhosts: &mut [VirtualHost], root: &Path) {
+        let files = vhosts.iter_mut().flat_map(|vhost| vhost.tls.certificate_files.iter_mut());
+
+        for file in files.filter(|file| is_supported_certificate_path(file) && Path::new(file.as_str()).is_relative()) {
+            let resolved_file = root.join(file.as_str()).display().to_string();
+            trace!("certificate path '{}' resolved to '{}'", file, resolved_file);
+            *file = resolved_file;
+        }
+    }
+
+    /// Config root of file from virtual hosts directory is parent of the directory,
+    /// i.e. `/etc/nginx` for `/etc/nginx/conf.d` and `/etc/apache2` for `/etc/apache2/sites-enabled`.
+    pub fn get_vhosts_config_root(vhosts_paths: &[PathBuf], vhost_file: &Path) -> PathBuf {
+        let vhosts_path = vhosts_paths.iter().find(|vhosts_path| vhost_file.starts_with(vhosts_path))
+            .map(|vhosts_path| vhosts_path.as_path())
+            .unwrap_or_else(|| vhost_file.parent().unwrap_or(Path::new("")));
+
+        vhosts_path.parent().unwrap_or(Path::new("")).to_path_buf()
+    }
+
+    /// Returns annotations of block at line: annotation comments right above the block line
+    /// and comment on the block line itself. Later values override earlier ones.
+    pub fn get_annotations(file: &Path, line: usize) -> BTreeMap<String, String> {
+        let mut annotations: BTreeMap<String, String> = BTreeMap::new();
+
+        let content = match fs::read_to_string(file) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("unable to read annotations from '{}': {}", file.display(), e);
+                return annotations
+            }
+        };
+
+        let lines: Vec<&str> = content.lines().collect();
+
+        if line == 0 || line > lines.len() {
+            return annotations
+        }
+
+        let comments_start = lines[..line - 1].iter()
+            .rposition(|row| !row.trim_start().starts_with('#'))
+            .map(|index| index + 1).unwrap_or(0);
+
+        let mut comments: Vec<&str> = lines[comments_start..line - 1].to_vec();
+
+        if let Some((_, comment)) = lines[line - 1].split_once('#') {
+            comments.push(comment);
+        }
+
+        for comment in comments {
+            annotations.extend(get_annotation_values(comment));
+        }
+
+        annotations
+    }
+
+    /// Parses `key=value` pairs of annotation comment, keys are lowercase.
+    pub fn get_annotation_values(comment: &str) -> Vec<(String, String)> {
+        let comment = comment.trim_start().trim_start_matches('#').trim();
+
+        match comment.strip_prefix(ANNOTATION_PREFIX) {
+            Some(values) => values.split_whitespace()
+                .filter_map(|item| item.split_once('='))
+                .filter(|(key, _)| !key.is_empty())
+                .map(|(key, value)| (key.to_lowercase(), value.to_string()))
+                .collect(),
+            None => Vec::new()
+        }
+    }
+
+    /// Parses port number, values out of 1-65535 range are invalid.
+    pub fn get_port(value: &str) -> Option<i32> {
+        value.parse::<i32>().ok().filter(|port| *port > 0 && *port <= 65535)
+    }
+
+    pub fn has_wildcards(pattern: &str) -> bool {
+        pattern.contains(&WILDCARD_CHARS[..])
+    }
+
+    /// Matches file name with glob(3) pattern: `*`, `?`, `[...]` character classes with ranges
+    /// and `!` or `^` negation, `\` escapes the next character. Unterminated `[` is matched as is.
+    pub fn is_glob_match(pattern: &str, file_name: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let file_name: Vec<char> = file_name.chars().collect();
+
+        is_glob_chars_match(&pattern, &file_name)
+    }
+
+    fn is_glob_chars_match(pattern: &[char], name: &[char]) -> bool {
+        match pattern.first() {
+            None => name.is_empty(),
+            Some('*') => (0..=name.len()).any(|index| is_glob_chars_match(&pattern[1..], &name[index..])),
+            Some('?') => !name.is_empty() && is_glob_chars_match(&pattern[1..], &name[1..]),
+            Some('[') => match get_glob_class(&pattern[1..]) {
+                Some(class) => match name.first() {
+                    Some(character) => {
+                        let in_class = class.ranges.iter().any(|(from, to)| from <= character && character <= to);
+                        in_class != class.negated && is_glob_chars_match(&pattern[1 + class.length..], &name[1..])
                     }
+                    None => false
+                },
+                None => name.first() == Some(&'[') && is_glob_chars_match(&pattern[1..], &name[1..])
+            },
+            Some('\\') if pattern.len() > 1 =>
+                name.first() == Some(&pattern[1]) && is_glob_chars_match(&pattern[2..], &name[1..]),
+            Some(character) => name.first() == Some(character) && is_glob_chars_match(&pattern[1..], &name[1..])
+        }
+    }
+
+    /// Character class of glob pattern, i.e. `[!a-c]`.
+    struct GlobClass {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+        /// Pattern characters after `[` including `]`
+        length: usize
+    }
+
+    /// Parses character class after `[`, `None` if class isn't terminated.
+    fn get_glob_class(pattern: &[char]) -> Option<GlobClass> {
+        let negated = matches!(pattern.first(), Some('!') | Some('^'));
+
+        let mut index = if negated { 1 } else { 0 };
+        let first_index = index;
+        let mut ranges: Vec<(char, char)> = Vec::new();
+
+        while let Some(character) = pattern.get(index) {
+            // `]` right after `[` or `[!` is a member of class
+            if *character == ']' && index > first_index {
+                return Some(GlobClass { negated, ranges, length: index + 1 })
+            }
+
+            match (pattern.get(index + 1), pattern.get(index + 2)) {
+                (Some('-'), Some(to)) if *to != ']' => {
+                    ranges.push((*character, *to));
+                    index += 3;
+                }
+                _ => {
+                    ranges.push((*character, *character));
+                    index += 1;
                 }
             }
         }
 
-        result
+        None
     }
 
-    fn get_first_group_match_as_string(row: &str, pattern: &Regex) -> String {
-        let groups = pattern.captures_iter(&row).next().unwrap();
-        String::from(&groups[1])
+    /// Expands include pattern like glob(3) does: wildcards are allowed in any path component,
+    /// hidden files are matched only by patterns starting with dot, results are sorted.
+    /// Pattern without wildcards is returned as is, even if file doesn't exist.
+    pub fn expand_include_pattern(pattern: &Path) -> Vec<PathBuf> {
+        if !has_wildcards(&pattern.to_string_lossy()) {
+            return vec![pattern.to_path_buf()]
+        }
+
+        let mut paths: Vec<PathBuf> = vec![PathBuf::new()];
+
+        for component in pattern.components() {
+            let component_value = component.as_os_str().to_string_lossy();
+
+            if has_wildcards(&component_value) {
+                let mut expanded_paths: Vec<PathBuf> = Vec::new();
+
+                for path in &paths {
+                    let mut file_names = get_matched_file_names(path, &component_value);
+                    file_names.sort();
+
+                    for file_name in file_names {
+                        expanded_paths.push(path.join(file_name));
+                    }
+                }
+
+                paths = expanded_paths;
+
+            } else {
+                paths = paths.iter().map(|path| path.join(component)).collect();
+            }
+        }
+
+        paths.into_iter().filter(|path| path.exists()).collect()
     }
 
-    fn get_virtual_host(domain: Option<String>, port: Option<i32>) -> VirtualHost {
-        let domain_name = domain.unwrap();
-        VirtualHost {
-            domain: domain_name.to_owned(), port: port.unwrap()
+    fn get_matched_file_names(dir: &Path, pattern: &str) -> Vec<String> {
+        let dir_path = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+
+        match fs::read_dir(dir_path) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|file_name| !file_name.starts_with('.') || pattern.starts_with('.'))
+                .filter(|file_name| is_glob_match(pattern, file_name))
+                .collect(),
+            Err(_) => Vec::new()
         }
     }
 }
@@ -1,13 +1,15 @@
 #[cfg(test)]
 mod webserver_tests {
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
 
-    use crate::webserver::webserver::get_vhost_config_file_list;
+    use crate::nginx::nginx::get_nginx_vhosts;
+    use crate::webserver::webserver::{ConfigScanOptions, expand_include_pattern, get_annotation_values,
+                                      get_vhost_config_file_list, is_glob_match};
 
     #[test]
     fn get_vhost_config_file_list_should_return_file_names() {
         let vhost_root_path = Path::new("tests/apache-vhosts");
-        let files = get_vhost_config_file_list(vhost_root_path).unwrap();
+        let files = get_vhost_config_file_list(vhost_root_path, &ConfigScanOptions::default(), &mut Vec::new()).unwrap();
 
         let expected_size: usize = 2;
         assert_eq!(&files.len(), &expected_size);
@@ -16,6 +18,110 @@ mod webserver_tests {
     #[test]
     fn get_vhost_config_file_list_should_return_error_for_unknown_path() {
         let unknown_path = Path::new("unknown-path");
-        assert!(get_vhost_config_file_list(unknown_path).is_err());
+        assert!(get_vhost_config_file_list(unknown_path, &ConfigScanOptions::default(), &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn get_vhost_config_file_list_should_not_recurse_by_default() {
+        let files = get_vhost_config_file_list(Path::new("tests/nginx-recursive"),
+                                               &ConfigScanOptions::default(), &mut Vec::new()).unwrap();
+
+        assert_eq!(files, vec![PathBuf::from("tests/nginx-recursive/site.conf")]);
+    }
+
+    #[test]
+    fn get_vhost_config_file_list_should_walk_subdirectories_once_per_file() {
+        let scan_options = ConfigScanOptions {
+            max_depth: 3,
+            include_patterns: vec!["*".to_string()],
+            exclude_patterns: vec!["*.bak".to_string()]
+        };
+
+        let mut errors = Vec::new();
+
+        let files = get_vhost_config_file_list(Path::new("tests/nginx-recursive"), &scan_options, &mut errors).unwrap();
+
+        let expected_files: Vec<PathBuf> = vec![
+            PathBuf::from("tests/nginx-recursive/site.conf"),
+            PathBuf::from("tests/nginx-recursive/project/app"),
+            PathBuf::from("tests/nginx-recursive/project/deep/deep.conf")
+        ];
+
+        assert_eq!(files, expected_files);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn get_vhost_config_file_list_should_respect_max_depth() {
+        let scan_options = ConfigScanOptions { max_depth: 2, ..ConfigScanOptions::default() };
+
+        let files = get_vhost_config_file_list(Path::new("tests/nginx-recursive"), &scan_options, &mut Vec::new()).unwrap();
+
+        assert_eq!(files, vec![PathBuf::from("tests/nginx-recursive/site.conf")]);
+    }
+
+    #[test]
+    fn file_from_several_roots_should_be_parsed_once() {
+        let vhosts_paths = vec![PathBuf::from("tests/nginx-recursive"), PathBuf::from("tests/nginx-recursive/enabled")];
+        let scan_options = ConfigScanOptions { include_patterns: vec!["*".to_string()], ..ConfigScanOptions::default() };
+
+        let vhosts = get_nginx_vhosts(&vhosts_paths, &scan_options, false, false, false, &mut Vec::new());
+
+        let domains: Vec<&str> = vhosts.iter().map(|vhost| vhost.domain.as_str()).collect();
+        assert_eq!(domains, vec!["site.company.ru"]);
+    }
+
+    #[test]
+    fn expand_include_pattern_should_return_sorted_matched_files() {
+        let files = expand_include_pattern(Path::new("tests/nginx-main/conf.d/*.conf"));
+
+        let expected_files: Vec<PathBuf> = vec![
+            PathBuf::from("tests/nginx-main/conf.d/one.conf"),
+            PathBuf::from("tests/nginx-main/conf.d/two.conf")
+        ];
+
+        assert_eq!(files, expected_files);
+    }
+
+    #[test]
+    fn expand_include_pattern_should_support_wildcards_in_directories() {
+        let files = expand_include_pattern(Path::new("tests/nginx-*/sites-enabled/*"));
+
+        assert_eq!(files, vec![PathBuf::from("tests/nginx-main/sites-enabled/three")]);
+    }
+
+    #[test]
+    fn expand_include_pattern_should_support_character_classes() {
+        let files = expand_include_pattern(Path::new("tests/nginx-main/conf.d/[!o]*.conf"));
+
+        assert_eq!(files, vec![PathBuf::from("tests/nginx-main/conf.d/two.conf")]);
+    }
+
+    #[test]
+    fn glob_pattern_should_match_like_glob3() {
+        assert!(is_glob_match("site[0-9].conf", "site1.conf"));
+        assert!(!is_glob_match("site[0-9].conf", "siteA.conf"));
+        assert!(is_glob_match("site[!a-c]?.conf", "sited1.conf"));
+        assert!(!is_glob_match("site[^a-c]?.conf", "siteb1.conf"));
+        assert!(is_glob_match("[]]*", "]x"));
+        assert!(is_glob_match("file[.conf", "file[.conf"));
+        assert!(is_glob_match("\\*.conf", "*.conf"));
+        assert!(!is_glob_match("\\*.conf", "a.conf"));
+        assert!(is_glob_match("*.c*f", "site.conf"));
+    }
+
+    #[test]
+    fn expand_include_pattern_should_return_path_without_wildcards_as_is() {
+        let path = Path::new("tests/unknown.conf");
+
+        assert_eq!(expand_include_pattern(path), vec![path.to_path_buf()]);
+    }
+
+    #[test]
+    fn annotation_values_should_be_parsed_from_comment() {
+        assert_eq!(get_annotation_values("# site-discovery-flea: env=production Owner=web-team broken"),
+                   vec![("env".to_string(), "production".to_string()), ("owner".to_string(), "web-team".to_string())]);
+
+        assert!(get_annotation_values("# env=production").is_empty());
     }
 }
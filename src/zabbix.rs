@@ -0,0 +1,425 @@
+pub mod zabbix {
+    use std::fmt;
+    use std::net::IpAddr;
+    use std::time::Duration;
+
+    use serde_json::{json, Value};
+    use ureq::AgentBuilder;
+
+    use crate::domain::domain::Site;
+    use crate::error::error::ZabbixApiError;
+
+    pub const DEFAULT_API_PATH: &str = "/api_jsonrpc.php";
+    pub const DEFAULT_API_TIMEOUT_SECONDS: u64 = 30;
+
+    /// Tag of web scenarios created by sync, scenarios without it are never changed.
+    pub const WEB_SCENARIO_TAG: &str = "source";
+    pub const WEB_SCENARIO_TAG_VALUE: &str = "site-discovery-flea";
+
+    const WEB_SCENARIO_DELAY: &str = "1m";
+    const WEB_SCENARIO_STEP_NAME: &str = "main page";
+    const DEFAULT_STATUS_CODES: &str = "200";
+
+    /// Trigger severity `High`
+    const TRIGGER_PRIORITY: i32 = 4;
+
+    const HTTP_SCHEME: &str = "http://";
+    const HTTPS_SCHEME: &str = "https://";
+    const LOCALHOST: &str = "localhost";
+
+    /// Zabbix frontend API endpoint, i.e. `https://zabbix.company.ru/api_jsonrpc.php`.
+    /// Token is sent with `Authorization: Bearer` header, Zabbix 6.4+.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct ZabbixApiSettings {
+        pub url: String,
+        pub token: String,
+        pub timeout: Duration
+    }
+
+    impl ZabbixApiSettings {
+        pub fn new(url: &str, token: &str) -> ZabbixApiSettings {
+            ZabbixApiSettings {
+                url: url.to_string(),
+                token: token.to_string(),
+                timeout: Duration::from_secs(DEFAULT_API_TIMEOUT_SECONDS)
+            }
+        }
+    }
+
+    /// Web scenario with single step which checks site url. Id is set for existing scenarios.
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct WebScenario {
+        pub id: Option<String>,
+        pub name: String,
+        pub url: String,
+        /// Comma separated expected status codes
+        pub status_codes: String,
+        pub follow_redirects: bool,
+        /// Scenario has `source=site-discovery-flea` tag, other scenarios are never changed
+        pub managed: bool,
+        /// Trigger on `web.test.fail` item of scenario exists, for existing scenarios
+        pub has_trigger: bool
+    }
+
+    impl WebScenario {
+        /// Scenario for site, redirect sites expect redirect code without following it.
+        pub fn new(site: &Site) -> WebScenario {
+            match &site.redirect_code {
+                Some(redirect_code) => WebScenario {
+                    id: None, name: site.name.to_string(), url: site.url.to_string(),
+                    status_codes: redirect_code.to_string(), follow_redirects: false,
+                    managed: true, has_trigger: false
+                },
+                None => WebScenario {
+                    id: None, name: site.name.to_string(), url: site.url.to_string(),
+                    status_codes: DEFAULT_STATUS_CODES.to_string(), follow_redirects: true,
+                    managed: true, has_trigger: false
+                }
+            }
+        }
+
+        fn is_same_check(&self, other: &WebScenario) -> bool {
+            self.url == other.url && self.status_codes == other.status_codes &&
+            self.follow_redirects == other.follow_redirects
+        }
+
+        fn get_steps(&self) -> Value {
+            json!([{
+                "no": 1,
+                "name": WEB_SCENARIO_STEP_NAME,
+                "url": self.url,
+                "status_codes": self.status_codes,
+                "follow_redirects": if self.follow_redirects { 1 } else { 0 }
+            }])
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum SyncAction {
+        Create(WebScenario),
+        /// Current and desired scenario
+        Update(WebScenario, WebScenario),
+        Delete(WebScenario),
+        /// Trigger of existing managed scenario is missing, i.e. its creation failed on previous sync
+        CreateTrigger(WebScenario),
+        /// Scenario with the same name exists without sync tag, it's left as is
+        Skip(WebScenario)
+    }
+
+    /// Diff line: `+ name url`, `~ name old url -> new url`, `- name url`, `+ trigger name`, `! name url`.
+    impl fmt::Display for SyncAction {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                SyncAction::Create(scenario) =>
+                    write!(f, "+ {} {} [{}]", scenario.name, scenario.url, scenario.status_codes),
+                SyncAction::Update(current, desired) =>
+                    write!(f, "~ {} {} [{}] -> {} [{}]", desired.name, current.url, current.status_codes,
+                             desired.url, desired.status_codes),
+                SyncAction::Delete(scenario) =>
+                    write!(f, "- {} {} [{}]", scenario.name, scenario.url, scenario.status_codes),
+                SyncAction::CreateTrigger(scenario) =>
+                    write!(f, "+ trigger {}", scenario.name),
+                SyncAction::Skip(scenario) =>
+                    write!(f, "! {} {} [{}] skipped, scenario without '{}={}' tag exists", scenario.name, scenario.url,
+                             scenario.status_codes, WEB_SCENARIO_TAG, WEB_SCENARIO_TAG_VALUE)
+            }
+        }
+    }
+
+    /// Zabbix JSON-RPC API client, https or plain http to loopback address.
+    pub struct ZabbixApi {
+        settings: ZabbixApiSettings
+    }
+
+    impl ZabbixApi {
+        pub fn new(settings: ZabbixApiSettings) -> ZabbixApi {
+            ZabbixApi { settings }
+        }
+
+        /// Calls API method, returns `result` property.
+        pub fn call(&self, method: &str, params: Value) -> Result<Value, ZabbixApiError> {
+            debug!("call zabbix api method '{}'", method);
+
+            let request = json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1});
+            let request = serde_json::to_string(&request).unwrap();
+            trace!("zabbix api request: '{}'", request);
+
+            let response = post_json(&self.settings, &request)?;
+            trace!("zabbix api response: '{}'", response);
+
+            let response: Value = serde_json::from_str(&response)
+                .map_err(|e| ZabbixApiError::InvalidResponse { message: e.to_string() })?;
+
+            if let Some(error) = response.get("error") {
+                return Err(ZabbixApiError::Api {
+                    method: method.to_string(),
+                    code: error["code"].as_i64().unwrap_or_default(),
+                    message: error["message"].as_str().unwrap_or_default().to_string(),
+                    data: error["data"].as_str().unwrap_or_default().to_string()
+                })
+            }
+
+            response.get("result").cloned()
+                .ok_or_else(|| ZabbixApiError::InvalidResponse { message: "missing 'result' property".to_string() })
+        }
+    }
+
+    /// Scenario per site name, the first site is used for repeated names.
+    pub fn get_web_scenarios(sites: &[Site]) -> Vec<WebScenario> {
+        let mut scenarios: Vec<WebScenario> = Vec::new();
+
+        for site in sites {
+            if scenarios.iter().all(|scenario| scenario.name != site.name) {
+                scenarios.push(WebScenario::new(site));
+            }
+        }
+
+        scenarios
+    }
+
+    /// Actions to turn current scenarios of host into desired ones, scenarios are matched by name.
+    /// Unmanaged scenarios are never changed, desired scenario with the same name is skipped.
+    /// Missing triggers of managed scenarios are created, so failed trigger creation is repaired on next sync.
+    pub fn get_sync_actions(current: &[WebScenario], desired: &[WebScenario]) -> Vec<SyncAction> {
+        let mut actions: Vec<SyncAction> = Vec::new();
+
+        for scenario in desired {
+            match current.iter().find(|current_scenario| current_scenario.name == scenario.name) {
+                Some(current_scenario) if !current_scenario.managed => {
+                    actions.push(SyncAction::Skip(scenario.clone()));
+                }
+                Some(current_scenario) => {
+                    let mut updated_scenario = scenario.clone();
+                    updated_scenario.id = current_scenario.id.clone();
+                    updated_scenario.has_trigger = current_scenario.has_trigger;
+
+                    if !current_scenario.is_same_check(scenario) {
+                        actions.push(SyncAction::Update(current_scenario.clone(), updated_scenario.clone()));
+                    }
+
+                    if !current_scenario.has_trigger {
+                        actions.push(SyncAction::CreateTrigger(updated_scenario));
+                    }
+                }
+                None => actions.push(SyncAction::Create(scenario.clone()))
+            }
+        }
+
+        for scenario in current.iter().filter(|scenario| scenario.managed) {
+            if desired.iter().all(|desired_scenario| desired_scenario.name != scenario.name) {
+                actions.push(SyncAction::Delete(scenario.clone()));
+            }
+        }
+
+        actions
+    }
+
+    /// Reconciles web scenarios of host with sites: creates scenario with trigger for new site,
+    /// updates changed url or status codes, creates missing triggers and deletes scenarios of missing sites.
+    /// Only scenarios with `source=site-discovery-flea` tag are managed. Nothing is changed in dry run.
+    pub fn sync_web_scenarios(api: &ZabbixApi, host: &str, sites: &[Site],
+                              dry_run: bool) -> Result<Vec<SyncAction>, ZabbixApiError> {
+        let host_id = get_host_id(api, host)?;
+
+        let current = get_host_web_scenarios(api, &host_id)?;
+        let desired = get_web_scenarios(sites);
+
+        let actions = get_sync_actions(&current, &desired);
+
+        info!("web scenarios of host '{}': current {}, desired {}, actions {}", host,
+              current.iter().filter(|scenario| scenario.managed).count(), desired.len(), actions.len());
+
+        if dry_run {
+            return Ok(actions)
+        }
+
+        for action in &actions {
+            info!("{}", action);
+
+            match action {
+                SyncAction::Create(scenario) => {
+                    api.call("httptest.create", json!({
+                        "name": scenario.name,
+                        "hostid": host_id,
+                        "delay": WEB_SCENARIO_DELAY,
+                        "tags": [{"tag": WEB_SCENARIO_TAG, "value": WEB_SCENARIO_TAG_VALUE}],
+                        "steps": scenario.get_steps()
+                    }))?;
+
+                    create_trigger(api, host, scenario)?;
+                }
+                SyncAction::CreateTrigger(scenario) => {
+                    create_trigger(api, host, scenario)?;
+                }
+                SyncAction::Skip(scenario) => {
+                    warn!("web scenario '{}' of host '{}' isn't managed by sync, remove it or add '{}={}' tag",
+                          scenario.name, host, WEB_SCENARIO_TAG, WEB_SCENARIO_TAG_VALUE);
+                }
+                SyncAction::Update(_, scenario) => {
+                    api.call("httptest.update", json!({
+                        "httptestid": scenario.id,
+                        "steps": scenario.get_steps()
+                    }))?;
+                }
+                SyncAction::Delete(scenario) => {
+                    api.call("httptest.delete", json!([scenario.id]))?;
+                }
+            }
+        }
+
+        Ok(actions)
+    }
+
+    /// Trigger fires when any scenario step fails, `web.test.fail` item is created with scenario.
+    pub fn get_trigger_expression(host: &str, scenario_name: &str) -> String {
+        format!("last(/{}/{})<>0", host, get_fail_item_key(scenario_name))
+    }
+
+    fn get_fail_item_key(scenario_name: &str) -> String {
+        format!("web.test.fail[{}]", get_quoted_key_parameter(scenario_name, false))
+    }
+
+    /// Item key parameter as Zabbix `quoteItemKeyParam` makes it: quoted only if it starts with quote
+    /// or contains `,` or `]`, unless quotes are forced.
+    fn get_quoted_key_parameter(value: &str, forced: bool) -> String {
+        if !forced && !value.starts_with('"') && !value.contains([',', ']']) {
+            return value.to_string()
+        }
+
+        format!("\"{}\"", value.replace('"', "\\\""))
+    }
+
+    fn create_trigger(api: &ZabbixApi, host: &str, scenario: &WebScenario) -> Result<(), ZabbixApiError> {
+        api.call("trigger.create", json!({
+            "description": format!("Site {} is unavailable", scenario.name),
+            "expression": get_trigger_expression(host, &scenario.name),
+            "priority": TRIGGER_PRIORITY
+        }))?;
+
+        Ok(())
+    }
+
+    fn get_host_id(api: &ZabbixApi, host: &str) -> Result<String, ZabbixApiError> {
+        let hosts = api.call("host.get", json!({"output": ["hostid"], "filter": {"host": [host]}}))?;
+
+        hosts.get(0).and_then(|item| item["hostid"].as_str()).map(|host_id| host_id.to_string())
+            .ok_or_else(|| ZabbixApiError::HostNotFound { host: host.to_string() })
+    }
+
+    /// All web scenarios of host, managed ones have sync tag. Trigger presence is checked
+    /// by `web.test.fail` item key in expanded trigger expressions of host.
+    fn get_host_web_scenarios(api: &ZabbixApi, host_id: &str) -> Result<Vec<WebScenario>, ZabbixApiError> {
+        let scenarios = api.call("httptest.get", json!({
+            "output": ["httptestid", "name"],
+            "hostids": [host_id],
+            "selectSteps": ["no", "url", "status_codes", "follow_redirects"],
+            "selectTags": ["tag", "value"]
+        }))?;
+
+        let scenarios = scenarios.as_array()
+            .ok_or_else(|| ZabbixApiError::InvalidResponse { message: "web scenarios array expected".to_string() })?;
+
+        let triggers = api.call("trigger.get", json!({
+            "output": ["triggerid", "expression"],
+            "hostids": [host_id],
+            "expandExpression": true
+        }))?;
+
+        let expressions: Vec<&str> = triggers.as_array()
+            .ok_or_else(|| ZabbixApiError::InvalidResponse { message: "triggers array expected".to_string() })?
+            .iter().filter_map(|trigger| trigger["expression"].as_str()).collect();
+
+        Ok(scenarios.iter().map(|scenario| {
+            let step = &scenario["steps"][0];
+            let name = scenario["name"].as_str().unwrap_or_default().to_string();
+
+            let managed = scenario["tags"].as_array().map(|tags| tags.iter().any(|tag| {
+                tag["tag"] == WEB_SCENARIO_TAG && tag["value"] == WEB_SCENARIO_TAG_VALUE
+            })).unwrap_or(false);
+
+            // key parameter may be quoted even if Zabbix doesn't require it
+            let item_keys = [get_fail_item_key(&name),
+                             format!("web.test.fail[{}]", get_quoted_key_parameter(&name, true))];
+            let has_trigger = expressions.iter()
+                .any(|expression| item_keys.iter().any(|item_key| expression.contains(item_key.as_str())));
+
+            WebScenario {
+                id: scenario["httptestid"].as_str().map(|id| id.to_string()),
+                name,
+                url: step["url"].as_str().unwrap_or_default().to_string(),
+                status_codes: step["status_codes"].as_str().unwrap_or_default().to_string(),
+                follow_redirects: step["follow_redirects"].as_str() != Some("0"),
+                managed,
+                has_trigger
+            }
+        }).collect())
+    }
+
+    /// Full API url, frontend address without path gets `DEFAULT_API_PATH`.
+    /// Token is sent with every request, so plain http is allowed for loopback addresses only.
+    pub fn get_api_url(url: &str) -> Result<String, ZabbixApiError> {
+        let invalid_url = |message: &str| ZabbixApiError::InvalidUrl {
+            url: url.to_string(), message: message.to_string()
+        };
+
+        let (plain_http, address) = match (url.strip_prefix(HTTP_SCHEME), url.strip_prefix(HTTPS_SCHEME)) {
+            (Some(address), _) => (true, address),
+            (_, Some(address)) => (false, address),
+            _ => return Err(invalid_url("only http and https urls are supported"))
+        };
+
+        let authority = address.split('/').next().unwrap_or_default();
+
+        let host = match authority.strip_prefix('[') {
+            Some(ipv6_authority) => ipv6_authority.split(']').next().unwrap_or_default(),
+            None => authority.split(':').next().unwrap_or_default()
+        };
+
+        if host.is_empty() {
+            return Err(invalid_url("missing host"))
+        }
+
+        if plain_http && !is_loopback_host(host) {
+            return Err(invalid_url("api token can't be sent over plain http, use https"))
+        }
+
+        match address.find('/') {
+            Some(_) => Ok(url.to_string()),
+            None => Ok(format!("{}{}", url, DEFAULT_API_PATH))
+        }
+    }
+
+    fn is_loopback_host(host: &str) -> bool {
+        host.eq_ignore_ascii_case(LOCALHOST) ||
+        host.parse::<IpAddr>().map(|address| address.is_loopback()).unwrap_or(false)
+    }
+
+    /// Sends POST request with JSON body and returns response body. Redirects aren't followed
+    /// to keep token within configured frontend.
+    fn post_json(settings: &ZabbixApiSettings, body: &str) -> Result<String, ZabbixApiError> {
+        let url = get_api_url(&settings.url)?;
+
+        let agent = AgentBuilder::new()
+            .timeout(settings.timeout)
+            .redirects(0)
+            .max_idle_connections(0)
+            .build();
+
+        let response = agent.post(&url)
+            .set("Content-Type", "application/json-rpc")
+            .set("Authorization", &format!("Bearer {}", settings.token))
+            .send_string(body)
+            .map_err(|e| match e {
+                ureq::Error::Status(status, _) => ZabbixApiError::HttpStatus { url: url.to_string(), status },
+                ureq::Error::Transport(transport) => ZabbixApiError::Connection {
+                    url: url.to_string(), message: transport.to_string()
+                }
+            })?;
+
+        if response.status() != 200 {
+            return Err(ZabbixApiError::HttpStatus { url: url.to_string(), status: response.status() })
+        }
+
+        response.into_string().map_err(|e| ZabbixApiError::InvalidResponse { message: e.to_string() })
+    }
+}
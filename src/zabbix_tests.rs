@@ -0,0 +1,359 @@
+#[cfg(test)]
+mod zabbix_tests {
+    use std::collections::HashMap;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+    use std::thread::JoinHandle;
+
+    use serde_json::{json, Value};
+
+    use crate::{DEFAULT_HTTP_PORT, DEFAULT_HTTPS_PORT};
+    use crate::domain::domain::{Redirect, RedirectScope, Site, VirtualHost};
+    use crate::error::error::ZabbixApiError;
+    use crate::site::site::{get_sites_from_vhosts, SiteFormat};
+    use crate::zabbix::zabbix::{get_api_url, get_sync_actions, get_trigger_expression, get_web_scenarios,
+                                sync_web_scenarios, SyncAction, WebScenario, ZabbixApi, ZabbixApiSettings};
+
+    const API_TOKEN: &str = "secret-token";
+
+    /// Method, params and authorization header of received request.
+    type ApiRequest = (String, Value, String);
+
+    /// Fake JSON-RPC server, answers given number of requests with result per method.
+    fn start_mock_api(results: HashMap<&'static str, Value>, requests: usize) -> (String, JoinHandle<Vec<ApiRequest>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://127.0.0.1:{}/api_jsonrpc.php", listener.local_addr().unwrap().port());
+
+        let handle = thread::spawn(move || {
+            let mut received: Vec<ApiRequest> = Vec::new();
+
+            for _ in 0..requests {
+                let (mut stream, _) = listener.accept().unwrap();
+                let (authorization, body) = read_http_request(&mut stream);
+
+                let request: Value = serde_json::from_str(&body).unwrap();
+                let method = request["method"].as_str().unwrap().to_string();
+
+                let response = match results.get(method.as_str()) {
+                    Some(result) => json!({"jsonrpc": "2.0", "result": result, "id": request["id"]}),
+                    None => json!({"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found.",
+                                   "data": "Incorrect API"}, "id": request["id"]})
+                };
+
+                write_chunked_response(&mut stream, &serde_json::to_string(&response).unwrap());
+
+                received.push((method, request["params"].clone(), authorization));
+            }
+
+            received
+        });
+
+        (url, handle)
+    }
+
+    fn read_http_request(stream: &mut TcpStream) -> (String, String) {
+        let mut request: Vec<u8> = Vec::new();
+        let mut buffer = [0u8; 1024];
+
+        loop {
+            let size = stream.read(&mut buffer).unwrap();
+            request.extend_from_slice(&buffer[..size]);
+
+            let text = String::from_utf8_lossy(&request).to_string();
+
+            if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                let header_value = |name: &str| head.lines()
+                    .find_map(|line| line.strip_prefix(name).map(|value| value.trim().to_string()))
+                    .unwrap_or_default();
+
+                let content_length: usize = header_value("Content-Length:").parse().unwrap();
+
+                if body.len() >= content_length {
+                    return (header_value("Authorization:"), body.to_string())
+                }
+            }
+        }
+    }
+
+    fn write_chunked_response(stream: &mut TcpStream, body: &str) {
+        let (first, second) = body.split_at(body.len() / 2);
+
+        let response = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n\
+                                {:x}\r\n{}\r\n{:x}\r\n{}\r\n0\r\n\r\n", first.len(), first, second.len(), second);
+
+        stream.write_all(response.as_bytes()).unwrap();
+    }
+
+    fn get_sites() -> Vec<Site> {
+        let mut redirect_vhost = VirtualHost::new("old.company.ru", DEFAULT_HTTP_PORT);
+        redirect_vhost.redirect = Some(Redirect {
+            code: 301, target: "https://app.company.ru".to_string(), scope: RedirectScope::VirtualHost
+        });
+
+        let vhosts = vec![VirtualHost::new("app.company.ru", DEFAULT_HTTPS_PORT), redirect_vhost];
+
+        get_sites_from_vhosts(vhosts, false, false, &HashMap::new(), &SiteFormat::default())
+    }
+
+    fn get_scenario(id: &str, name: &str, url: &str) -> WebScenario {
+        WebScenario {
+            id: Some(id.to_string()), name: name.to_string(), url: url.to_string(),
+            status_codes: "200".to_string(), follow_redirects: true, managed: true, has_trigger: true
+        }
+    }
+
+    fn get_scenario_json(id: &str, name: &str, url: &str, managed: bool) -> Value {
+        let tags = if managed { json!([{"tag": "source", "value": "site-discovery-flea"}]) } else { json!([]) };
+
+        json!({
+            "httptestid": id, "name": name, "tags": tags,
+            "steps": [{"no": "1", "url": url, "status_codes": "200", "follow_redirects": "1"}]
+        })
+    }
+
+    fn get_trigger_json(host: &str, scenario_name: &str) -> Value {
+        json!({"triggerid": "30", "expression": get_trigger_expression(host, scenario_name)})
+    }
+
+    #[test]
+    fn redirect_site_scenario_should_expect_redirect_code() {
+        let scenarios = get_web_scenarios(&get_sites());
+
+        assert_eq!(scenarios.len(), 2);
+
+        assert_eq!(scenarios[0].url, "https://app.company.ru");
+        assert_eq!(scenarios[0].status_codes, "200");
+        assert!(scenarios[0].follow_redirects);
+
+        assert_eq!(scenarios[1].url, "http://old.company.ru");
+        assert_eq!(scenarios[1].status_codes, "301");
+        assert!(!scenarios[1].follow_redirects);
+    }
+
+    #[test]
+    fn sync_actions_should_contain_created_updated_and_deleted_scenarios() {
+        let current = vec![
+            get_scenario("1", "app.company.ru", "https://app.company.ru"),
+            get_scenario("2", "old.company.ru_http", "http://old.company.ru"),
+            get_scenario("3", "gone.company.ru", "https://gone.company.ru")
+        ];
+
+        let mut desired = get_web_scenarios(&get_sites());
+        desired.push(get_scenario("", "new.company.ru", "https://new.company.ru"));
+        desired[2].id = None;
+
+        let actions = get_sync_actions(&current, &desired);
+
+        assert!(matches!(&actions[0], SyncAction::Update(_, scenario) if scenario.id == Some("2".to_string())));
+
+        let lines: Vec<String> = actions.iter().map(|action| action.to_string()).collect();
+
+        assert_eq!(lines, vec![
+            "~ old.company.ru_http http://old.company.ru [200] -> http://old.company.ru [301]".to_string(),
+            "+ new.company.ru https://new.company.ru [200]".to_string(),
+            "- gone.company.ru https://gone.company.ru [200]".to_string()
+        ]);
+    }
+
+    #[test]
+    fn dry_run_should_not_change_web_scenarios() {
+        let mut results: HashMap<&str, Value> = HashMap::new();
+        results.insert("host.get", json!([{"hostid": "10084"}]));
+        results.insert("httptest.get", json!([get_scenario_json("7", "app.company.ru", "https://app.company.ru", true)]));
+        results.insert("trigger.get", json!([get_trigger_json("web-1", "app.company.ru")]));
+
+        let (url, handle) = start_mock_api(results, 3);
+
+        let api = ZabbixApi::new(ZabbixApiSettings::new(&url, API_TOKEN));
+        let actions = sync_web_scenarios(&api, "web-1", &get_sites(), true).unwrap();
+
+        assert_eq!(actions.len(), 1);
+        assert!(matches!(&actions[0], SyncAction::Create(scenario) if scenario.name == "old.company.ru_http"));
+
+        let requests = handle.join().unwrap();
+        let methods: Vec<&str> = requests.iter().map(|(method, _, _)| method.as_str()).collect();
+
+        assert_eq!(methods, vec!["host.get", "httptest.get", "trigger.get"]);
+        assert_eq!(requests[0].1["filter"]["host"][0], "web-1");
+        assert_eq!(requests[0].2, format!("Bearer {}", API_TOKEN));
+        assert_eq!(requests[2].1["expandExpression"], true);
+    }
+
+    #[test]
+    fn sync_should_create_scenarios_with_triggers_and_delete_stale_ones() {
+        let mut results: HashMap<&str, Value> = HashMap::new();
+        results.insert("host.get", json!([{"hostid": "10084"}]));
+        results.insert("httptest.get", json!([get_scenario_json("9", "gone.company.ru", "https://gone.company.ru", true)]));
+        results.insert("trigger.get", json!([get_trigger_json("web-1", "gone.company.ru")]));
+        results.insert("httptest.create", json!({"httptestids": ["10"]}));
+        results.insert("trigger.create", json!({"triggerids": ["20"]}));
+        results.insert("httptest.delete", json!({"httptestids": ["9"]}));
+
+        let (url, handle) = start_mock_api(results, 8);
+
+        let api = ZabbixApi::new(ZabbixApiSettings::new(&url, API_TOKEN));
+        let actions = sync_web_scenarios(&api, "web-1", &get_sites(), false).unwrap();
+
+        assert_eq!(actions.len(), 3);
+
+        let requests = handle.join().unwrap();
+        let methods: Vec<&str> = requests.iter().map(|(method, _, _)| method.as_str()).collect();
+
+        assert_eq!(methods, vec!["host.get", "httptest.get", "trigger.get", "httptest.create", "trigger.create",
+                                 "httptest.create", "trigger.create", "httptest.delete"]);
+
+        let create_params = &requests[3].1;
+        assert_eq!(create_params["name"], "app.company.ru");
+        assert_eq!(create_params["hostid"], "10084");
+        assert_eq!(create_params["steps"][0]["url"], "https://app.company.ru");
+
+        assert_eq!(requests[4].1["expression"], "last(/web-1/web.test.fail[app.company.ru])<>0");
+        assert_eq!(requests[7].1, json!(["9"]));
+    }
+
+    #[test]
+    fn sync_should_create_missing_trigger_of_existing_scenario() {
+        let mut results: HashMap<&str, Value> = HashMap::new();
+        results.insert("host.get", json!([{"hostid": "10084"}]));
+        results.insert("httptest.get", json!([get_scenario_json("7", "app.company.ru", "https://app.company.ru", true)]));
+        results.insert("trigger.get", json!([get_trigger_json("web-1", "other.company.ru")]));
+        results.insert("httptest.create", json!({"httptestids": ["10"]}));
+        results.insert("trigger.create", json!({"triggerids": ["20"]}));
+
+        let (url, handle) = start_mock_api(results, 6);
+
+        let api = ZabbixApi::new(ZabbixApiSettings::new(&url, API_TOKEN));
+        let actions = sync_web_scenarios(&api, "web-1", &get_sites(), false).unwrap();
+
+        let lines: Vec<String> = actions.iter().map(|action| action.to_string()).collect();
+        assert_eq!(lines, vec![
+            "+ trigger app.company.ru".to_string(),
+            "+ old.company.ru_http http://old.company.ru [301]".to_string()
+        ]);
+
+        let requests = handle.join().unwrap();
+        let methods: Vec<&str> = requests.iter().map(|(method, _, _)| method.as_str()).collect();
+
+        assert_eq!(methods, vec!["host.get", "httptest.get", "trigger.get", "trigger.create",
+                                 "httptest.create", "trigger.create"]);
+        assert_eq!(requests[3].1["expression"], "last(/web-1/web.test.fail[app.company.ru])<>0");
+    }
+
+    #[test]
+    fn sync_should_skip_unmanaged_scenario_with_same_name() {
+        let mut results: HashMap<&str, Value> = HashMap::new();
+        results.insert("host.get", json!([{"hostid": "10084"}]));
+        results.insert("httptest.get", json!([
+            get_scenario_json("7", "app.company.ru", "https://app.company.ru/health", false),
+            get_scenario_json("8", "manual.company.ru", "https://manual.company.ru", false)
+        ]));
+        results.insert("trigger.get", json!([]));
+        results.insert("httptest.create", json!({"httptestids": ["10"]}));
+        results.insert("trigger.create", json!({"triggerids": ["20"]}));
+
+        let (url, handle) = start_mock_api(results, 5);
+
+        let api = ZabbixApi::new(ZabbixApiSettings::new(&url, API_TOKEN));
+        let actions = sync_web_scenarios(&api, "web-1", &get_sites(), false).unwrap();
+
+        assert_eq!(actions.len(), 2);
+        assert!(matches!(&actions[0], SyncAction::Skip(scenario) if scenario.name == "app.company.ru"));
+        assert!(matches!(&actions[1], SyncAction::Create(scenario) if scenario.name == "old.company.ru_http"));
+
+        let requests = handle.join().unwrap();
+        let methods: Vec<&str> = requests.iter().map(|(method, _, _)| method.as_str()).collect();
+
+        // unmanaged scenarios are neither updated nor deleted
+        assert_eq!(methods, vec!["host.get", "httptest.get", "trigger.get", "httptest.create", "trigger.create"]);
+        assert_eq!(requests[3].1["name"], "old.company.ru_http");
+    }
+
+    #[test]
+    fn unknown_host_should_be_returned_as_error() {
+        let mut results: HashMap<&str, Value> = HashMap::new();
+        results.insert("host.get", json!([]));
+
+        let (url, handle) = start_mock_api(results, 1);
+
+        let api = ZabbixApi::new(ZabbixApiSettings::new(&url, API_TOKEN));
+
+        assert_eq!(sync_web_scenarios(&api, "web-2", &get_sites(), true),
+                   Err(ZabbixApiError::HostNotFound { host: "web-2".to_string() }));
+
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn api_error_should_contain_method_and_message() {
+        let (url, handle) = start_mock_api(HashMap::new(), 1);
+
+        let api = ZabbixApi::new(ZabbixApiSettings::new(&url, API_TOKEN));
+
+        match api.call("host.get", json!({})) {
+            Err(ZabbixApiError::Api { method, code, message, .. }) => {
+                assert_eq!(method, "host.get");
+                assert_eq!(code, -32601);
+                assert_eq!(message, "Method not found.");
+            }
+            result => panic!("api error expected, got {:?}", result)
+        }
+
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn plain_http_api_url_should_be_invalid_for_remote_frontend() {
+        let api = ZabbixApi::new(ZabbixApiSettings::new("http://zabbix.company.ru/api_jsonrpc.php", API_TOKEN));
+
+        assert!(matches!(api.call("host.get", json!({})), Err(ZabbixApiError::InvalidUrl { .. })));
+
+        assert!(matches!(get_api_url("ftp://zabbix.company.ru"), Err(ZabbixApiError::InvalidUrl { .. })));
+        assert!(matches!(get_api_url("https://"), Err(ZabbixApiError::InvalidUrl { .. })));
+    }
+
+    #[test]
+    fn api_url_should_allow_https_and_loopback_http() {
+        assert_eq!(get_api_url("https://zabbix.company.ru").unwrap(), "https://zabbix.company.ru/api_jsonrpc.php");
+        assert_eq!(get_api_url("https://zabbix.company.ru/zabbix/api_jsonrpc.php").unwrap(),
+                   "https://zabbix.company.ru/zabbix/api_jsonrpc.php");
+
+        assert!(get_api_url("http://localhost:8080/api_jsonrpc.php").is_ok());
+        assert!(get_api_url("http://127.0.0.1/api_jsonrpc.php").is_ok());
+        assert!(get_api_url("http://[::1]:8080/api_jsonrpc.php").is_ok());
+    }
+
+    #[test]
+    fn trigger_expression_should_quote_scenario_name_like_zabbix() {
+        assert_eq!(get_trigger_expression("web-1", "app.company.ru:8080"),
+                   "last(/web-1/web.test.fail[app.company.ru:8080])<>0");
+
+        assert_eq!(get_trigger_expression("web-1", "app.company.ru,api"),
+                   "last(/web-1/web.test.fail[\"app.company.ru,api\"])<>0");
+        assert_eq!(get_trigger_expression("web-1", "\"app\"]"),
+                   "last(/web-1/web.test.fail[\"\\\"app\\\"]\"])<>0");
+    }
+
+    #[test]
+    fn existing_trigger_should_be_found_by_plain_and_quoted_item_key() {
+        let mut results: HashMap<&str, Value> = HashMap::new();
+        results.insert("host.get", json!([{"hostid": "10084"}]));
+        results.insert("httptest.get", json!([
+            get_scenario_json("7", "app.company.ru", "https://app.company.ru", true),
+            get_scenario_json("8", "old.company.ru_http", "http://old.company.ru", true)
+        ]));
+        results.insert("trigger.get", json!([
+            get_trigger_json("web-1", "app.company.ru"),
+            {"triggerid": "31", "expression": "last(/web-1/web.test.fail[\"old.company.ru_http\"])<>0"}
+        ]));
+
+        let (url, handle) = start_mock_api(results, 3);
+
+        let api = ZabbixApi::new(ZabbixApiSettings::new(&url, API_TOKEN));
+        let actions = sync_web_scenarios(&api, "web-1", &get_sites(), true).unwrap();
+
+        assert!(actions.iter().all(|action| !matches!(action, SyncAction::CreateTrigger(_))));
+
+        handle.join().unwrap();
+    }
+}